@@ -0,0 +1,395 @@
+use num;
+use num::traits::Float;
+
+use traits::{
+  ModulatedDelayLine,
+  Processor,
+  TappableDelayLine
+};
+
+/* Notes on the all-pass interpolation
+  - The fractional part of the delay, `alpha`, is a first-order Thiran
+    all-pass filter, `(c + z^-1) / (1 + c*z^-1)` with `c = (1 - alpha) /
+    (1 + alpha)`, whose group delay at DC is `alpha`
+  - The all-pass has a unity gain at every frequency, so unlike linear
+    interpolation it does not low-pass the signal, which matters in feedback
+    loops, where the loss compounds on each pass
+  - `alpha` is kept between 0.5 and 1.5, where the group delay is flattest,
+    by moving a sample of the delay to the integer part; the delay must then
+    be at least 0.5 samples
+  - The state of the all-pass belongs to its old coefficient when the delay
+    changes, which causes a transient, so the state is recomputed by running
+    the new filter over the samples before the new read position
+  - A modulated delay changes the coefficient every sample, which only
+    causes a small transient while the read position moves on by one sample,
+    so the state is only recomputed when the read position moves otherwise
+*/
+
+/// The number of samples the all-pass filter runs over when the delay
+/// changes.
+const WARM_UP: usize = 32;
+
+/// A time-varying, all-pass interpolating delay line.
+pub struct AllpassDelay<T> {
+  memory: Vec<T>,
+  output: T,
+  read_ptr: usize,
+  write_ptr: usize,
+  /// Delay time as a number of samples, which must be less than or equal to
+  /// the size of the delay internal memory.
+  delay: f32,
+  do_next_out: bool,
+  next_out: T,
+  /// All-pass coefficient
+  coefficient: T,
+  /// All-pass state, the previous output; the previous input is the sample
+  /// before the read position
+  ap_output: T
+}
+
+impl<T> AllpassDelay<T> where T: Float {
+  /// Creates a delay line.
+  ///
+  /// Both `delay` and `max_delay` are represented in samples. The `delay`
+  /// value will be clipped if it is greater than `max_delay`, or less than
+  /// 0.5.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # #![allow(unused_mut)]
+  /// use rasp::delay::AllpassDelay;
+  ///
+  /// let sample_rate: usize = 44100;
+  /// let max_delay = 5; // seconds
+  /// let delay = 1300.5f32; // samples
+  ///
+  /// let mut delay1: AllpassDelay<f32> = AllpassDelay::new(delay, max_delay * sample_rate);
+  /// let mut delay2: AllpassDelay<f64> = AllpassDelay::new(delay, max_delay * sample_rate);
+  /// let mut delay3 = AllpassDelay::<f32>::new(delay, max_delay * sample_rate);
+  /// let mut delay4 = AllpassDelay::<f64>::new(delay, max_delay * sample_rate);
+  /// ```
+  pub fn new(delay: f32,
+             max_delay: usize) -> Self {
+    let mut delay_line =
+      AllpassDelay {
+        memory: vec![num::zero(); max_delay + 1],
+        output: num::zero(),
+        read_ptr: 0,
+        write_ptr: 0,
+        delay: 0f32,
+        do_next_out: true,
+        next_out: num::zero(),
+        coefficient: num::zero(),
+        ap_output: num::zero()
+      };
+
+    delay_line.set_delay(delay);
+    delay_line
+  }
+
+  /// Set the maximum delay-line length, in samples.
+  pub fn set_max_delay(&mut self, delay: usize) {
+    if delay < self.memory.len() { return; }
+    self.memory.resize(delay + 1, num::zero());
+  }
+
+  /// Returns the maximum delay-line length, in samples.
+  pub fn get_max_delay(&self) -> usize {
+    self.memory.len() - 1
+  }
+
+  /// Set the current delay-line length, in samples.
+  ///
+  /// The `delay` value will be clipped if it is greater than `max_delay`, or
+  /// less than 0.5.
+  pub fn set_delay(&mut self, delay: f32) {
+    let delay: T = num::cast(delay).unwrap_or_else(T::zero);
+    self.locate(delay);
+    self.warm_up();
+  }
+
+  /// Returns the current delay-line length, in samples.
+  pub fn get_delay(&self) -> f32 {
+    self.delay
+  }
+
+  /// Returns the value that will be output by the next call to `tick()`.
+  pub fn next_out(&mut self) -> T {
+    if self.do_next_out {
+      let previous = if self.read_ptr == 0 { self.memory.len() - 1 } else { self.read_ptr - 1 };
+      self.next_out = self.coefficient * (self.memory[self.read_ptr] - self.ap_output)
+                    + self.memory[previous];
+      self.do_next_out = false;
+    }
+
+    self.next_out
+  }
+
+  /// Clips `delay`, and moves the read pointer and the all-pass coefficient
+  /// to `delay` samples before the write pointer.
+  fn locate(&mut self, delay: T) {
+    let max_delay: T = num::cast(self.memory.len() - 1).unwrap();
+    let half: T = num::cast(0.5f64).unwrap();
+    let delay = if delay > max_delay { max_delay }
+                else if delay < half || delay.is_nan() { half }
+                else { delay };
+    self.delay = num::cast(delay).unwrap();
+
+    // The read position is one sample later, since the all-pass adds at
+    // least half a sample of delay, and the fractional delay is kept within
+    // [0.5, 1.5)
+    let length = self.memory.len();
+    let whole: usize = num::cast(delay.floor()).unwrap();
+    let fraction = delay - delay.floor();
+    let alpha = if fraction >= half {
+      self.read_ptr = (self.write_ptr + length - whole) % length;
+      fraction
+    }
+    else {
+      self.read_ptr = (self.write_ptr + length + 1 - whole) % length;
+      T::one() + fraction
+    };
+    self.coefficient = (T::one() - alpha) / (T::one() + alpha);
+    self.do_next_out = true;
+  }
+
+  /// Recomputes the all-pass state with the current coefficient, from the
+  /// samples before the read position.
+  fn warm_up(&mut self) {
+    let length = self.memory.len();
+    let available = length.saturating_sub(self.delay.ceil() as usize + 2);
+    let steps = WARM_UP.min(available);
+
+    let index = |offset: usize| (self.read_ptr + length * 2 - offset) % length;
+    let mut input = self.memory[index(steps + 1)];
+    let mut output = T::zero();
+    for offset in (1..steps + 1).rev() {
+      let sample = self.memory[index(offset)];
+      output = self.coefficient * (sample - output) + input;
+      input = sample;
+    }
+
+    self.ap_output = output;
+    self.do_next_out = true;
+  }
+}
+
+impl<T> Processor<T> for AllpassDelay<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    // write input sample into memory
+    self.memory[self.write_ptr] = sample;
+    self.write_ptr += 1;
+    self.write_ptr %= self.memory.len();
+
+    // interpolate
+    self.output = self.next_out();
+    self.do_next_out = true;
+    self.ap_output = self.output;
+
+    // increment read_ptr
+    self.read_ptr += 1;
+    self.read_ptr %= self.memory.len();
+
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for sample in self.memory.iter_mut() {
+      *sample = num::zero();
+    }
+    self.output = num::zero();
+    self.ap_output = num::zero();
+    self.do_next_out = true;
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+impl<T> ModulatedDelayLine<T> for AllpassDelay<T> where T: Float {
+  /// Processes and stores input sample into memory, and outputs the sample
+  /// at `delay` samples, which is kept as the delay.
+  fn process_modulated(&mut self, sample: T, delay: T) -> T {
+    let read_ptr = self.read_ptr;
+    self.locate(delay);
+    if self.read_ptr != read_ptr {
+      self.warm_up();
+    }
+    self.process(sample)
+  }
+}
+
+impl<T> TappableDelayLine<T> for AllpassDelay<T> where T: Float {
+  fn tap_out(&self, tap_delay: usize) -> T {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize]
+  }
+
+  fn tap_in(&mut self, value: T, tap_delay: usize) {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize] = value;
+  }
+
+  fn add_to(&mut self, value: T, tap_delay: usize) -> T {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize] = self.memory[tap as usize] + value;
+    self.memory[tap as usize]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::EPSILON;
+  use std::f64::consts::PI;
+  use ::traits::{ModulatedDelayLine, Processor, TappableDelayLine};
+
+  /// Returns the largest error of `delay` against a delayed sine, after the
+  /// first `start` samples of `length`.
+  fn sine_error(delay: &mut AllpassDelay<f64>, start: usize, length: usize, offset: usize) -> f64 {
+    let frequency = 0.01f64;
+    (offset..offset + length).fold(0f64, |error, n| {
+      let input = (2f64 * PI * frequency * n as f64).sin();
+      let expected = (2f64 * PI * frequency * (n as f64 - delay.get_delay() as f64)).sin();
+      let actual = delay.process(input);
+      if n < offset + start { error } else { error.max((expected - actual).abs()) }
+    })
+  }
+
+  #[test]
+  fn new() {
+    let mut delay1 = AllpassDelay::<f32>::new(0.5f32, 4095);
+    let mut delay2 = AllpassDelay::<f32>::new(10.25f32, 4095);
+
+    assert_eq!(delay1.next_out(), 0f32);
+    assert_eq!(delay2.next_out(), 0f32);
+
+    assert!((delay1.get_delay() - 0.5f32).abs()   < EPSILON);
+    assert!((delay2.get_delay() - 10.25f32).abs() < EPSILON);
+    assert_eq!(delay1.get_max_delay(), delay2.get_max_delay());
+
+    delay1.set_delay(2.65f32);
+    assert!((delay1.get_delay() - 2.65f32).abs() < EPSILON);
+  }
+
+  #[test]
+  fn new_beyond_bounds() {
+    let delay1 = AllpassDelay::<f32>::new(2000f32, 1000);
+    assert!((delay1.get_delay() - delay1.get_max_delay() as f32).abs() < EPSILON);
+
+    let delay2 = AllpassDelay::<f32>::new(0f32, 1000);
+    assert!((delay2.get_delay() - 0.5f32).abs() < EPSILON);
+  }
+
+  #[test]
+  fn process() {
+    // An integer delay has a coefficient of zero, so it is not interpolated
+    let mut input     = vec![0f32; 5];    input[0] = 1f32;
+    let mut expected  = vec![0f32; 5]; expected[4] = 1f32;
+    let mut delay     = AllpassDelay::<f32>::new(4f32, 4095);
+
+    for (i, sample) in input.iter().enumerate() {
+      assert!((expected[i] - delay.process(*sample)).abs() < EPSILON);
+    }
+
+    // The all-pass is lossless, so an impulse keeps its energy
+    let mut delay = AllpassDelay::<f64>::new(2.3f32, 4095);
+    let energy = (0..200).fold(0f64, |energy, n| {
+      let output = delay.process(if n == 0 { 1f64 } else { 0f64 });
+      energy + output * output
+    });
+    assert!((energy - 1f64).abs() < 1e-9);
+  }
+
+  #[test]
+  fn fractional_delay() {
+    for delay in [0.5f32, 1.3f32, 10.25f32, 99.75f32].iter() {
+      let mut delay_line = AllpassDelay::<f64>::new(*delay, 4095);
+      assert!(sine_error(&mut delay_line, 200, 1000, 0) < 1e-3);
+    }
+  }
+
+  #[test]
+  fn set_delay_without_transient() {
+    let mut delay = AllpassDelay::<f64>::new(10.3f32, 4095);
+    sine_error(&mut delay, 0, 1000, 0);
+
+    // The output follows the new delay from the first sample
+    delay.set_delay(20.7f32);
+    assert!(sine_error(&mut delay, 0, 200, 1000) < 1e-3);
+  }
+
+  #[test]
+  fn process_modulated() {
+    // A slowly modulated delay follows the delayed sine, through the moves
+    // of the read position
+    let frequency = 0.01f64;
+    let mut delay = AllpassDelay::<f64>::new(10f32, 4095);
+    for n in 0..4_000 {
+      let time = 10f64 + 5f64 * (n as f64 * 0.002f64).sin();
+      let input = (2f64 * PI * frequency * n as f64).sin();
+      let expected = (2f64 * PI * frequency * (n as f64 - time)).sin();
+      let actual = delay.process_modulated(input, time);
+      if n > 100 {
+        assert!((expected - actual).abs() < 1e-2);
+      }
+    }
+    assert!((delay.get_delay() as f64 - (10f64 + 5f64 * (3_999f64 * 0.002f64).sin())).abs() < 1e-4);
+  }
+
+  #[test]
+  fn clear() {
+    let mut delay = AllpassDelay::<f32>::new(10.5f32, 4095);
+    for i in 0..20 {
+      delay.process(i as f32);
+    }
+
+    delay.clear();
+    assert_eq!(delay.last_out(), 0f32);
+    for i in 0..10 {
+      assert_eq!(delay.process(i as f32), 0f32);
+    }
+  }
+
+  #[test]
+  fn tap_out() {
+    let input     = vec![0f32, 0.25f32, 0.5f32, 0.75f32];
+    let expected  = vec![0.75f32, 0.5f32, 0.25f32, 0f32];
+    let mut delay = AllpassDelay::<f32>::new(4.5f32, 4095);
+
+    for sample in input.iter() {
+      delay.process(*sample);
+      assert_eq!(*sample, delay.tap_out(0));
+    }
+
+    for (i, sample) in expected.iter().enumerate() {
+      assert_eq!(*sample, delay.tap_out(i));
+    }
+  }
+
+  #[test]
+  fn tap_in() {
+    let input     = vec![0f32, 0.25f32, 0.5f32, 0.75f32];
+    let expected  = vec![0.75f32, 0.5f32, 0.25f32, 0f32];
+    let mut delay = AllpassDelay::<f32>::new(4f32, 4095);
+
+    for (i, sample) in input.iter().enumerate() {
+      delay.tap_in(*sample, i);
+    }
+
+    for sample in expected.iter() {
+      assert_eq!(*sample, delay.process(0f32));
+    }
+  }
+}
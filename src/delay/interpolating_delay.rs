@@ -0,0 +1,599 @@
+use num;
+use num::traits::Float;
+
+use traits::{
+  FloatConst,
+  FractionalDelayLine,
+  ModulatedDelayLine,
+  Processor,
+  TappableDelayLine
+};
+use window::{self, Window};
+
+/* Notes on the interpolation
+  - Every method reads `points()` samples around the fractional position,
+    from `points() / 2 - 1` taps before it to `points() / 2` taps after it,
+    so the memory is longer than the maximum delay by `points() / 2` samples
+  - Delays shorter than `points() / 2 - 1` samples would read samples that
+    have not been written yet, so they are linearly interpolated, and a
+    modulated delay can pass through zero
+  - Lagrange interpolation fits a polynomial through all points; it is
+    maximally flat at DC, but its high-frequency loss still depends on the
+    fraction, less so with more points
+  - Cubic Hermite (Catmull-Rom) interpolation matches the slopes at the two
+    middle points instead, so it is smoother when the delay is modulated
+  - The windowed sinc is tabulated once per delay line, with `RESOLUTION`
+    points per zero crossing, and its weights are normalized to a unity
+    gain at DC for every fraction
+  - A modulated delay that changes by more than a sample in one sample is a
+    jump, which would be a discontinuity in the output, so the output
+    crossfades over `CROSSFADE` samples from a read at the previous delay
+  - A jump during a crossfade fades from the output as it was mixed, so the
+    delays before each jump are kept, oldest first, each with the remaining
+    crossfade from the mix of it and the older delays to the next delay
+*/
+
+/// The number of zero crossings on each side of the windowed-sinc kernel.
+const ZERO_CROSSINGS: usize = 8;
+
+/// The number of points of the windowed-sinc table per zero crossing.
+const RESOLUTION: usize = 256;
+
+/// The length of the crossfade after a jump of the delay, in samples.
+const CROSSFADE: usize = 64;
+
+/// The interpolation of an `InterpolatingDelay`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Interpolation {
+  /// Two points, as in `LinearDelay`
+  Linear,
+  /// Third-order Lagrange interpolation, with four points
+  Lagrange3,
+  /// Fifth-order Lagrange interpolation, with six points
+  Lagrange5,
+  /// Cubic Hermite (Catmull-Rom) interpolation, with four points
+  Hermite,
+  /// Band-limited interpolation, with a Blackman-windowed sinc of sixteen
+  /// points
+  Sinc
+}
+
+impl Interpolation {
+  /// Returns the number of samples read for each output.
+  pub fn points(self) -> usize {
+    match self {
+      Interpolation::Linear    => 2,
+      Interpolation::Lagrange3 => 4,
+      Interpolation::Lagrange5 => 6,
+      Interpolation::Hermite   => 4,
+      Interpolation::Sinc      => 2 * ZERO_CROSSINGS
+    }
+  }
+
+  /// Returns the shortest delay, in samples, that is interpolated with all
+  /// points; shorter delays are linearly interpolated.
+  pub fn min_delay(self) -> usize {
+    self.points() / 2 - 1
+  }
+}
+
+/// A time-varying delay line, with a choice of interpolation.
+pub struct InterpolatingDelay<T> {
+  memory: Vec<T>,
+  output: T,
+  write_ptr: usize,
+  /// Delay time as a number of samples, which must be less than or equal to
+  /// the maximum delay.
+  delay: f32,
+  interpolation: Interpolation,
+  /// The right half of the windowed-sinc kernel, if it is used
+  table: Vec<T>,
+  /// The delay of the previous output
+  last_delay: T,
+  /// The delays before the recent jumps, oldest first, and the remaining
+  /// crossfade from each
+  fades: Vec<(T, usize)>
+}
+
+impl<T> InterpolatingDelay<T> where T: Float + FloatConst {
+  /// Creates a delay line.
+  ///
+  /// Both `delay` and `max_delay` are represented in samples. The `delay`
+  /// value will be clipped if it is greater than `max_delay`, or less than
+  /// zero.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::traits::{FractionalDelayLine, Processor};
+  /// use rasp::delay::{Interpolation, InterpolatingDelay};
+  ///
+  /// let mut delay = InterpolatingDelay::<f32>::new(220.5f32, 44100, Interpolation::Hermite);
+  /// delay.process(1f32);
+  ///
+  /// // Reads between the samples written 10 and 11 samples ago
+  /// let tap = delay.tap_out_fractional(10.25f32);
+  /// # assert_eq!(tap, 0f32);
+  /// ```
+  pub fn new(delay: f32,
+             max_delay: usize,
+             interpolation: Interpolation) -> Self {
+    let mut table = Vec::new();
+    if interpolation == Interpolation::Sinc {
+      let length = ZERO_CROSSINGS * RESOLUTION;
+      let mut window = vec![T::one(); 2 * length + 1];
+      window::apply_window(&mut window, Window::Blackman);
+      table = (0..length + 1).map(|i| {
+        let x = ::std::f64::consts::PI * i as f64 / RESOLUTION as f64;
+        let sinc: T = num::cast(if i == 0 { 1f64 } else { x.sin() / x }).unwrap();
+        sinc * window[length + i]
+      }).collect();
+    }
+
+    let mut delay_line =
+      InterpolatingDelay {
+        memory: vec![num::zero(); max_delay + 1 + interpolation.points() / 2],
+        output: num::zero(),
+        write_ptr: 0,
+        delay: 0f32,
+        interpolation: interpolation,
+        table: table,
+        last_delay: T::zero(),
+        fades: Vec::with_capacity(CROSSFADE + 1)
+      };
+
+    delay_line.set_delay(delay);
+    delay_line.last_delay = num::cast(delay_line.delay).unwrap();
+    delay_line
+  }
+
+  /// Set the maximum delay-line length, in samples.
+  pub fn set_max_delay(&mut self, delay: usize) {
+    if delay <= self.get_max_delay() { return; }
+
+    // The samples after the write pointer are the oldest, so they move to
+    // the end of the longer memory
+    let length = self.memory.len();
+    let added = delay - self.get_max_delay();
+    self.memory.resize(length + added, num::zero());
+    for index in (self.write_ptr..length).rev() {
+      self.memory[index + added] = self.memory[index];
+      self.memory[index] = num::zero();
+    }
+  }
+
+  /// Returns the maximum delay-line length, in samples.
+  pub fn get_max_delay(&self) -> usize {
+    self.memory.len() - 1 - self.interpolation.points() / 2
+  }
+
+  /// Set the current delay-line length, in samples.
+  ///
+  /// The `delay` value will be clipped if it is greater than `max_delay`, or
+  /// less than zero. A change of more than one sample is crossfaded.
+  pub fn set_delay(&mut self, delay: f32) {
+    let delay: T = num::cast(delay).unwrap_or_else(T::zero);
+    self.delay = num::cast(self.clip(delay)).unwrap();
+  }
+
+  /// Returns the current delay-line length, in samples.
+  pub fn get_delay(&self) -> f32 {
+    self.delay
+  }
+
+  /// Writes `sample` into memory without reading, for a delay line that is
+  /// only read with `tap_out_fractional()`.
+  pub fn write(&mut self, sample: T) {
+    self.memory[self.write_ptr] = sample;
+    self.write_ptr += 1;
+    self.write_ptr %= self.memory.len();
+  }
+
+  /// Returns the interpolation.
+  pub fn interpolation(&self) -> Interpolation {
+    self.interpolation
+  }
+
+  /// Clips `delay` to the delays that can be read.
+  fn clip(&self, delay: T) -> T {
+    let max_delay: T = num::cast(self.get_max_delay()).unwrap();
+    if delay > max_delay {
+      max_delay
+    }
+    else if delay < T::zero() || delay.is_nan() {
+      T::zero()
+    }
+    else {
+      delay
+    }
+  }
+
+  /// Returns the sample at `tap_delay` samples from the current delay-line
+  /// input.
+  fn sample(&self, tap_delay: usize) -> T {
+    let length = self.memory.len();
+    self.memory[(self.write_ptr + 2 * length - tap_delay - 1) % length]
+  }
+
+  /// Returns the windowed-sinc kernel at `x` samples from its center.
+  fn kernel(&self, x: T) -> T {
+    let index = x.abs() * num::cast(RESOLUTION).unwrap();
+    let i = index.to_usize().unwrap();
+    if i >= self.table.len() - 1 {
+      return T::zero();
+    }
+    let fraction = index - num::cast(i).unwrap();
+    self.table[i] + (self.table[i + 1] - self.table[i]) * fraction
+  }
+
+  /// Returns the value at `tap_delay` samples from the current delay-line
+  /// input, interpolated between samples.
+  fn read(&self, tap_delay: T) -> T {
+    let tap_delay = self.clip(tap_delay);
+    let tap = tap_delay.floor().to_usize().unwrap();
+    let fraction = tap_delay - tap_delay.floor();
+
+    let interpolation = if tap < self.interpolation.min_delay() {
+      Interpolation::Linear
+    }
+    else {
+      self.interpolation
+    };
+    match interpolation {
+      Interpolation::Linear => {
+        self.sample(tap) * (T::one() - fraction) + self.sample(tap + 1) * fraction
+      },
+      Interpolation::Lagrange3 => self.lagrange(tap, fraction, 4),
+      Interpolation::Lagrange5 => self.lagrange(tap, fraction, 6),
+      Interpolation::Hermite => {
+        let half: T = num::cast(0.5f64).unwrap();
+        let (y0, y1, y2, y3) = (self.sample(tap - 1), self.sample(tap),
+                                self.sample(tap + 1), self.sample(tap + 2));
+        let c1 = half * (y2 - y0);
+        let c2 = y0 - num::cast::<f64, T>(2.5f64).unwrap() * y1 + T::two() * y2 - half * y3;
+        let c3 = half * (y3 - y0) + num::cast::<f64, T>(1.5f64).unwrap() * (y1 - y2);
+        ((c3 * fraction + c2) * fraction + c1) * fraction + y1
+      },
+      Interpolation::Sinc => {
+        let first = tap + 1 - ZERO_CROSSINGS;
+        let (sum, weights) = (first..tap + ZERO_CROSSINGS + 1).fold(
+          (T::zero(), T::zero()),
+          |(sum, weights), index| {
+            let x = num::cast::<usize, T>(index).unwrap() - tap_delay;
+            let weight = self.kernel(x);
+            (sum + weight * self.sample(index), weights + weight)
+          });
+        sum / weights
+      }
+    }
+  }
+
+  /// Returns the Lagrange interpolation of the samples around `tap`.
+  fn lagrange(&self, tap: usize, fraction: T, points: usize) -> T {
+    let first = 1 - (points / 2) as isize;
+    (0..points).fold(T::zero(), |sum, j| {
+      let weight = (0..points).filter(|m| *m != j).fold(T::one(), |weight, m| {
+        let distance: T = num::cast(j as isize - m as isize).unwrap();
+        let position: T = num::cast(first + m as isize).unwrap();
+        weight * (fraction - position) / distance
+      });
+      let offset = first + j as isize;
+      sum + weight * self.sample((tap as isize + offset) as usize)
+    })
+  }
+}
+
+impl<T> FractionalDelayLine<T> for InterpolatingDelay<T> where T: Float + FloatConst {
+  /// Returns the value at `tap_delay` samples from the current delay-line
+  /// input, which is clipped like the delay.
+  fn tap_out_fractional(&self, tap_delay: f32) -> T {
+    self.read(num::cast(tap_delay).unwrap_or_else(T::zero))
+  }
+}
+
+impl<T> ModulatedDelayLine<T> for InterpolatingDelay<T> where T: Float + FloatConst {
+  fn process_modulated(&mut self, sample: T, delay: T) -> T {
+    self.write(sample);
+
+    let delay = self.clip(delay);
+    if (delay - self.last_delay).abs() > T::one() {
+      self.fades.push((self.last_delay, CROSSFADE));
+    }
+    self.last_delay = delay;
+
+    let mut mix = self.fades.first().map(|fade| self.read(fade.0));
+    for (index, &(_, remaining)) in self.fades.iter().enumerate() {
+      let next = self.fades.get(index + 1).map_or(delay, |fade| fade.0);
+      let next = self.read(next);
+      let gain: T = num::cast(remaining as f64 / (CROSSFADE + 1) as f64).unwrap();
+      mix = mix.map(|mix| next + (mix - next) * gain);
+    }
+    self.output = mix.unwrap_or_else(|| self.read(delay));
+
+    for fade in self.fades.iter_mut() {
+      fade.1 -= 1;
+    }
+    self.fades.retain(|fade| fade.1 > 0);
+    self.output
+  }
+}
+
+impl<T> Processor<T> for InterpolatingDelay<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    let delay = num::cast(self.delay).unwrap();
+    self.process_modulated(sample, delay)
+  }
+
+  fn clear(&mut self) {
+    for sample in self.memory.iter_mut() {
+      *sample = num::zero();
+    }
+    self.output = num::zero();
+    self.last_delay = num::cast(self.delay).unwrap();
+    self.fades.clear();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+impl<T> TappableDelayLine<T> for InterpolatingDelay<T> where T: Float {
+  fn tap_out(&self, tap_delay: usize) -> T {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize]
+  }
+
+  fn tap_in(&mut self, value: T, tap_delay: usize) {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize] = value;
+  }
+
+  fn add_to(&mut self, value: T, tap_delay: usize) -> T {
+    let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
+    if tap < 0 {
+      tap += self.memory.len() as isize;
+    }
+    self.memory[tap as usize] = self.memory[tap as usize] + value;
+    self.memory[tap as usize]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::EPSILON;
+  use std::f64::consts::PI;
+  use ::traits::{FractionalDelayLine, ModulatedDelayLine, Processor, TappableDelayLine};
+
+  const INTERPOLATIONS: [Interpolation; 5] = [
+    Interpolation::Linear,
+    Interpolation::Lagrange3,
+    Interpolation::Lagrange5,
+    Interpolation::Hermite,
+    Interpolation::Sinc
+  ];
+
+  /// Returns the largest error of a sine of `frequency` through `delay`.
+  fn sine_error(delay: &mut InterpolatingDelay<f64>, frequency: f64) -> f64 {
+    (0..1000).fold(0f64, |error, n| {
+      let input = (2f64 * PI * frequency * n as f64).sin();
+      let expected = (2f64 * PI * frequency * (n as f64 - delay.get_delay() as f64)).sin();
+      let actual = delay.process(input);
+      if n < 100 { error } else { error.max((expected - actual).abs()) }
+    })
+  }
+
+  #[test]
+  fn new_beyond_bounds() {
+    for interpolation in INTERPOLATIONS.iter() {
+      let delay1 = InterpolatingDelay::<f32>::new(2000f32, 1000, *interpolation);
+      assert_eq!(delay1.get_max_delay(), 1000);
+      assert!((delay1.get_delay() - 1000f32).abs() < EPSILON);
+
+      let delay2 = InterpolatingDelay::<f32>::new(-2000f32, 1000, *interpolation);
+      assert!((delay2.get_delay() - 0f32).abs() < EPSILON);
+    }
+  }
+
+  #[test]
+  fn process() {
+    // Integer delays are not interpolated
+    for interpolation in INTERPOLATIONS.iter() {
+      let mut delay = InterpolatingDelay::<f32>::new(10f32, 4095, *interpolation);
+      for n in 0..20 {
+        let output = delay.process(if n == 0 { 1f32 } else { 0f32 });
+        let expected = if n == 10 { 1f32 } else { 0f32 };
+        assert!((output - expected).abs() < 1e-6f32);
+      }
+    }
+  }
+
+  #[test]
+  fn fractional_delay() {
+    // Lagrange, Hermite, and sinc interpolation lose less than linear
+    // interpolation at high frequencies
+    let errors: Vec<f64> = INTERPOLATIONS.iter().map(|interpolation| {
+      let mut delay = InterpolatingDelay::new(10.5f32, 4095, *interpolation);
+      sine_error(&mut delay, 0.1f64)
+    }).collect();
+    assert!(errors[0] > 0.04f64);
+    assert!(errors[1] < 0.01f64);
+    assert!(errors[2] < 0.002f64);
+    assert!(errors[3] < 0.02f64);
+    assert!(errors[4] < 0.002f64);
+
+    // Band-limited interpolation is accurate up to near the Nyquist
+    let mut delay = InterpolatingDelay::new(20.3f32, 4095, Interpolation::Sinc);
+    assert!(sine_error(&mut delay, 0.35f64) < 0.02f64);
+  }
+
+  #[test]
+  fn tap_out_fractional() {
+    for interpolation in INTERPOLATIONS.iter() {
+      let mut delay = InterpolatingDelay::<f32>::new(4f32, 4095, *interpolation);
+      for n in 0..12 {
+        delay.process(n as f32 * 0.125f32);
+      }
+
+      // Every polynomial method is exact on a line
+      if *interpolation != Interpolation::Sinc {
+        assert!((delay.tap_out_fractional(2.5f32) - 1.0625f32).abs() < 1e-6f32);
+      }
+      assert!((delay.tap_out_fractional(7f32) - delay.tap_out(7)).abs() < 1e-6f32);
+    }
+
+    let mut delay = InterpolatingDelay::<f32>::new(4f32, 4095, Interpolation::Linear);
+    delay.process(1f32);
+    assert!((delay.tap_out_fractional(0.25f32) - 0.75f32).abs() < EPSILON);
+    assert!((delay.tap_out_fractional(-1f32) - 1f32).abs() < EPSILON);
+  }
+
+  #[test]
+  fn through_zero() {
+    // The delay sweeps down to zero and back, across the shortest delay each
+    // interpolation reads in full, below which it falls back to linear, and
+    // the error does not step where it switches
+    for interpolation in INTERPOLATIONS.iter() {
+      let shortest = interpolation.min_delay() as f64;
+      assert!(shortest + 1f64 < 12f64);
+      let mut delay = InterpolatingDelay::new(12f32, 4095, *interpolation);
+      let frequency = 0.005f64;
+      let (mut error, mut full, mut step, mut previous) = (0f64, 0f64, 0f64, 0f64);
+      for n in 0..2000 {
+        let time = 6f64 + 6f64 * (2f64 * PI * 0.001f64 * n as f64).cos();
+        let input = (2f64 * PI * frequency * n as f64).sin();
+        let expected = (2f64 * PI * frequency * (n as f64 - time)).sin();
+        let difference = expected - delay.process_modulated(input, time);
+        if n >= 20 {
+          error = error.max(difference.abs());
+          step = step.max((difference - previous).abs());
+          if time > shortest + 1f64 {
+            full = full.max(difference.abs());
+          }
+        }
+        previous = difference;
+      }
+      println!("{:?} {:.8} {:.8} {:.8}", interpolation, error, full, step);
+      assert!(error < 1e-3 && step < 5e-5);
+      if *interpolation != Interpolation::Linear {
+        assert!(full < 1e-5);
+      }
+    }
+  }
+
+  #[test]
+  fn jump() {
+    // A jump is crossfaded, so the output has no discontinuity
+    let mut delay = InterpolatingDelay::new(10f32, 4095, Interpolation::Lagrange3);
+    let frequency = 0.01f64;
+    let mut previous = 0f64;
+    let mut largest_step = 0f64;
+    for n in 0..1000 {
+      let time = if n < 500 { 10.5f64 } else { 301.25f64 };
+      let input = (2f64 * PI * frequency * n as f64).sin();
+      let actual = delay.process_modulated(input, time);
+      if n > 20 {
+        largest_step = largest_step.max((actual - previous).abs());
+      }
+      if n > 500 + CROSSFADE {
+        let expected = (2f64 * PI * frequency * (n as f64 - time)).sin();
+        assert!((expected - actual).abs() < 1e-3);
+      }
+      previous = actual;
+    }
+    assert!(largest_step < 0.1f64);
+  }
+
+  #[test]
+  fn jump_during_crossfade() {
+    // A second jump fades from the mix of the first crossfade, rather than
+    // from the delay before it
+    let mut delay = InterpolatingDelay::new(10f32, 4095, Interpolation::Hermite);
+    let frequency = 0.01f64;
+    let mut previous = 0f64;
+    let mut largest_step = 0f64;
+    for n in 0..1000 {
+      let time = match n {
+        0..=499 => 10.5f64,
+        500..=519 => 301.25f64,
+        _ => 140.75f64
+      };
+      let input = (2f64 * PI * frequency * n as f64).sin();
+      let actual = delay.process_modulated(input, time);
+      if n > 20 {
+        largest_step = largest_step.max((actual - previous).abs());
+      }
+      if n > 520 + CROSSFADE {
+        let expected = (2f64 * PI * frequency * (n as f64 - time)).sin();
+        assert!((expected - actual).abs() < 1e-3);
+      }
+      previous = actual;
+    }
+    assert!(largest_step < 0.1f64);
+  }
+
+  #[test]
+  fn process_modulated_block() {
+    let mut samples: Vec<f64> = (0..256).map(|n| (n as f64 * 0.1f64).sin()).collect();
+    let delays: Vec<f64> = (0..256).map(|n| 20f64 + 5f64 * (n as f64 * 0.01f64).sin()).collect();
+
+    let mut delay1 = InterpolatingDelay::new(20f32, 100, Interpolation::Sinc);
+    let expected: Vec<f64> = samples.iter().zip(delays.iter())
+      .map(|(sample, time)| delay1.process_modulated(*sample, *time))
+      .collect();
+
+    let mut delay2 = InterpolatingDelay::new(20f32, 100, Interpolation::Sinc);
+    delay2.process_modulated_block(&mut samples, &delays);
+    assert_eq!(samples, expected);
+  }
+
+  #[test]
+  fn set_max_delay() {
+    let mut delay = InterpolatingDelay::<f32>::new(3f32, 3, Interpolation::Hermite);
+    for n in 0..10 {
+      delay.process(n as f32);
+    }
+    delay.set_max_delay(6);
+    assert_eq!(delay.get_max_delay(), 6);
+    assert_eq!(delay.tap_out(3), 6f32);
+
+    delay.process(10f32);
+    assert_eq!(delay.tap_out_fractional(6f32), 4f32);
+  }
+
+  #[test]
+  fn clear() {
+    let mut delay = InterpolatingDelay::<f32>::new(10.5f32, 4095, Interpolation::Sinc);
+    for i in 0..40 {
+      delay.process(i as f32);
+    }
+
+    delay.clear();
+    assert_eq!(delay.last_out(), 0f32);
+    for _ in 0..10 {
+      assert_eq!(delay.process(0f32), 0f32);
+    }
+  }
+
+  #[test]
+  fn tap_in() {
+    let input     = vec![0f32, 0.25f32, 0.5f32, 0.75f32];
+    let expected  = vec![0.75f32, 0.5f32, 0.25f32, 0f32];
+    let mut delay = InterpolatingDelay::<f32>::new(4f32, 4095, Interpolation::Lagrange3);
+
+    for (i, sample) in input.iter().enumerate() {
+      delay.tap_in(*sample, i);
+    }
+
+    for sample in expected.iter() {
+      assert_eq!(*sample, delay.process(0f32));
+    }
+  }
+}
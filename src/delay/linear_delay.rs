@@ -2,6 +2,8 @@ use num;
 use num::traits::Float;
 
 use traits::{
+  FractionalDelayLine,
+  ModulatedDelayLine,
   Processor,
   TappableDelayLine
 };
@@ -85,31 +87,8 @@ impl<T> LinearDelay<T> where T: Float {
   ///
   /// The `delay` value will be clipped if it is greater than `max_delay`.
   pub fn set_delay(&mut self, delay: f32) {
-    let mut delay_time = delay;
-    let max_delay_samples = (self.memory.len() - 1) as f32;
-    if delay_time > max_delay_samples {
-      delay_time = max_delay_samples;
-    }
-    if delay_time < 0f32 {
-      delay_time = 0f32;
-    }
-
-    let mut read_ptr_integer: f32 = self.write_ptr as f32 - delay_time;
-    self.delay = delay_time;
-
-    while read_ptr_integer < 0f32 {
-      read_ptr_integer += self.memory.len() as f32;
-    }
-
-    // save integer part
-    self.read_ptr = read_ptr_integer as usize;
-    if self.read_ptr == self.memory.len() {
-      self.read_ptr = 0;
-    }
-
-    // save fractional part
-    self.alpha = num::cast(read_ptr_integer - self.read_ptr as f32).unwrap();
-    self.om_alpha = T::one() - self.alpha;
+    let delay: T = num::cast(delay).unwrap_or_else(T::zero);
+    self.locate(delay);
   }
 
   /// Returns the current delay-line length, in samples.
@@ -136,6 +115,32 @@ impl<T> LinearDelay<T> where T: Float {
 
     return self.next_out;
   }
+
+  /// Clips `delay`, and moves the read pointer to `delay` samples before the
+  /// write pointer.
+  fn locate(&mut self, delay: T) {
+    let max_delay: T = num::cast(self.memory.len() - 1).unwrap();
+    let delay = if delay > max_delay { max_delay }
+                else if delay < T::zero() || delay.is_nan() { T::zero() }
+                else { delay };
+    self.delay = num::cast(delay).unwrap();
+
+    // The read position is split into whole samples and a fraction, so the
+    // pointers stay exact for long delay lines
+    let length = self.memory.len();
+    let whole: usize = num::cast(delay.floor()).unwrap();
+    let fraction = delay - delay.floor();
+    if fraction > T::zero() {
+      self.read_ptr = (self.write_ptr + 2 * length - whole - 1) % length;
+      self.alpha = T::one() - fraction;
+    }
+    else {
+      self.read_ptr = (self.write_ptr + length - whole) % length;
+      self.alpha = T::zero();
+    }
+    self.om_alpha = T::one() - self.alpha;
+    self.do_next_out = true;
+  }
 }
 
 impl<T> Processor<T> for LinearDelay<T> where T: Float {
@@ -168,6 +173,17 @@ impl<T> Processor<T> for LinearDelay<T> where T: Float {
   }
 }
 
+impl<T> ModulatedDelayLine<T> for LinearDelay<T> where T: Float {
+  /// Processes and stores input sample into memory, and outputs the sample
+  /// at `delay` samples, which is kept as the delay.
+  ///
+  /// Unlike `InterpolatingDelay`, a jump of the delay is not crossfaded.
+  fn process_modulated(&mut self, sample: T, delay: T) -> T {
+    self.locate(delay);
+    self.process(sample)
+  }
+}
+
 impl<T> TappableDelayLine<T> for LinearDelay<T> where T: Float {
   fn tap_out(&self, tap_delay: usize) -> T {
     let mut tap: isize = self.write_ptr as isize - tap_delay as isize - 1;
@@ -195,11 +211,25 @@ impl<T> TappableDelayLine<T> for LinearDelay<T> where T: Float {
   }
 }
 
+impl<T> FractionalDelayLine<T> for LinearDelay<T> where T: Float {
+  /// Returns the value at `tap_delay` samples from the current delay-line
+  /// input, which is clipped like the delay.
+  fn tap_out_fractional(&self, tap_delay: f32) -> T {
+    let max_delay = (self.memory.len() - 1) as f32;
+    let tap_delay = tap_delay.max(0f32).min(max_delay);
+    let tap = tap_delay as usize;
+    let alpha: T = num::cast(tap_delay - tap as f32).unwrap();
+
+    let next = if tap == self.memory.len() - 1 { tap } else { tap + 1 };
+    self.tap_out(tap) * (T::one() - alpha) + self.tap_out(next) * alpha
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use std::f32::EPSILON;
-  use ::traits::{Processor, TappableDelayLine};
+  use ::traits::{FractionalDelayLine, ModulatedDelayLine, Processor, TappableDelayLine};
 
   #[test]
   fn new() {
@@ -277,6 +307,42 @@ mod tests {
     }
   }
 
+  #[test]
+  fn tap_out_fractional() {
+    let input     = vec![0f32, 0.25f32, 0.5f32, 0.75f32];
+    let mut delay = LinearDelay::<f32>::new(4f32, 4095);
+
+    for sample in input.iter() {
+      delay.process(*sample);
+    }
+
+    assert!((delay.tap_out_fractional(0.5f32) - 0.625f32).abs() < EPSILON);
+    assert!((delay.tap_out_fractional(2.25f32) - 0.1875f32).abs() < EPSILON);
+    assert_eq!(delay.tap_out_fractional(1f32), delay.tap_out(1));
+  }
+
+  #[test]
+  fn process_modulated() {
+    // A modulated delay reads like a delay set before each sample
+    let input: Vec<f64> = (0..300).map(|n| (n as f64 * 0.1f64).sin()).collect();
+    let delays: Vec<f64> = (0..300).map(|n| 20f64 + 19.5f64 * (n as f64 * 0.03f64).sin()).collect();
+    let mut modulated = LinearDelay::<f64>::new(0f32, 100);
+    let mut reference = LinearDelay::<f64>::new(0f32, 100);
+    for (sample, delay) in input.iter().zip(delays.iter()) {
+      reference.set_delay(*delay as f32);
+      let expected = reference.process(*sample);
+      let actual = modulated.process_modulated(*sample, *delay);
+      assert!((expected - actual).abs() < 1e-6);
+      assert_eq!(modulated.get_delay(), *delay as f32);
+    }
+
+    // The delay passes through zero, where the input is output
+    let mut delay = LinearDelay::<f64>::new(0f32, 100);
+    assert_eq!(delay.process_modulated(0.5f64, 0f64), 0.5f64);
+    assert_eq!(delay.process_modulated(0.25f64, 1f64), 0.5f64);
+    assert_eq!(delay.process_modulated(0.75f64, 0.5f64), 0.5f64);
+  }
+
   #[test]
   fn tap_in() {
     // NOTE: More test cases should be added
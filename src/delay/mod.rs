@@ -1,6 +1,16 @@
+mod allpass_delay;
+mod interpolating_delay;
 mod linear_delay;
-
-pub use self::linear_delay::LinearDelay as LinearDelay;
+mod multi_tap;
+
+pub use self::allpass_delay::AllpassDelay             as AllpassDelay;
+pub use self::interpolating_delay::Interpolation      as Interpolation;
+pub use self::interpolating_delay::InterpolatingDelay as InterpolatingDelay;
+pub use self::linear_delay::LinearDelay               as LinearDelay;
+pub use self::multi_tap::MultiTapDelay                as MultiTapDelay;
+pub use self::multi_tap::ParsePatternError            as ParsePatternError;
+pub use self::multi_tap::Pattern                      as Pattern;
+pub use self::multi_tap::Tap                          as Tap;
 
 use num;
 use num::traits::Float;
@@ -0,0 +1,376 @@
+use num;
+use num::traits::Float;
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use delay::{Interpolation, InterpolatingDelay};
+use filter::OnePole;
+use traits::{FloatConst, FractionalDelayLine, Processor};
+
+/* Notes on the multi-tap delay
+  - All taps read one `InterpolatingDelay`, which is only written to, so a
+    tap costs one interpolated read, a multiply, and its damping filter
+  - The damping filter of a tap is a one-pole low-pass,
+    `y[n] = (1 - d)*x[n] + d*y[n-1]`, which has a unity gain at DC; a damping
+    of zero bypasses it
+  - Stereo output pans each tap with a constant-power law, so a tap panned
+    to the center is 3 dB lower in each channel than in the mono sum
+  - A pattern is written as one tap per `;`-separated entry, each with its
+    delay in samples, gain, pan, and damping, separated by spaces, such as
+    `4410 0.7 -0.5 0; 8820 0.5 0.5 0.2`
+*/
+
+/// A tap of a `MultiTapDelay`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tap<T> {
+  /// The delay, in samples, which may be fractional
+  pub delay: f32,
+  pub gain: T,
+  /// The position from left, -1, to right, 1
+  pub pan: T,
+  /// The coefficient of the damping filter, from 0 (no filtering) to 1
+  pub damping: T
+}
+
+impl<T> Tap<T> where T: Float {
+  /// Creates a new `Tap`.
+  pub fn new(delay: f32, gain: T, pan: T, damping: T) -> Self {
+    Tap {
+      delay: delay,
+      gain: gain,
+      pan: pan,
+      damping: damping
+    }
+  }
+
+  /// Returns the left and right gains of the constant-power pan.
+  fn pan_gains(&self) -> (T, T)
+    where T: FloatConst
+  {
+    let pan = self.pan.max(-T::one()).min(T::one());
+    let four: T = num::cast(4).unwrap();
+    let angle = (pan + T::one()) * T::pi() / four;
+    (angle.cos(), angle.sin())
+  }
+
+  /// Returns a damping filter for the tap.
+  fn filter(&self) -> OnePole<T> {
+    let mut filter = OnePole::new();
+    filter.set_coefficients(T::one() - self.damping, -self.damping);
+    filter
+  }
+}
+
+/// A set of taps, which can be written to and parsed from a string.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::delay::{Pattern, Tap};
+///
+/// let pattern: Pattern<f32> = "4410 0.7 -0.5 0; 8820 0.5 0.5 0.2".parse().unwrap();
+/// assert_eq!(pattern.taps[1], Tap::new(8820f32, 0.5f32, 0.5f32, 0.2f32));
+/// assert_eq!(pattern.to_string(), "4410 0.7 -0.5 0; 8820 0.5 0.5 0.2");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern<T> {
+  pub taps: Vec<Tap<T>>
+}
+
+/// An error from parsing a `Pattern`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ParsePatternError {
+  /// The index of the invalid tap
+  pub tap: usize
+}
+
+impl fmt::Display for ParsePatternError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "invalid tap {} in pattern, expected delay, gain, pan, and damping", self.tap)
+  }
+}
+
+impl error::Error for ParsePatternError {}
+
+impl<T> fmt::Display for Pattern<T> where T: Float + fmt::Display {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (index, tap) in self.taps.iter().enumerate() {
+      if index > 0 {
+        write!(f, "; ")?;
+      }
+      write!(f, "{} {} {} {}", tap.delay, tap.gain, tap.pan, tap.damping)?;
+    }
+    Ok(())
+  }
+}
+
+impl<T> FromStr for Pattern<T> where T: Float + FromStr {
+  type Err = ParsePatternError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut taps = Vec::new();
+    for (index, entry) in s.split(';').enumerate() {
+      // Empty entries after the first are skipped, so a trailing separator
+      // is accepted, and the first is only empty in an empty pattern
+      if entry.trim().is_empty() && (index > 0 || s.trim().is_empty()) {
+        continue;
+      }
+
+      let error = ParsePatternError { tap: index };
+      let fields: Vec<&str> = entry.split_whitespace().collect();
+      if fields.len() != 4 {
+        return Err(error);
+      }
+      let delay = fields[0].parse::<f32>().map_err(|_| error)?;
+      let mut values = [T::zero(); 3];
+      for (value, field) in values.iter_mut().zip(fields[1..].iter()) {
+        *value = field.parse::<T>().map_err(|_| error)?;
+      }
+      taps.push(Tap::new(delay, values[0], values[1], values[2]));
+    }
+    Ok(Pattern { taps: taps })
+  }
+}
+
+/// A delay line with any number of taps, each with its own delay, gain, pan,
+/// and damping.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::delay::{Interpolation, MultiTapDelay, Tap};
+///
+/// // Dotted eighth and quarter notes at 120 bpm
+/// let mut delay = MultiTapDelay::new(44_100, Interpolation::Hermite);
+/// delay.add_tap(Tap::new(16_537.5f32, 0.6f32, -0.7f32, 0.1f32));
+/// delay.add_tap(Tap::new(22_050f32, 0.4f32, 0.7f32, 0.3f32));
+///
+/// let mono = delay.process(1f32);
+/// let (left, right) = delay.process_stereo(0f32);
+/// # assert_eq!((mono, left, right), (0f32, 0f32, 0f32));
+/// ```
+pub struct MultiTapDelay<T> {
+  delay_line: InterpolatingDelay<T>,
+  taps: Vec<Tap<T>>,
+  filters: Vec<OnePole<T>>,
+  /// The left and right gains of each tap
+  pans: Vec<(T, T)>,
+  output: T
+}
+
+impl<T> MultiTapDelay<T> where T: Float + FloatConst {
+  /// Creates a new `MultiTapDelay`, without taps, whose taps can be delayed
+  /// by up to `max_delay` samples.
+  pub fn new(max_delay: usize, interpolation: Interpolation) -> Self {
+    MultiTapDelay {
+      delay_line: InterpolatingDelay::new(0f32, max_delay, interpolation),
+      taps: Vec::new(),
+      filters: Vec::new(),
+      pans: Vec::new(),
+      output: T::zero()
+    }
+  }
+
+  /// Adds a tap, and returns its index.
+  ///
+  /// The delay of the tap is clipped to the maximum delay.
+  pub fn add_tap(&mut self, tap: Tap<T>) -> usize {
+    self.filters.push(tap.filter());
+    self.pans.push(tap.pan_gains());
+    self.taps.push(tap);
+    self.taps.len() - 1
+  }
+
+  /// Removes and returns the tap at `index`, which moves the following taps
+  /// down by one.
+  pub fn remove_tap(&mut self, index: usize) -> Tap<T> {
+    self.filters.remove(index);
+    self.pans.remove(index);
+    self.taps.remove(index)
+  }
+
+  /// Replaces the tap at `index`, keeping the state of its damping filter.
+  pub fn set_tap(&mut self, index: usize, tap: Tap<T>) {
+    self.filters[index].set_coefficients(T::one() - tap.damping, -tap.damping);
+    self.pans[index] = tap.pan_gains();
+    self.taps[index] = tap;
+  }
+
+  /// Returns the taps.
+  pub fn taps(&self) -> &[Tap<T>] {
+    &self.taps
+  }
+
+  /// Removes all taps.
+  pub fn clear_taps(&mut self) {
+    self.taps.clear();
+    self.filters.clear();
+    self.pans.clear();
+  }
+
+  /// Returns the taps as a `Pattern`.
+  pub fn pattern(&self) -> Pattern<T> {
+    Pattern { taps: self.taps.clone() }
+  }
+
+  /// Replaces all taps with those of `pattern`.
+  pub fn set_pattern(&mut self, pattern: &Pattern<T>) {
+    self.clear_taps();
+    for tap in pattern.taps.iter() {
+      self.add_tap(*tap);
+    }
+  }
+
+  /// Returns the maximum delay of a tap, in samples.
+  pub fn get_max_delay(&self) -> usize {
+    self.delay_line.get_max_delay()
+  }
+
+  /// Set the maximum delay of a tap, in samples.
+  pub fn set_max_delay(&mut self, delay: usize) {
+    self.delay_line.set_max_delay(delay);
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right sums of the panned taps.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    self.delay_line.write(sample);
+    let mut output = (T::zero(), T::zero());
+    let mut mono = T::zero();
+    for ((tap, filter), &(left, right)) in self.taps.iter().zip(self.filters.iter_mut()).zip(self.pans.iter()) {
+      let value = filter.process(self.delay_line.tap_out_fractional(tap.delay)) * tap.gain;
+      output = (output.0 + value * left, output.1 + value * right);
+      mono = mono + value;
+    }
+    self.output = mono;
+    output
+  }
+}
+
+impl<T> Processor<T> for MultiTapDelay<T> where T: Float + FloatConst {
+  /// Processes and stores input sample into memory, and outputs the sum of
+  /// the taps, without panning.
+  fn process(&mut self, sample: T) -> T {
+    self.delay_line.write(sample);
+    let mut output = T::zero();
+    for (tap, filter) in self.taps.iter().zip(self.filters.iter_mut()) {
+      output = output + filter.process(self.delay_line.tap_out_fractional(tap.delay)) * tap.gain;
+    }
+    self.output = output;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.delay_line.clear();
+    for filter in self.filters.iter_mut() {
+      filter.clear();
+    }
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::EPSILON;
+  use ::traits::Processor;
+
+  #[test]
+  fn taps() {
+    let mut delay = MultiTapDelay::<f32>::new(100, Interpolation::Linear);
+    assert_eq!(delay.add_tap(Tap::new(3f32, 0.5f32, 0f32, 0f32)), 0);
+    assert_eq!(delay.add_tap(Tap::new(5.5f32, 0.25f32, 0f32, 0f32)), 1);
+
+    let output: Vec<f32> = (0..8).map(|n| delay.process(if n == 0 { 1f32 } else { 0f32 })).collect();
+    let expected = [0f32, 0f32, 0f32, 0.5f32, 0f32, 0.125f32, 0.125f32, 0f32];
+    for (actual, expected) in output.iter().zip(expected.iter()) {
+      assert!((actual - expected).abs() < EPSILON);
+    }
+
+    // Removing a tap keeps the delayed samples
+    delay.remove_tap(0);
+    delay.process(1f32);
+    assert_eq!(delay.taps().len(), 1);
+    for _ in 0..4 {
+      delay.process(0f32);
+    }
+    assert!((delay.process(0f32) - 0.125f32).abs() < EPSILON);
+  }
+
+  #[test]
+  fn stereo() {
+    let mut delay = MultiTapDelay::<f64>::new(100, Interpolation::Lagrange3);
+    delay.add_tap(Tap::new(2f32, 1f64, -1f64, 0f64));
+    delay.add_tap(Tap::new(4f32, 1f64, 1f64, 0f64));
+    delay.add_tap(Tap::new(6f32, 1f64, 0f64, 0f64));
+
+    let output: Vec<(f64, f64)> = (0..8).map(|n| {
+      delay.process_stereo(if n == 0 { 1f64 } else { 0f64 })
+    }).collect();
+    assert!((output[2].0 - 1f64).abs() < 1e-12 && output[2].1.abs() < 1e-12);
+    assert!(output[4].0.abs() < 1e-12 && (output[4].1 - 1f64).abs() < 1e-12);
+    assert!((output[6].0 - 0.5f64.sqrt()).abs() < 1e-12);
+    assert!((output[6].1 - 0.5f64.sqrt()).abs() < 1e-12);
+    assert_eq!(delay.last_out(), 0f64);
+  }
+
+  #[test]
+  fn damping() {
+    // A damped tap has a unity gain at DC, and less at high frequencies
+    let mut delay = MultiTapDelay::<f64>::new(100, Interpolation::Linear);
+    delay.add_tap(Tap::new(10f32, 1f64, 0f64, 0.5f64));
+    let mut dc = 0f64;
+    let mut nyquist = 0f64;
+    for _ in 0..200 {
+      dc = delay.process(1f64);
+    }
+    delay.clear();
+    for n in 0..200 {
+      nyquist = delay.process(if n % 2 == 1 { -1f64 } else { 1f64 }).abs();
+    }
+    assert!((dc - 1f64).abs() < 1e-9);
+    assert!((nyquist - 1f64 / 3f64).abs() < 1e-9);
+
+    // Changing the damping keeps the filter state
+    let input = |n: usize| if n % 2 == 1 { -1f64 } else { 1f64 };
+    let mut filter = OnePole::new();
+    filter.set_coefficients(0.5f64, -0.5f64);
+    delay.clear();
+    for n in 0..200 {
+      delay.process(input(n));
+      filter.process(if n >= 10 { input(n - 10) } else { 0f64 });
+    }
+    delay.set_tap(0, Tap::new(10f32, 1f64, 0f64, 0.25f64));
+    filter.set_coefficients(0.75f64, -0.25f64);
+    for n in 200..210 {
+      assert!((delay.process(input(n)) - filter.process(input(n - 10))).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn pattern() {
+    let mut delay = MultiTapDelay::<f32>::new(48_000, Interpolation::Hermite);
+    delay.add_tap(Tap::new(4_410f32, 0.7f32, -0.5f32, 0f32));
+    delay.add_tap(Tap::new(8_820.5f32, 0.5f32, 0.5f32, 0.2f32));
+
+    let pattern = delay.pattern().to_string();
+    assert_eq!(pattern, "4410 0.7 -0.5 0; 8820.5 0.5 0.5 0.2");
+
+    let mut other = MultiTapDelay::<f32>::new(48_000, Interpolation::Hermite);
+    other.set_pattern(&pattern.parse().unwrap());
+    assert_eq!(other.taps(), delay.taps());
+
+    assert_eq!("".parse::<Pattern<f32>>().unwrap().taps.len(), 0);
+    assert_eq!("1 1 0 0; 2 1 0".parse::<Pattern<f32>>(), Err(ParsePatternError { tap: 1 }));
+    assert_eq!("1 x 0 0".parse::<Pattern<f64>>(), Err(ParsePatternError { tap: 0 }));
+    assert_eq!("1 1 0 0;".parse::<Pattern<f32>>().unwrap().taps.len(), 1);
+    assert_eq!("; 1 1 0 0".parse::<Pattern<f32>>(), Err(ParsePatternError { tap: 0 }));
+  }
+}
@@ -0,0 +1,212 @@
+use num;
+use num::traits::Float;
+
+use delay::{Interpolation, InterpolatingDelay};
+use effects::{Lfo, Waveform};
+use traits::{FloatConst, FractionalDelayLine, Processor};
+
+/// The longest delay of a voice, in seconds.
+const MAX_DELAY: f64 = 0.1;
+
+/// A multi-voice chorus.
+///
+/// Each voice reads the input from a shared delay line, at a delay swept by
+/// its own phase of the oscillator, so the voices detune against each
+/// other. In stereo, the voices are spread from left to right.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::{Chorus, Waveform};
+///
+/// let mut chorus = Chorus::new(3, 44_100f32);
+/// chorus.set_delay(0.02f32);
+/// chorus.set_depth(0.004f32);
+/// chorus.set_rate(0.6f32);
+/// chorus.set_waveform(Waveform::Triangle);
+/// chorus.set_mix(0.4f32);
+///
+/// let (left, right) = chorus.process_stereo(1f32);
+/// let output = chorus.process(0f32);
+/// # assert_eq!((left, right), (0.6f32, 0.6f32));
+/// # assert_eq!(output, 0f32);
+/// ```
+pub struct Chorus<T> {
+  sample_rate: T,
+  delay_line: InterpolatingDelay<T>,
+  lfo: Lfo<T>,
+  voices: usize,
+  /// The center delay, and the depth of the sweep around it, in samples
+  delay: T,
+  depth: T,
+  mix: T,
+  output: T
+}
+
+impl<T> Chorus<T> where T: Float + FloatConst {
+  /// Creates a new `Chorus` with `voices` voices, at least one.
+  ///
+  /// It has a delay of 15 ms, a depth of 3 ms, a rate of 0.8 Hz, a sine
+  /// waveform, and a mix of 0.5.
+  pub fn new(voices: usize, sample_rate: T) -> Self {
+    let max_delay: usize = num::cast((sample_rate * num::cast(MAX_DELAY).unwrap()).ceil()).unwrap();
+    let mut chorus = Chorus {
+      sample_rate: sample_rate,
+      delay_line: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      lfo: Lfo::new(Waveform::Sine, num::cast(0.8f64).unwrap(), sample_rate),
+      voices: voices.max(1),
+      delay: T::zero(),
+      depth: T::zero(),
+      mix: num::cast(0.5f64).unwrap(),
+      output: T::zero()
+    };
+    chorus.set_delay(num::cast(0.015f64).unwrap());
+    chorus.set_depth(num::cast(0.003f64).unwrap());
+    chorus
+  }
+
+  /// Returns the number of voices.
+  pub fn voices(&self) -> usize {
+    self.voices
+  }
+
+  /// Sets the number of voices, at least one.
+  pub fn set_voices(&mut self, voices: usize) {
+    self.voices = voices.max(1);
+  }
+
+  /// Sets the center delay of the voices, in seconds.
+  ///
+  /// The delay plus the depth is at most 100 ms.
+  pub fn set_delay(&mut self, delay: T) {
+    self.delay = delay * self.sample_rate;
+  }
+
+  /// Sets the depth of the sweep around the delay, in seconds.
+  pub fn set_depth(&mut self, depth: T) {
+    self.depth = depth * self.sample_rate;
+  }
+
+  /// Sets the rate of the sweep, in hertz.
+  pub fn set_rate(&mut self, rate: T) {
+    self.lfo.set_rate(rate);
+  }
+
+  /// Sets the waveform of the sweep.
+  pub fn set_waveform(&mut self, waveform: Waveform) {
+    self.lfo.set_waveform(waveform);
+  }
+
+  /// Sets the mix of the voices with the input, from 0, which is only the
+  /// input, to 1, which is only the voices.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right channels.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    self.delay_line.write(sample);
+
+    let voices: T = num::cast(self.voices).unwrap();
+    let mut left = T::zero();
+    let mut right = T::zero();
+    for voice in 0..self.voices {
+      let index: T = num::cast(voice).unwrap();
+      let offset = index / voices;
+      let delay = self.delay + self.depth * self.lfo.value(offset);
+      let output = self.delay_line.tap_out_fractional(num::cast(delay).unwrap());
+
+      // Constant-power pan, from left to right, or centered for one voice
+      let pan = if self.voices == 1 {
+        T::one() / T::two()
+      } else {
+        index / (voices - T::one())
+      };
+      let angle = pan * T::pi() / T::two();
+      left = left + output * angle.cos();
+      right = right + output * angle.sin();
+    }
+    self.lfo.advance();
+
+    // Constant-power panning puts each voice at -3 dB in the center
+    let scale = T::two().sqrt() / voices;
+    let dry = sample * (T::one() - self.mix);
+    let left = dry + left * scale * self.mix;
+    let right = dry + right * scale * self.mix;
+    self.output = (left + right) / T::two();
+    (left, right)
+  }
+}
+
+impl<T> Processor<T> for Chorus<T> where T: Float + FloatConst {
+  /// Processes and stores input sample into memory, and outputs the average
+  /// of the left and right channels.
+  fn process(&mut self, sample: T) -> T {
+    self.process_stereo(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.delay_line.clear();
+    self.lfo.reset();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+
+  #[test]
+  fn voice() {
+    // Without depth, a voice is the input, delayed
+    let mut chorus = Chorus::new(1, 1_000f64);
+    chorus.set_delay(0.01f64);
+    chorus.set_depth(0f64);
+    chorus.set_mix(1f64);
+    let input: Vec<f64> = (0..100).map(|n| (n as f64 * 0.1f64).sin()).collect();
+    for (n, x) in input.iter().enumerate() {
+      let output = chorus.process(*x);
+      let expected = if n >= 10 { input[n - 10] } else { 0f64 };
+      assert!((output - expected).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn mix() {
+    let mut chorus = Chorus::new(4, 44_100f64);
+    chorus.set_mix(0f64);
+    for n in 0..1_000 {
+      let x = (n as f64 * 0.05f64).sin();
+      assert_eq!(chorus.process_stereo(x), (x, x));
+    }
+  }
+
+  #[test]
+  fn stereo() {
+    // The voices are spread, and sweep at different phases
+    let mut chorus = Chorus::new(3, 44_100f64);
+    chorus.set_mix(1f64);
+    assert_eq!(chorus.voices(), 3);
+    let mut difference = 0f64;
+    for n in 0..10_000 {
+      let (left, right) = chorus.process_stereo((n as f64 * 0.05f64).sin());
+      difference += (left - right).abs();
+      assert!(left.abs() < 2f64 && right.abs() < 2f64);
+    }
+    assert!(difference > 1f64);
+
+    chorus.clear();
+    assert_eq!(chorus.last_out(), 0f64);
+    chorus.set_voices(0);
+    assert_eq!(chorus.voices(), 1);
+    assert_eq!(chorus.process_stereo(0f64), (0f64, 0f64));
+  }
+}
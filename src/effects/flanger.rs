@@ -0,0 +1,214 @@
+use num;
+use num::traits::Float;
+
+use delay::{Interpolation, InterpolatingDelay};
+use effects::{Lfo, Waveform};
+use traits::{FloatConst, FractionalDelayLine, Processor};
+
+/// The longest delay, in seconds.
+const MAX_DELAY: f64 = 0.05;
+
+/// A flanger, with feedback.
+///
+/// The input is mixed with a copy of itself, delayed by a few milliseconds
+/// swept by the oscillator, which is also fed back into the delay line.
+///
+/// In through-zero mode, the input is also delayed, by `delay + depth`, so
+/// the swept copy passes in front of the input, and back.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::Flanger;
+///
+/// let mut flanger = Flanger::new(44_100f32);
+/// flanger.set_depth(0.003f32);
+/// flanger.set_rate(0.1f32);
+/// flanger.set_feedback(-0.7f32);
+/// flanger.set_through_zero(true);
+///
+/// let output = flanger.process(1f32);
+/// # assert_eq!(output, 0f32);
+/// ```
+pub struct Flanger<T> {
+  sample_rate: T,
+  delay_line: InterpolatingDelay<T>,
+  lfo: Lfo<T>,
+  /// The shortest delay, and the width of the sweep above it, in samples
+  delay: T,
+  depth: T,
+  feedback: T,
+  mix: T,
+  through_zero: bool,
+  /// The last output of the swept copy
+  wet: T,
+  output: T
+}
+
+impl<T> Flanger<T> where T: Float + FloatConst {
+  /// Creates a new `Flanger`.
+  ///
+  /// It has a delay of 0.5 ms, a depth of 2 ms, a rate of 0.25 Hz, a
+  /// triangle waveform, no feedback, and a mix of 0.5.
+  pub fn new(sample_rate: T) -> Self {
+    let max_delay: usize = num::cast((sample_rate * num::cast(MAX_DELAY).unwrap()).ceil()).unwrap();
+    let mut flanger = Flanger {
+      sample_rate: sample_rate,
+      delay_line: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      lfo: Lfo::new(Waveform::Triangle, num::cast(0.25f64).unwrap(), sample_rate),
+      delay: T::zero(),
+      depth: T::zero(),
+      feedback: T::zero(),
+      mix: num::cast(0.5f64).unwrap(),
+      through_zero: false,
+      wet: T::zero(),
+      output: T::zero()
+    };
+    flanger.set_delay(num::cast(0.000_5f64).unwrap());
+    flanger.set_depth(num::cast(0.002f64).unwrap());
+    flanger
+  }
+
+  /// Sets the shortest delay of the sweep, in seconds.
+  ///
+  /// The delay plus twice the depth is at most 50 ms.
+  pub fn set_delay(&mut self, delay: T) {
+    self.delay = delay * self.sample_rate;
+  }
+
+  /// Sets the depth of the sweep, in seconds, which is half its width.
+  pub fn set_depth(&mut self, depth: T) {
+    self.depth = depth * self.sample_rate;
+  }
+
+  /// Sets the rate of the sweep, in hertz.
+  pub fn set_rate(&mut self, rate: T) {
+    self.lfo.set_rate(rate);
+  }
+
+  /// Sets the waveform of the sweep.
+  pub fn set_waveform(&mut self, waveform: Waveform) {
+    self.lfo.set_waveform(waveform);
+  }
+
+  /// Sets the gain of the feedback, between -1 and 1.
+  ///
+  /// A negative feedback moves the resonances to between the notches of a
+  /// positive feedback.
+  pub fn set_feedback(&mut self, feedback: T) {
+    self.feedback = feedback;
+  }
+
+  /// Sets the mix of the swept copy with the input, from 0, which is only
+  /// the input, to 1, which is only the swept copy.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+
+  /// Sets the through-zero mode.
+  pub fn set_through_zero(&mut self, through_zero: bool) {
+    self.through_zero = through_zero;
+  }
+
+  /// Returns `true` in through-zero mode.
+  pub fn is_through_zero(&self) -> bool {
+    self.through_zero
+  }
+}
+
+impl<T> Processor<T> for Flanger<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    self.delay_line.write(sample + self.wet * self.feedback);
+
+    let delay = self.delay + self.depth * (T::one() + self.lfo.value(T::zero()));
+    self.lfo.advance();
+    self.wet = self.delay_line.tap_out_fractional(num::cast(delay).unwrap());
+
+    let dry = if self.through_zero {
+      self.delay_line.tap_out_fractional(num::cast(self.delay + self.depth).unwrap())
+    } else {
+      sample
+    };
+    self.output = dry * (T::one() - self.mix) + self.wet * self.mix;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.delay_line.clear();
+    self.lfo.reset();
+    self.wet = T::zero();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sine(n: usize) -> f64 {
+    (n as f64 * 0.1f64).sin()
+  }
+
+  #[test]
+  fn delay() {
+    // Without depth, the swept copy is the input, delayed
+    let mut flanger = Flanger::new(1_000f64);
+    flanger.set_delay(0.005f64);
+    flanger.set_depth(0f64);
+    flanger.set_mix(1f64);
+    for n in 0..100 {
+      let expected = if n >= 5 { sine(n - 5) } else { 0f64 };
+      assert!((flanger.process(sine(n)) - expected).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn through_zero() {
+    // The input is delayed by the center of the sweep
+    let mut flanger = Flanger::new(1_000f64);
+    flanger.set_delay(0.001f64);
+    flanger.set_depth(0.003f64);
+    flanger.set_mix(0f64);
+    flanger.set_through_zero(true);
+    assert!(flanger.is_through_zero());
+    for n in 0..100 {
+      let expected = if n >= 4 { sine(n - 4) } else { 0f64 };
+      assert!((flanger.process(sine(n)) - expected).abs() < 1e-12);
+    }
+
+    // Where the copy passes the input, they add without any comb filtering
+    let mut flanger = Flanger::new(1_000f64);
+    flanger.set_delay(0f64);
+    flanger.set_depth(0.01f64);
+    flanger.set_rate(0f64);
+    flanger.set_through_zero(true);
+    let output = (0..100).fold(0f64, |output, n| output.max(flanger.process(sine(n)).abs()));
+    assert!((output - 1f64).abs() < 1e-2);
+  }
+
+  #[test]
+  fn feedback() {
+    // Feedback rings after the input stops, and decays
+    let mut flanger = Flanger::new(44_100f64);
+    flanger.set_feedback(0.9f64);
+    flanger.set_mix(1f64);
+    let mut peak = 0f64;
+    for n in 0..20_000 {
+      let output = flanger.process(if n == 0 { 1f64 } else { 0f64 });
+      assert!(output.abs() <= 1f64);
+      if n > 10_000 {
+        peak = peak.max(output.abs());
+      }
+    }
+    assert!(peak > 0f64 && peak < 1e-3);
+
+    flanger.clear();
+    assert_eq!(flanger.last_out(), 0f64);
+    assert_eq!(flanger.process(0f64), 0f64);
+  }
+}
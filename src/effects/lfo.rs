@@ -0,0 +1,104 @@
+use num;
+use num::traits::Float;
+
+use traits::FloatConst;
+
+/// The shape of a low-frequency oscillator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Waveform {
+  /// A sine, which lingers at its extremes
+  Sine,
+  /// A triangle, which sweeps at a constant speed
+  Triangle
+}
+
+/// A low-frequency oscillator, from -1 to 1.
+pub struct Lfo<T> {
+  waveform: Waveform,
+  /// The phase, from 0 to 1, and its increment for each sample
+  phase: T,
+  increment: T,
+  sample_rate: T
+}
+
+impl<T> Lfo<T> where T: Float + FloatConst {
+  /// Creates a new `Lfo`, which starts at zero, and rises.
+  pub fn new(waveform: Waveform, rate: T, sample_rate: T) -> Self {
+    Lfo {
+      waveform: waveform,
+      phase: T::zero(),
+      increment: rate / sample_rate,
+      sample_rate: sample_rate
+    }
+  }
+
+  /// Sets the waveform.
+  pub fn set_waveform(&mut self, waveform: Waveform) {
+    self.waveform = waveform;
+  }
+
+  /// Sets the rate, in hertz.
+  pub fn set_rate(&mut self, rate: T) {
+    self.increment = rate / self.sample_rate;
+  }
+
+  /// Returns the value at `offset` cycles from the current phase.
+  pub fn value(&self, offset: T) -> T {
+    let phase = self.phase + offset;
+    let phase = phase - phase.floor();
+    match self.waveform {
+      Waveform::Sine => (T::two() * T::pi() * phase).sin(),
+      Waveform::Triangle => {
+        // Rises from 0 to 1 in the first quarter, and so on
+        let quarter: T = num::cast(0.25f64).unwrap();
+        let four = T::two() * T::two();
+        if phase < quarter {
+          four * phase
+        } else if phase < T::one() - quarter {
+          T::two() - four * phase
+        } else {
+          four * phase - four
+        }
+      }
+    }
+  }
+
+  /// Advances the oscillator by one sample.
+  pub fn advance(&mut self) {
+    self.phase = self.phase + self.increment;
+    if self.phase >= T::one() {
+      self.phase = self.phase - T::one();
+    }
+  }
+
+  /// Resets the phase to zero.
+  pub fn reset(&mut self) {
+    self.phase = T::zero();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn waveforms() {
+    let mut lfo = Lfo::new(Waveform::Triangle, 1f64, 8f64);
+    let expected = [0f64, 0.5f64, 1f64, 0.5f64, 0f64, -0.5f64, -1f64, -0.5f64, 0f64];
+    for value in expected.iter() {
+      assert!((lfo.value(0f64) - value).abs() < 1e-12);
+      lfo.advance();
+    }
+
+    lfo.set_waveform(Waveform::Sine);
+    lfo.reset();
+    assert!((lfo.value(0.25f64) - 1f64).abs() < 1e-12);
+    assert!((lfo.value(1.75f64) + 1f64).abs() < 1e-12);
+
+    lfo.set_rate(2f64);
+    for _ in 0..3 {
+      lfo.advance();
+    }
+    assert!((lfo.value(0f64) + 1f64).abs() < 1e-12);
+  }
+}
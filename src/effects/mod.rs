@@ -0,0 +1,66 @@
+//! Modulation effects.
+//!
+//! - `Chorus` mixes the input with several voices, each delayed by a slowly
+//!   swept delay, and spreads them in stereo
+//! - `Flanger` mixes the input with one copy, swept over a few milliseconds,
+//!   with feedback, and a through-zero mode
+//! - `Phaser` mixes the input with its output through a chain of swept
+//!   all-pass stages, with feedback
+//! - `PitchShifter` shifts the pitch of the input, with crossfaded read
+//!   heads over a delay line, and an optional correlation splice
+//! - `PingPongDelay` bounces echoes between the left and right channels,
+//!   with delay times that can be synced to a tempo with a `Division`
+//! - `TapeEcho` repeats the input through a saturating, filtered feedback
+//!   path, with wow and flutter
+//!
+//! The modulation effects are swept by a low-frequency oscillator, with a
+//! `Waveform`.
+
+/* Notes on the effects
+  - Times are in seconds, rates and frequencies in hertz, so the sample rate
+    is passed on creation
+  - The mix is from 0, which is only the input, to 1, which is only the
+    effect, since the effects are usually used as inserts
+  - The delays are read with Hermite interpolation, which is smoother than
+    linear interpolation when the delay is swept
+  - The echoes read their delay lines before writing them, a sample earlier
+    than the delay time, so an echo is exactly one delay time after its
+    source, and the feedback does not add a sample to each repeat
+  - A new delay time is approached by a one-pole glide of the read position,
+    rather than a jump, so the echoes bend in pitch, like a tape machine
+    changing speed, instead of clicking; the reads are continuous, and
+    bypass the crossfade of `InterpolatingDelay::process_modulated`, which
+    would only smear a glide
+*/
+
+mod chorus;
+mod flanger;
+mod lfo;
+mod phaser;
+mod ping_pong;
+mod pitch_shifter;
+mod tape_echo;
+
+pub use self::chorus::Chorus              as Chorus;
+pub use self::flanger::Flanger            as Flanger;
+pub use self::lfo::Waveform               as Waveform;
+pub use self::phaser::Phaser              as Phaser;
+pub use self::ping_pong::Division         as Division;
+pub use self::ping_pong::PingPongDelay    as PingPongDelay;
+pub use self::pitch_shifter::PitchShifter as PitchShifter;
+pub use self::tape_echo::TapeEcho         as TapeEcho;
+
+use num::traits::Float;
+
+use self::lfo::Lfo;
+
+/// Returns the coefficient of a one-pole glide with a time constant of
+/// `time` seconds.
+fn glide_coefficient<T: Float>(time: T, sample_rate: T) -> T {
+  let samples = time * sample_rate;
+  if samples <= T::one() {
+    T::one()
+  } else {
+    T::one() - (-samples.recip()).exp()
+  }
+}
@@ -0,0 +1,246 @@
+use num;
+use num::traits::Float;
+
+use effects::{Lfo, Waveform};
+use traits::{FloatConst, Processor};
+
+/* Notes on the phaser
+  - The stages are first-order all-pass filters, rather than
+    `rbj::AllPass`, since their coefficient changes every sample, and a
+    biquad would cost twice as much, and clears its state when set
+  - A stage `(a + z^-1) / (1 + a*z^-1)` with `a = (K - 1) / (K + 1)` and
+    `K = tan(pi*f/fs)` shifts the phase by -90 degrees at `f`, so `N`
+    stages, mixed equally with the input, have a notch wherever their phase
+    is an odd multiple of -180 degrees, which is `N/2` notches
+  - The frequency is swept exponentially between the two ends of the range,
+    so that the sweep sounds even
+*/
+
+/// A first-order all-pass filter.
+struct Stage<T> {
+  coefficient: T,
+  x1: T,
+  y1: T
+}
+
+impl<T> Stage<T> where T: Float {
+  fn new() -> Self {
+    Stage {
+      coefficient: T::zero(),
+      x1: T::zero(),
+      y1: T::zero()
+    }
+  }
+
+  fn process(&mut self, sample: T) -> T {
+    let output = self.coefficient * (sample - self.y1) + self.x1;
+    self.x1 = sample;
+    self.y1 = output;
+    output
+  }
+
+  fn clear(&mut self) {
+    self.x1 = T::zero();
+    self.y1 = T::zero();
+  }
+}
+
+/// A phaser, with feedback.
+///
+/// The input is mixed with its output through a chain of all-pass stages,
+/// whose frequency is swept by the oscillator, which gives notches that
+/// move up and down the spectrum.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::{Phaser, Waveform};
+///
+/// let mut phaser = Phaser::new(6, 44_100f32);
+/// phaser.set_range(200f32, 4_000f32);
+/// phaser.set_rate(0.3f32);
+/// phaser.set_waveform(Waveform::Triangle);
+/// phaser.set_feedback(0.6f32);
+///
+/// let output = phaser.process(1f32);
+/// # assert!(output.abs() < 1f32);
+/// ```
+pub struct Phaser<T> {
+  sample_rate: T,
+  stages: Vec<Stage<T>>,
+  lfo: Lfo<T>,
+  /// The lowest frequency of the sweep, and the ratio of the highest to it
+  low: T,
+  ratio: T,
+  feedback: T,
+  mix: T,
+  output: T
+}
+
+impl<T> Phaser<T> where T: Float + FloatConst {
+  /// Creates a new `Phaser` with `stages` all-pass stages, an even number
+  /// from 2 to 24.
+  ///
+  /// It sweeps from 300 Hz to 3 kHz at 0.5 Hz, with a sine waveform, no
+  /// feedback, and a mix of 0.5.
+  pub fn new(stages: usize, sample_rate: T) -> Self {
+    let mut phaser = Phaser {
+      sample_rate: sample_rate,
+      stages: Vec::new(),
+      lfo: Lfo::new(Waveform::Sine, num::cast(0.5f64).unwrap(), sample_rate),
+      low: T::zero(),
+      ratio: T::one(),
+      feedback: T::zero(),
+      mix: num::cast(0.5f64).unwrap(),
+      output: T::zero()
+    };
+    phaser.set_stages(stages);
+    phaser.set_range(num::cast(300f64).unwrap(), num::cast(3_000f64).unwrap());
+    phaser
+  }
+
+  /// Returns the number of all-pass stages.
+  pub fn stages(&self) -> usize {
+    self.stages.len()
+  }
+
+  /// Sets the number of all-pass stages, an even number from 2 to 24.
+  pub fn set_stages(&mut self, stages: usize) {
+    debug_assert!(stages >= 2 && stages & 1 == 0);
+    debug_assert!(stages <= 24);
+    self.stages.truncate(stages);
+    while self.stages.len() < stages {
+      self.stages.push(Stage::new());
+    }
+  }
+
+  /// Sets the lowest and highest frequencies of the sweep, in hertz.
+  ///
+  /// The frequencies are clipped below the Nyquist frequency.
+  pub fn set_range(&mut self, low: T, high: T) {
+    let limit = self.sample_rate / T::two() * num::cast(0.95f64).unwrap();
+    self.low = low.min(limit);
+    self.ratio = high.min(limit) / self.low;
+  }
+
+  /// Sets the rate of the sweep, in hertz.
+  pub fn set_rate(&mut self, rate: T) {
+    self.lfo.set_rate(rate);
+  }
+
+  /// Sets the waveform of the sweep.
+  pub fn set_waveform(&mut self, waveform: Waveform) {
+    self.lfo.set_waveform(waveform);
+  }
+
+  /// Sets the gain of the feedback, between -1 and 1, which sharpens the
+  /// notches.
+  pub fn set_feedback(&mut self, feedback: T) {
+    self.feedback = feedback;
+  }
+
+  /// Sets the mix of the all-pass chain with the input, from 0, which is only
+  /// the input, to 1, which is only the chain; the notches are deepest at
+  /// 0.5.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+}
+
+impl<T> Processor<T> for Phaser<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    let position = (self.lfo.value(T::zero()) + T::one()) / T::two();
+    self.lfo.advance();
+    let frequency = self.low * self.ratio.powf(position);
+    let k = (T::pi() * frequency / self.sample_rate).tan();
+    let coefficient = (k - T::one()) / (k + T::one());
+
+    let last = self.stages.last().map_or(T::zero(), |stage| stage.y1);
+    let input = sample + last * self.feedback;
+    let wet = self.stages.iter_mut().fold(input, |x, stage| {
+      stage.coefficient = coefficient;
+      stage.process(x)
+    });
+
+    self.output = sample * (T::one() - self.mix) + wet * self.mix;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for stage in self.stages.iter_mut() {
+      stage.clear();
+    }
+    self.lfo.reset();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::f64::consts::PI;
+
+  /// Returns the peak output for a sine at `frequency`, after it settles.
+  fn peak(phaser: &mut Phaser<f64>, frequency: f64) -> f64 {
+    (0..4_000).fold(0f64, |peak, n| {
+      let output = phaser.process((2f64 * PI * frequency * n as f64 / 8_000f64).sin());
+      if n < 2_000 { peak } else { peak.max(output.abs()) }
+    })
+  }
+
+  #[test]
+  fn allpass() {
+    // Without the input, the chain only shifts the phase
+    let mut phaser = Phaser::new(8, 8_000f64);
+    phaser.set_mix(1f64);
+    for frequency in [100f64, 700f64, 2_500f64].iter() {
+      assert!((peak(&mut phaser, *frequency) - 1f64).abs() < 1e-2);
+    }
+  }
+
+  #[test]
+  fn notch() {
+    // Two stages have a notch where each shifts the phase by -90 degrees
+    let mut phaser = Phaser::new(2, 8_000f64);
+    phaser.set_range(1_000f64, 1_000f64);
+    assert!(peak(&mut phaser, 1_000f64) < 1e-3);
+    assert!(peak(&mut phaser, 100f64) > 0.9f64);
+
+    // Four stages are in phase there, and have notches on either side, where
+    // each shifts the phase by -45 and -135 degrees
+    phaser.set_stages(4);
+    assert_eq!(phaser.stages(), 4);
+    assert!(peak(&mut phaser, 1_000f64) > 0.9f64);
+    let notch = 8_000f64 / PI * (3f64 - 2f64 * 2f64.sqrt()).atan();
+    assert!(peak(&mut phaser, notch) < 1e-3);
+    assert!(peak(&mut phaser, 2_000f64) < 1e-3);
+  }
+
+  #[test]
+  fn feedback() {
+    // Four stages are in phase at their frequency, so positive feedback
+    // resonates near there, above the level without feedback
+    let mut phaser = Phaser::new(4, 8_000f64);
+    phaser.set_range(500f64, 500f64);
+    let without = peak(&mut phaser, 500f64);
+    assert!((without - 1f64).abs() < 1e-2);
+    phaser.set_feedback(0.7f64);
+    let with = peak(&mut phaser, 500f64);
+    assert!(with > 1.5f64 * without);
+
+    phaser.set_rate(3f64);
+    phaser.set_range(100f64, 5_000f64);
+    for n in 0..10_000 {
+      assert!(phaser.process((n as f64 * 0.3f64).sin()).abs() < 10f64);
+    }
+    phaser.clear();
+    assert_eq!(phaser.last_out(), 0f64);
+    assert_eq!(phaser.process(0f64), 0f64);
+  }
+}
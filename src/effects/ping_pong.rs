@@ -0,0 +1,255 @@
+use num;
+use num::traits::Float;
+
+use delay::{Interpolation, InterpolatingDelay};
+use effects::glide_coefficient;
+use traits::{FloatConst, FractionalDelayLine, Processor};
+
+/// A note value, for delay times synced to a tempo.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Division {
+  /// Four beats
+  Whole,
+  /// Two beats
+  Half,
+  /// One beat
+  Quarter,
+  /// Half a beat
+  Eighth,
+  /// A quarter of a beat
+  Sixteenth,
+  /// Three beats
+  DottedHalf,
+  /// One and a half beats
+  DottedQuarter,
+  /// Three quarters of a beat
+  DottedEighth,
+  /// Two thirds of a beat
+  TripletQuarter,
+  /// A third of a beat
+  TripletEighth,
+  /// A sixth of a beat
+  TripletSixteenth
+}
+
+impl Division {
+  /// Returns the length of the note, in quarter-note beats.
+  pub fn beats(self) -> f64 {
+    match self {
+      Division::Whole            => 4.0,
+      Division::Half             => 2.0,
+      Division::Quarter          => 1.0,
+      Division::Eighth           => 0.5,
+      Division::Sixteenth        => 0.25,
+      Division::DottedHalf       => 3.0,
+      Division::DottedQuarter    => 1.5,
+      Division::DottedEighth     => 0.75,
+      Division::TripletQuarter   => 2.0 / 3.0,
+      Division::TripletEighth    => 1.0 / 3.0,
+      Division::TripletSixteenth => 1.0 / 6.0
+    }
+  }
+}
+
+/// A stereo delay, whose echoes bounce between the left and right channels.
+///
+/// The input feeds the left channel, each echo on the left is repeated on
+/// the right, and each echo on the right on the left, scaled by the
+/// feedback.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::{Division, PingPongDelay};
+///
+/// let mut delay = PingPongDelay::new(2f32, 44_100f32);
+/// delay.set_tempo(120f32, Division::DottedEighth);
+/// delay.set_feedback(0.6f32);
+/// delay.set_mix(0.3f32);
+///
+/// let (left, right) = delay.process_stereo(1f32);
+/// # assert_eq!((left, right), (0.7f32, 0.7f32));
+/// ```
+pub struct PingPongDelay<T> {
+  sample_rate: T,
+  left: InterpolatingDelay<T>,
+  right: InterpolatingDelay<T>,
+  /// The delay time, and its target, in samples
+  time: T,
+  target: T,
+  glide: T,
+  feedback: T,
+  mix: T,
+  output: T
+}
+
+impl<T> PingPongDelay<T> where T: Float + FloatConst {
+  /// Creates a new `PingPongDelay`, with delay times up to `max_time`
+  /// seconds.
+  ///
+  /// It has a delay time of 250 ms, a glide of 50 ms, a feedback of 0.5, and
+  /// a mix of 0.5.
+  pub fn new(max_time: T, sample_rate: T) -> Self {
+    let max_delay: usize = num::cast((max_time * sample_rate).ceil()).unwrap();
+    let mut delay = PingPongDelay {
+      sample_rate: sample_rate,
+      left: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      right: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      time: T::zero(),
+      target: T::zero(),
+      glide: T::one(),
+      feedback: num::cast(0.5f64).unwrap(),
+      mix: num::cast(0.5f64).unwrap(),
+      output: T::zero()
+    };
+    delay.set_time(num::cast(0.25f64).unwrap());
+    delay.time = delay.target;
+    delay.set_glide(num::cast(0.05f64).unwrap());
+    delay
+  }
+
+  /// Sets the delay time, in seconds, which is glided to.
+  pub fn set_time(&mut self, time: T) {
+    let max_delay: T = num::cast(self.left.get_max_delay()).unwrap();
+    self.target = (time * self.sample_rate).max(T::one()).min(max_delay);
+  }
+
+  /// Returns the delay time, in seconds, which may still be gliding.
+  pub fn time(&self) -> T {
+    self.time / self.sample_rate
+  }
+
+  /// Sets the delay time to a `division` of a beat at `tempo`, in beats per
+  /// minute.
+  pub fn set_tempo(&mut self, tempo: T, division: Division) {
+    let beats: T = num::cast(division.beats()).unwrap();
+    let minute: T = num::cast(60f64).unwrap();
+    self.set_time(beats * minute / tempo);
+  }
+
+  /// Sets the time constant of the glide to a new delay time, in seconds.
+  pub fn set_glide(&mut self, glide: T) {
+    self.glide = glide_coefficient(glide, self.sample_rate);
+  }
+
+  /// Sets the gain of each bounce, between -1 and 1.
+  pub fn set_feedback(&mut self, feedback: T) {
+    self.feedback = feedback;
+  }
+
+  /// Sets the mix of the echoes with the input, from 0, which is only the
+  /// input, to 1, which is only the echoes.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right channels.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    self.time = self.time + (self.target - self.time) * self.glide;
+    let tap: f32 = num::cast(self.time - T::one()).unwrap();
+
+    let left = self.left.tap_out_fractional(tap);
+    let right = self.right.tap_out_fractional(tap);
+    self.left.write(sample + right * self.feedback);
+    self.right.write(left * self.feedback);
+
+    let dry = sample * (T::one() - self.mix);
+    let left = dry + left * self.mix;
+    let right = dry + right * self.mix;
+    self.output = (left + right) / T::two();
+    (left, right)
+  }
+}
+
+impl<T> Processor<T> for PingPongDelay<T> where T: Float + FloatConst {
+  /// Processes and stores input sample into memory, and outputs the average
+  /// of the left and right channels.
+  fn process(&mut self, sample: T) -> T {
+    self.process_stereo(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.left.clear();
+    self.right.clear();
+    self.time = self.target;
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+
+  #[test]
+  fn bounces() {
+    let mut delay = PingPongDelay::new(1f64, 1_000f64);
+    delay.set_time(0.01f64);
+    delay.clear();
+    delay.set_mix(1f64);
+    for n in 0..50 {
+      let (left, right) = delay.process_stereo(if n == 0 { 1f64 } else { 0f64 });
+      let (expected_left, expected_right) = match n {
+        10 => (1f64, 0f64),
+        20 => (0f64, 0.5f64),
+        30 => (0.25f64, 0f64),
+        40 => (0f64, 0.125f64),
+        _ => (0f64, 0f64)
+      };
+      assert!((left - expected_left).abs() < 1e-12);
+      assert!((right - expected_right).abs() < 1e-12);
+    }
+
+    delay.clear();
+    assert_eq!(delay.last_out(), 0f64);
+    assert_eq!(delay.process_stereo(0f64), (0f64, 0f64));
+  }
+
+  #[test]
+  fn tempo() {
+    let mut delay = PingPongDelay::new(4f64, 1_000f64);
+    delay.set_tempo(120f64, Division::DottedEighth);
+    delay.clear();
+    assert!((delay.time() - 0.375f64).abs() < 1e-12);
+    delay.set_tempo(90f64, Division::TripletQuarter);
+    delay.clear();
+    assert!((delay.time() - 4f64 / 9f64).abs() < 1e-12);
+    delay.set_tempo(10f64, Division::Whole);
+    delay.clear();
+    assert_eq!(delay.time(), 4f64);
+  }
+
+  #[test]
+  fn glide() {
+    // A change of the delay time bends the echoes, where a jump would click
+    let largest_step = |glide: f64| {
+      let mut delay = PingPongDelay::new(1f64, 8_000f64);
+      delay.set_glide(glide);
+      delay.set_mix(1f64);
+      let mut previous = (0f64, 0f64);
+      let mut largest_step = 0f64;
+      for n in 0..16_000 {
+        if n == 4_000 {
+          delay.set_time(0.13f64);
+        }
+        let output = delay.process_stereo((n as f64 * 0.05f64).sin());
+        if n > 3_000 {
+          largest_step = largest_step.max((output.0 - previous.0).abs());
+          largest_step = largest_step.max((output.1 - previous.1).abs());
+        }
+        previous = output;
+      }
+      assert!((delay.time() - 0.13f64).abs() < 1e-3);
+      largest_step
+    };
+    assert!(largest_step(0.2f64) < 0.1f64);
+    assert!(largest_step(0f64) > 0.3f64);
+  }
+}
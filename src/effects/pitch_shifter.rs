@@ -0,0 +1,440 @@
+use num;
+use num::traits::Float;
+
+use std::iter::StepBy;
+use std::ops::Range;
+
+use delay::{Interpolation, InterpolatingDelay};
+use traits::{FloatConst, FractionalDelayLine, Processor, TappableDelayLine};
+use window::{self, Window};
+
+/* Notes on the pitch shifter
+  - Each read head moves through the delay line at `ratio` times the speed
+    of the input, so its delay changes by `1 - ratio` samples every sample,
+    and it wraps around after sweeping over a grain
+  - The heads are spread evenly over the grain, and each is faded by the
+    window at its position in the grain, which is zero where it wraps, so
+    the jumps are silent
+  - Windows that are not zero at their ends, such as the rectangular and
+    Hamming windows, have the ends of their tables tapered by half a Hann
+    window over `TAPER` points each, which keeps the jumps silent
+  - The windows of the heads do not sum to one for every window and number
+    of heads, so the output is divided by their sum
+  - The window is tabulated once, from a symmetric window one point longer
+    than the table, whose last point is the first point of the next period
+  - With the correlation splice, a head that wraps is moved further back by
+    up to `SEARCH` seconds, to where the last `CORRELATION` seconds of the
+    input best match what the loudest head is reading, so the two heads are
+    in phase while they crossfade, which reduces the warbling of tonal input
+  - The correlation splice searches every `STRIDE`th offset, comparing
+    every `STRIDE`th sample, then every offset within `STRIDE` of the best
+    one, which costs about a twenty-fifth of a full search on the sample where
+    a head wraps
+*/
+
+/// The longest grain, in seconds.
+const MAX_GRAIN: f64 = 0.2;
+
+/// The longest distance the correlation splice moves a head, in seconds.
+const SEARCH: f64 = 0.015;
+
+/// The length of the input compared by the correlation splice, in seconds.
+const CORRELATION: f64 = 0.01;
+
+/// The spacing of the offsets and samples of the coarse correlation search.
+const STRIDE: usize = 8;
+
+/// The number of points of the window table.
+const TABLE: usize = 512;
+
+/// The number of points tapered at each end of the window table, for
+/// windows that are not zero there.
+const TAPER: usize = TABLE / 16;
+
+/// The shortest delay of a head, above which the delay line can be read
+/// with Hermite interpolation.
+const MIN_DELAY: f64 = 2.0;
+
+/// A pitch shifter, with crossfaded read heads over a delay line.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::PitchShifter;
+/// use rasp::window::Window;
+///
+/// let mut shifter = PitchShifter::new(2, 44_100f32);
+///
+/// // A fifth and a few cents down
+/// shifter.set_pitch(-7f32, -5f32);
+/// shifter.set_grain_size(0.06f32);
+/// shifter.set_window(Window::Hann);
+/// shifter.set_splice(true);
+///
+/// let output = shifter.process(1f32);
+/// # assert_eq!(output, 0f32);
+/// ```
+pub struct PitchShifter<T> {
+  sample_rate: T,
+  delay_line: InterpolatingDelay<T>,
+  ratio: T,
+  /// The grain size, in samples
+  grain: T,
+  window: Window,
+  table: Vec<T>,
+  /// The phase of the first head, from 0 to 1, and its change each sample
+  phase: T,
+  increment: T,
+  /// The phase of each head at the previous sample, and how far each head
+  /// is moved back by the splice, in samples
+  phases: Vec<T>,
+  offsets: Vec<T>,
+  splice: bool,
+  mix: T,
+  output: T
+}
+
+impl<T> PitchShifter<T> where T: Float + FloatConst {
+  /// Creates a new `PitchShifter` with `heads` read heads, at least two.
+  ///
+  /// It does not shift the pitch, and has a grain of 50 ms, a Hann window,
+  /// no correlation splice, and a mix of 1.
+  pub fn new(heads: usize, sample_rate: T) -> Self {
+    let rate: f64 = num::cast(sample_rate).unwrap();
+    let max_delay = ((MAX_GRAIN + SEARCH + CORRELATION) * rate).ceil() as usize + MIN_DELAY as usize + 1;
+    let heads = heads.max(2);
+
+    let mut shifter = PitchShifter {
+      sample_rate: sample_rate,
+      delay_line: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      ratio: T::one(),
+      grain: T::zero(),
+      window: Window::Hann,
+      table: Vec::new(),
+      phase: T::zero(),
+      increment: T::zero(),
+      phases: vec![T::zero(); heads],
+      offsets: vec![T::zero(); heads],
+      splice: false,
+      mix: T::one(),
+      output: T::zero()
+    };
+    shifter.set_window(Window::Hann);
+    shifter.set_grain_size(num::cast(0.05f64).unwrap());
+    shifter
+  }
+
+  /// Returns the number of read heads.
+  pub fn heads(&self) -> usize {
+    self.phases.len()
+  }
+
+  /// Sets the pitch shift, in semitones and cents.
+  pub fn set_pitch(&mut self, semitones: T, cents: T) {
+    let hundred: T = num::cast(100f64).unwrap();
+    let twelve: T = num::cast(12f64).unwrap();
+    self.ratio = T::two().powf((semitones + cents / hundred) / twelve);
+    self.update();
+  }
+
+  /// Returns the ratio of the output frequencies to the input frequencies.
+  pub fn ratio(&self) -> T {
+    self.ratio
+  }
+
+  /// Sets the grain size, in seconds, up to 200 ms.
+  ///
+  /// Longer grains warble less, but smear transients, and add latency.
+  pub fn set_grain_size(&mut self, grain: T) {
+    let max_grain: T = num::cast(MAX_GRAIN).unwrap();
+    self.grain = (grain.min(max_grain) * self.sample_rate).max(T::one());
+    self.update();
+  }
+
+  /// Sets the window of the crossfade between the heads.
+  ///
+  /// The ends of windows that are not zero there are tapered to zero, so a
+  /// head is silent where it wraps.
+  pub fn set_window(&mut self, window: Window) {
+    self.window = window;
+    self.table = vec![T::one(); TABLE + 1];
+    window::apply_window(&mut self.table, window);
+    if self.table[0] != T::zero() || self.table[TABLE] != T::zero() {
+      let taper: T = num::cast(TAPER).unwrap();
+      for n in 0..TAPER {
+        let angle = T::pi() * num::cast(n).unwrap() / taper;
+        let gain = (T::one() - angle.cos()) / T::two();
+        self.table[n] = self.table[n] * gain;
+        self.table[TABLE - n] = self.table[TABLE - n] * gain;
+      }
+    }
+  }
+
+  /// Returns the window of the crossfade between the heads.
+  pub fn window(&self) -> Window {
+    self.window
+  }
+
+  /// Sets whether a head that wraps is moved to where it best matches the
+  /// loudest head.
+  pub fn set_splice(&mut self, splice: bool) {
+    self.splice = splice;
+    if !splice {
+      for offset in self.offsets.iter_mut() {
+        *offset = T::zero();
+      }
+    }
+  }
+
+  /// Sets the mix of the shifted signal with the input, from 0, which is
+  /// only the input, to 1, which is only the shifted signal.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+
+  fn update(&mut self) {
+    self.increment = (T::one() - self.ratio) / self.grain;
+  }
+
+  /// Returns the phase of `head`.
+  fn head_phase(&self, head: usize) -> T {
+    let heads: T = num::cast(self.heads()).unwrap();
+    let index: T = num::cast(head).unwrap();
+    let phase = self.phase + index / heads;
+    phase - phase.floor()
+  }
+
+  /// Returns the delay of a head at `phase`, before its offset.
+  fn delay(&self, phase: T) -> T {
+    num::cast::<f64, T>(MIN_DELAY).unwrap() + phase * self.grain
+  }
+
+  /// Returns the window at `phase`.
+  fn gain(&self, phase: T) -> T {
+    let position = phase * num::cast(TABLE).unwrap();
+    let index: usize = num::cast(position.floor()).unwrap();
+    let index = index.min(TABLE - 1);
+    let fraction = position - num::cast(index).unwrap();
+    self.table[index] + (self.table[index + 1] - self.table[index]) * fraction
+  }
+
+  /// Returns how well the input at `delay` matches the input at `reference`,
+  /// comparing every `step`th of `length` samples.
+  fn score(&self, reference: usize, delay: usize, length: usize, step: usize) -> T {
+    let (correlation, energy) = (0..length).step_by(step).fold((T::zero(), T::zero()), |(c, e), i| {
+      let x = self.delay_line.tap_out(delay + i);
+      (c + x * self.delay_line.tap_out(reference + i), e + x * x)
+    });
+    if energy > T::zero() { correlation / energy.sqrt() } else { T::neg_infinity() }
+  }
+
+  /// Returns how far back from `delay` the input best matches the input at
+  /// `reference`.
+  fn best_offset(&self, reference: usize, delay: usize) -> usize {
+    let rate: f64 = num::cast(self.sample_rate).unwrap();
+    let search = (SEARCH * rate) as usize;
+    let length = ((CORRELATION * rate) as usize).max(1);
+
+    let best = |offsets: StepBy<Range<usize>>, step: usize| {
+      offsets.fold((0, T::neg_infinity()), |best, offset| {
+        let score = self.score(reference, delay + offset, length, step);
+        if score > best.1 { (offset, score) } else { best }
+      }).0
+    };
+    let coarse = best((0..search).step_by(STRIDE), STRIDE);
+    let end = (coarse + STRIDE).min(search);
+    best((coarse.saturating_sub(STRIDE)..end).step_by(1), 1)
+  }
+}
+
+impl<T> Processor<T> for PitchShifter<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    self.delay_line.write(sample);
+    self.phase = self.phase + self.increment;
+    self.phase = self.phase - self.phase.floor();
+
+    let half: T = num::cast(0.5f64).unwrap();
+    let mut shifted = T::zero();
+    let mut total = T::zero();
+    for head in 0..self.heads() {
+      let phase = self.head_phase(head);
+      let wrapped = (phase - self.phases[head]).abs() > half;
+      self.phases[head] = phase;
+
+      if wrapped && self.splice {
+        // The loudest head is the one nearest the middle of its grain
+        let loudest = (0..self.heads())
+          .filter(|other| *other != head)
+          .max_by(|a, b| {
+            let a = self.gain(self.head_phase(*a));
+            let b = self.gain(self.head_phase(*b));
+            a.partial_cmp(&b).unwrap()
+          })
+          .unwrap();
+        let other = self.head_phase(loudest);
+        let reference = self.delay(other) + self.offsets[loudest];
+        let offset = self.best_offset(num::cast(reference.round()).unwrap(),
+                                      num::cast(self.delay(phase).round()).unwrap());
+        self.offsets[head] = num::cast(offset).unwrap();
+      }
+
+      let gain = self.gain(phase);
+      let delay: f32 = num::cast(self.delay(phase) + self.offsets[head]).unwrap();
+      shifted = shifted + self.delay_line.tap_out_fractional(delay) * gain;
+      total = total + gain;
+    }
+    if total > T::zero() {
+      shifted = shifted / total;
+    }
+
+    self.output = sample * (T::one() - self.mix) + shifted * self.mix;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.delay_line.clear();
+    self.phase = T::zero();
+    for (phase, offset) in self.phases.iter_mut().zip(self.offsets.iter_mut()) {
+      *phase = T::zero();
+      *offset = T::zero();
+    }
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+  use test_util::amplitude;
+
+  use std::f64::consts::PI;
+
+  /// Returns the shifted output for a sine at `frequency`, relative to the
+  /// sample rate, after it settles.
+  fn shift(shifter: &mut PitchShifter<f64>, frequency: f64) -> Vec<f64> {
+    let output: Vec<f64> = (0..16_000).map(|n| {
+      shifter.process((2f64 * PI * frequency * n as f64).sin())
+    }).collect();
+    output[8_000..].to_vec()
+  }
+
+  /// Returns the standard deviation of the RMS of blocks of `signal`.
+  fn warble(signal: &[f64]) -> f64 {
+    let levels: Vec<f64> = signal.chunks(100).map(|block| {
+      (block.iter().fold(0f64, |energy, x| energy + x * x) / block.len() as f64).sqrt()
+    }).collect();
+    let mean = levels.iter().sum::<f64>() / levels.len() as f64;
+    (levels.iter().fold(0f64, |sum, level| sum + (level - mean).powi(2)) / levels.len() as f64).sqrt()
+  }
+
+  #[test]
+  fn ratio() {
+    let mut shifter = PitchShifter::<f64>::new(2, 44_100f64);
+    assert_eq!(shifter.ratio(), 1f64);
+    shifter.set_pitch(12f64, 0f64);
+    assert!((shifter.ratio() - 2f64).abs() < 1e-12);
+    shifter.set_pitch(-1f64, 100f64);
+    assert!((shifter.ratio() - 1f64).abs() < 1e-12);
+    shifter.set_pitch(0f64, -1_200f64);
+    assert!((shifter.ratio() - 0.5f64).abs() < 1e-12);
+  }
+
+  #[test]
+  fn unison() {
+    // Without a shift, the output is the input, delayed by half a grain
+    let mut shifter = PitchShifter::new(2, 1_000f64);
+    shifter.set_grain_size(0.02f64);
+    let input: Vec<f64> = (0..200).map(|n| (n as f64 * 0.1f64).sin()).collect();
+    for (n, x) in input.iter().enumerate() {
+      let output = shifter.process(*x);
+      let expected = if n >= 12 { input[n - 12] } else { 0f64 };
+      assert!((output - expected).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn shift_frequency() {
+    for &(semitones, heads) in [(12f64, 2), (-7f64, 3), (5f64, 4)].iter() {
+      let mut shifter = PitchShifter::new(heads, 8_000f64);
+      shifter.set_pitch(semitones, 0f64);
+      shifter.set_splice(true);
+      let output = shift(&mut shifter, 0.01f64);
+      let shifted = amplitude(&output, 0, 0.01f64 * shifter.ratio());
+      assert!(shifted > 0.5f64);
+      assert!(amplitude(&output, 0, 0.01f64) < 0.1f64 * shifted);
+    }
+  }
+
+  #[test]
+  fn splice() {
+    // Splicing in phase keeps the level of a shifted sine steadier
+    let mut plain = PitchShifter::new(2, 8_000f64);
+    plain.set_pitch(3f64, 0f64);
+    let mut spliced = PitchShifter::new(2, 8_000f64);
+    spliced.set_pitch(3f64, 0f64);
+    spliced.set_splice(true);
+    let plain = warble(&shift(&mut plain, 0.013f64));
+    let spliced = warble(&shift(&mut spliced, 0.013f64));
+    assert!(spliced < 0.2f64 * plain);
+  }
+
+  #[test]
+  fn windows() {
+    for window in [Window::Triangular, Window::Hamming, Window::Blackman].iter() {
+      let mut shifter = PitchShifter::new(3, 8_000f64);
+      shifter.set_window(*window);
+      assert_eq!(shifter.window(), *window);
+      shifter.set_pitch(-12f64, 0f64);
+      shifter.set_splice(true);
+      let output = shift(&mut shifter, 0.02f64);
+      assert!(amplitude(&output, 0, 0.01f64) > 0.5f64);
+      assert!(output.iter().all(|x| x.abs() < 1.5f64));
+
+      shifter.clear();
+      assert_eq!(shifter.last_out(), 0f64);
+      assert_eq!(shifter.process(0f64), 0f64);
+    }
+  }
+
+  #[test]
+  fn wrap() {
+    // Every window is silent at the ends, so a wrapping head does not step
+    let windows = [Window::Rectangular, Window::Triangular, Window::Bartlett, Window::Hann,
+                   Window::Hamming, Window::Blackman, Window::BlackmanHarris];
+    for window in windows.iter() {
+      let mut shifter = PitchShifter::new(2, 8_000f64);
+      shifter.set_window(*window);
+      assert_eq!(shifter.gain(0f64), 0f64);
+      assert_eq!(shifter.gain(1f64), 0f64);
+      shifter.set_pitch(-12f64, 0f64);
+      let output = shift(&mut shifter, 0.013f64);
+      let step = output.windows(2).fold(0f64, |step, pair| step.max((pair[1] - pair[0]).abs()));
+      println!("{:?}: {:.6}", window, step);
+      assert!(step < 0.1f64);
+    }
+  }
+
+  #[test]
+  fn coarse_search() {
+    // The coarse search then refinement finds the best offset of a full search
+    let mut shifter = PitchShifter::new(2, 44_100f64);
+    for n in 0..10_000 {
+      let n = n as f64;
+      shifter.process((2f64 * PI * 0.011f64 * n).sin() + 0.5f64 * (0.0037f64 * n).sin());
+    }
+    let (search, length) = ((SEARCH * 44_100f64) as usize, (CORRELATION * 44_100f64) as usize);
+    for &(reference, delay) in [(100usize, 1_000usize), (2_000, 500), (37, 3_001)].iter() {
+      let full = (0..search).fold((0, f64::NEG_INFINITY), |best, offset| {
+        let score = shifter.score(reference, delay + offset, length, 1);
+        if score > best.1 { (offset, score) } else { best }
+      });
+      assert_eq!(shifter.best_offset(reference, delay), full.0);
+    }
+  }
+}
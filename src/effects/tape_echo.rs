@@ -0,0 +1,292 @@
+use num;
+use num::traits::Float;
+
+use delay::{Interpolation, InterpolatingDelay};
+use effects::{glide_coefficient, Lfo, Waveform};
+use filter::rbj::{HighPass, LowPass};
+use traits::{FloatConst, FractionalDelayLine, Processor};
+
+/// The number of samples over which the tone filters glide.
+const TONE_GLIDE: usize = 64;
+
+/// A tape-style echo.
+///
+/// The echoes are read from a delay line whose read position wanders with a
+/// slow wow and a fast flutter, then saturated and filtered, like a worn
+/// tape, before they are output and fed back. The saturation bounds the
+/// echoes, so a feedback above one builds up without running away.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::effects::TapeEcho;
+///
+/// let mut echo = TapeEcho::new(1f32, 44_100f32);
+/// echo.set_time(0.35f32);
+/// echo.set_feedback(0.7f32);
+/// echo.set_drive(2f32);
+/// echo.set_tone(150f32, 3_000f32);
+/// echo.set_wow(0.002f32, 0.5f32);
+///
+/// let output = echo.process(1f32);
+/// # assert_eq!(output, 0.5f32);
+/// ```
+pub struct TapeEcho<T> {
+  sample_rate: T,
+  delay_line: InterpolatingDelay<T>,
+  /// The delay time, and its target, in samples
+  time: T,
+  target: T,
+  glide: T,
+  wow: Lfo<T>,
+  flutter: Lfo<T>,
+  /// The depths of the wow and flutter, in samples
+  wow_depth: T,
+  flutter_depth: T,
+  drive: T,
+  low_cut: HighPass<T>,
+  high_cut: LowPass<T>,
+  feedback: T,
+  mix: T,
+  output: T
+}
+
+impl<T> TapeEcho<T> where T: Float + FloatConst {
+  /// Creates a new `TapeEcho`, with delay times up to `max_time` seconds.
+  ///
+  /// It has a delay time of 300 ms, a glide of 200 ms, a wow of 1 ms at
+  /// 0.5 Hz, a flutter of 0.05 ms at 6 Hz, a drive of 1, a tone from 100 Hz
+  /// to 4 kHz, a feedback of 0.5, and a mix of 0.5.
+  pub fn new(max_time: T, sample_rate: T) -> Self {
+    let max_delay: usize = num::cast((max_time * sample_rate).ceil()).unwrap();
+    let mut echo = TapeEcho {
+      sample_rate: sample_rate,
+      delay_line: InterpolatingDelay::new(0f32, max_delay, Interpolation::Hermite),
+      time: T::zero(),
+      target: T::zero(),
+      glide: T::one(),
+      wow: Lfo::new(Waveform::Sine, T::zero(), sample_rate),
+      flutter: Lfo::new(Waveform::Sine, T::zero(), sample_rate),
+      wow_depth: T::zero(),
+      flutter_depth: T::zero(),
+      drive: T::one(),
+      low_cut: HighPass::new(),
+      high_cut: LowPass::new(),
+      feedback: num::cast(0.5f64).unwrap(),
+      mix: num::cast(0.5f64).unwrap(),
+      output: T::zero()
+    };
+    echo.low_cut.set_glide_length(TONE_GLIDE);
+    echo.high_cut.set_glide_length(TONE_GLIDE);
+
+    echo.set_time(num::cast(0.3f64).unwrap());
+    echo.time = echo.target;
+    echo.set_glide(num::cast(0.2f64).unwrap());
+    echo.set_wow(num::cast(0.001f64).unwrap(), num::cast(0.5f64).unwrap());
+    echo.set_flutter(num::cast(0.000_05f64).unwrap(), num::cast(6f64).unwrap());
+    echo.set_tone(num::cast(100f64).unwrap(), num::cast(4_000f64).unwrap());
+    echo
+  }
+
+  /// Sets the delay time, in seconds, which is glided to.
+  ///
+  /// The delay time, plus the depths of the wow and flutter, is at most the
+  /// maximum time, so a longer time is shortened by the depths.
+  pub fn set_time(&mut self, time: T) {
+    let max_delay: T = num::cast(self.delay_line.get_max_delay()).unwrap();
+    self.target = (time * self.sample_rate).max(T::one()).min(max_delay);
+  }
+
+  /// Returns the delay time, in seconds, which may still be gliding.
+  pub fn time(&self) -> T {
+    self.time / self.sample_rate
+  }
+
+  /// Sets the time constant of the glide to a new delay time, in seconds.
+  pub fn set_glide(&mut self, glide: T) {
+    self.glide = glide_coefficient(glide, self.sample_rate);
+  }
+
+  /// Sets the depth, in seconds, and rate, in hertz, of the slow wandering
+  /// of the delay time.
+  pub fn set_wow(&mut self, depth: T, rate: T) {
+    self.wow_depth = depth * self.sample_rate;
+    self.wow.set_rate(rate);
+  }
+
+  /// Sets the depth, in seconds, and rate, in hertz, of the fast wandering
+  /// of the delay time.
+  pub fn set_flutter(&mut self, depth: T, rate: T) {
+    self.flutter_depth = depth * self.sample_rate;
+    self.flutter.set_rate(rate);
+  }
+
+  /// Sets the drive of the saturation, above zero.
+  ///
+  /// Echoes are shaped by `tanh(drive*x)/drive`, which keeps quiet echoes at
+  /// the same level, and limits loud echoes to `1/drive`.
+  pub fn set_drive(&mut self, drive: T) {
+    self.drive = drive;
+  }
+
+  /// Sets the cutoff frequencies, in hertz, of the high-pass and low-pass
+  /// filters of the echoes, which glide to new values.
+  pub fn set_tone(&mut self, low_cut: T, high_cut: T) {
+    let q: T = num::cast(::std::f64::consts::FRAC_1_SQRT_2).unwrap();
+    self.low_cut.set_coefficients(self.sample_rate, low_cut, q);
+    self.high_cut.set_coefficients(self.sample_rate, high_cut, q);
+  }
+
+  /// Sets the gain of the feedback, from 0, above which the echoes build up
+  /// to the limit of the saturation.
+  pub fn set_feedback(&mut self, feedback: T) {
+    self.feedback = feedback;
+  }
+
+  /// Sets the mix of the echoes with the input, from 0, which is only the
+  /// input, to 1, which is only the echoes.
+  pub fn set_mix(&mut self, mix: T) {
+    self.mix = mix;
+  }
+
+  /// Returns the target delay time, in samples, shortened so that the wow
+  /// and flutter stay within the delay line.
+  fn limited_target(&self) -> T {
+    let max_delay: T = num::cast(self.delay_line.get_max_delay()).unwrap();
+    let longest = (max_delay - self.wow_depth.abs() - self.flutter_depth.abs()).max(T::one());
+    self.target.min(longest)
+  }
+}
+
+impl<T> Processor<T> for TapeEcho<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    self.time = self.time + (self.limited_target() - self.time) * self.glide;
+    let wander = self.wow_depth * self.wow.value(T::zero())
+               + self.flutter_depth * self.flutter.value(T::zero());
+    self.wow.advance();
+    self.flutter.advance();
+    let tap: f32 = num::cast(self.time + wander - T::one()).unwrap();
+
+    let echo = self.delay_line.tap_out_fractional(tap);
+    let echo = (echo * self.drive).tanh() / self.drive;
+    let echo = self.high_cut.process(self.low_cut.process(echo));
+    self.delay_line.write(sample + echo * self.feedback);
+
+    self.output = sample * (T::one() - self.mix) + echo * self.mix;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.delay_line.clear();
+    self.low_cut.clear();
+    self.high_cut.clear();
+    self.wow.reset();
+    self.flutter.reset();
+    self.time = self.limited_target();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+
+  /// Returns a `TapeEcho` with a delay of `time` seconds, without wow,
+  /// flutter, or feedback, and with a wide tone.
+  fn clean(time: f64) -> TapeEcho<f64> {
+    let mut echo = TapeEcho::new(1f64, 8_000f64);
+    echo.set_time(time);
+    echo.set_wow(0f64, 0f64);
+    echo.set_flutter(0f64, 0f64);
+    echo.set_tone(1f64, 3_900f64);
+    echo.set_feedback(0f64);
+    echo.set_drive(0.01f64);
+    echo.set_mix(1f64);
+    echo.clear();
+    echo
+  }
+
+  #[test]
+  fn echo() {
+    // The echo of a sine is the sine, delayed
+    let mut echo = clean(0.1f64);
+    let input: Vec<f64> = (0..4_000).map(|n| (n as f64 * 0.1f64).sin()).collect();
+    for (n, x) in input.iter().enumerate() {
+      let output = echo.process(*x);
+      if n > 2_000 {
+        assert!((output - input[n - 800]).abs() < 2e-2);
+      }
+    }
+  }
+
+  #[test]
+  fn saturation() {
+    // With a feedback above one, the echoes build up to the saturation
+    let mut echo = clean(0.01f64);
+    echo.set_drive(2f64);
+    echo.set_feedback(1.5f64);
+    let mut peak = 0f64;
+    for n in 0..20_000 {
+      let output = echo.process(if n < 100 { (n as f64 * 0.3f64).sin() * 0.1f64 } else { 0f64 });
+      assert!(output.abs() < 0.75f64);
+      peak = peak.max(output.abs());
+    }
+    assert!(peak > 0.3f64);
+
+    echo.clear();
+    assert_eq!(echo.last_out(), 0f64);
+    assert_eq!(echo.process(0f64), 0f64);
+  }
+
+  #[test]
+  fn wow() {
+    // The wow and flutter move the echo, and a new time is glided to
+    let mut steady = clean(0.1f64);
+    let mut wobbly = clean(0.1f64);
+    wobbly.set_wow(0.002f64, 2f64);
+    wobbly.set_flutter(0.000_2f64, 8f64);
+    let mut difference = 0f64;
+    let mut previous = 0f64;
+    let mut largest_step = 0f64;
+    for n in 0..8_000 {
+      if n == 4_000 {
+        wobbly.set_time(0.3f64);
+      }
+      let input = (n as f64 * 0.1f64).sin();
+      let output = wobbly.process(input);
+      difference += (output - steady.process(input)).abs();
+      if n > 1_000 {
+        largest_step = largest_step.max((output - previous).abs());
+      }
+      previous = output;
+    }
+    assert!(difference > 100f64);
+    assert!(largest_step < 0.3f64);
+    assert!(wobbly.time() > 0.25f64);
+  }
+
+  #[test]
+  fn longest_time() {
+    // The longest time is shortened by the depths, so the wow is not clipped
+    let mut echo = clean(1f64);
+    echo.set_wow(0.01f64, 2f64);
+    echo.set_flutter(0.001f64, 8f64);
+    for _ in 0..40_000 {
+      echo.process(0f64);
+    }
+    assert!((echo.time() - 0.989f64).abs() < 1e-6);
+
+    echo.set_wow(0f64, 2f64);
+    echo.set_flutter(0f64, 8f64);
+    for _ in 0..40_000 {
+      echo.process(0f64);
+    }
+    assert!((echo.time() - 1f64).abs() < 1e-6);
+  }
+}
@@ -0,0 +1,325 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use traits::FloatConst;
+
+/// A mixed-radix fast Fourier transform of complex sequences.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate num;
+/// # extern crate rasp;
+/// # fn main() {
+/// use num::Complex;
+/// use rasp::fft::Fft;
+///
+/// let mut fft = Fft::new(6);
+/// let mut buffer = vec![Complex::new(1f32, 0f32); 6];
+/// fft.forward(&mut buffer);
+/// assert_eq!(buffer[0], Complex::new(6f32, 0f32));
+///
+/// // The inverse transform is not normalized
+/// fft.inverse(&mut buffer);
+/// # assert!((buffer[3].re - 6f32).abs() < 1e-5f32);
+/// # }
+/// ```
+pub struct Fft<T> {
+  size: usize,
+  /// The radix and remaining length of each stage
+  factors: Vec<(usize, usize)>,
+  twiddles: Vec<Complex<T>>,
+  /// A copy of the input of a transform
+  input: Vec<Complex<T>>,
+  /// Working memory for the generic butterfly
+  scratch: Vec<Complex<T>>
+}
+
+/// Returns the radix and remaining length of each stage of a transform.
+fn factorize(size: usize) -> Vec<(usize, usize)> {
+  let mut factors = Vec::new();
+  let mut n = size;
+  let mut p = 4;
+  let root = (size as f64).sqrt().floor() as usize;
+  while n > 1 {
+    // The next radix that divides `n`, or `n` itself when there is none
+    loop {
+      match n % p {
+        0 => break,
+        _ => {
+          p = match p {
+            4 => 2,
+            2 => 3,
+            _ => p + 2
+          };
+          if p > root {
+            p = n;
+          }
+        }
+      }
+    }
+    n /= p;
+    factors.push((p, n));
+  }
+  factors
+}
+
+impl<T> Fft<T> where T: Float + FloatConst {
+  /// Creates a new `Fft` plan for sequences of `size` samples.
+  pub fn new(size: usize) -> Self {
+    debug_assert!(size > 0);
+    let factors = factorize(size);
+    let largest = factors.iter().fold(1, |largest, &(p, _)| largest.max(p));
+    let n: T = num::cast(size).unwrap();
+    let twiddles = (0..size).map(|k| {
+      let k: T = num::cast(k).unwrap();
+      Complex::from_polar(&T::one(), &(-T::two() * T::pi() * k / n))
+    }).collect();
+    Fft {
+      size: size,
+      factors: factors,
+      twiddles: twiddles,
+      input: vec![Complex::new(T::zero(), T::zero()); size],
+      scratch: vec![Complex::new(T::zero(), T::zero()); largest]
+    }
+  }
+
+  /// Returns the number of samples in a transform.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  /// Replaces `buffer` with its discrete Fourier transform.
+  pub fn forward(&mut self, buffer: &mut [Complex<T>]) {
+    debug_assert_eq!(buffer.len(), self.size);
+    if self.size == 1 {
+      return;
+    }
+    self.input.copy_from_slice(buffer);
+    transform(buffer, &self.input, 0, 1, &self.factors, &self.twiddles, &mut self.scratch);
+  }
+
+  /// Replaces `buffer` with its inverse discrete Fourier transform, scaled
+  /// by the size of the transform.
+  pub fn inverse(&mut self, buffer: &mut [Complex<T>]) {
+    for x in buffer.iter_mut() {
+      *x = x.conj();
+    }
+    self.forward(buffer);
+    for x in buffer.iter_mut() {
+      *x = x.conj();
+    }
+  }
+}
+
+/// Transforms every `stride` sample of `input`, starting at `offset`, into
+/// `output`.
+fn transform<T>(output: &mut [Complex<T>],
+                input: &[Complex<T>],
+                offset: usize,
+                stride: usize,
+                factors: &[(usize, usize)],
+                twiddles: &[Complex<T>],
+                scratch: &mut [Complex<T>])
+  where T: Float
+{
+  let (p, m) = factors[0];
+  if m == 1 {
+    for (i, x) in output.iter_mut().enumerate() {
+      *x = input[offset + i * stride];
+    }
+  }
+  else {
+    for (i, sub) in output.chunks_mut(m).enumerate() {
+      transform(sub, input, offset + i * stride, stride * p, &factors[1..], twiddles, scratch);
+    }
+  }
+
+  match p {
+    2 => butterfly2(output, stride, m, twiddles),
+    3 => butterfly3(output, stride, m, twiddles),
+    4 => butterfly4(output, stride, m, twiddles),
+    5 => butterfly5(output, stride, m, twiddles),
+    _ => butterfly(output, stride, m, p, twiddles, scratch)
+  }
+}
+
+fn butterfly2<T: Float>(x: &mut [Complex<T>], stride: usize, m: usize, twiddles: &[Complex<T>]) {
+  for k in 0..m {
+    let t = x[k + m] * twiddles[k * stride];
+    x[k + m] = x[k] - t;
+    x[k] = x[k] + t;
+  }
+}
+
+fn butterfly3<T: Float>(x: &mut [Complex<T>], stride: usize, m: usize, twiddles: &[Complex<T>]) {
+  let half: T = num::cast(0.5f64).unwrap();
+  let epsilon = twiddles[stride * m].im;
+  for k in 0..m {
+    let s1 = x[k + m] * twiddles[k * stride];
+    let s2 = x[k + 2 * m] * twiddles[2 * k * stride];
+    let sum = s1 + s2;
+    let difference = (s1 - s2) * epsilon;
+    let middle = x[k] - sum * half;
+    x[k] = x[k] + sum;
+    x[k + m] = Complex::new(middle.re - difference.im, middle.im + difference.re);
+    x[k + 2 * m] = Complex::new(middle.re + difference.im, middle.im - difference.re);
+  }
+}
+
+fn butterfly4<T: Float>(x: &mut [Complex<T>], stride: usize, m: usize, twiddles: &[Complex<T>]) {
+  for k in 0..m {
+    let s0 = x[k + m] * twiddles[k * stride];
+    let s1 = x[k + 2 * m] * twiddles[2 * k * stride];
+    let s2 = x[k + 3 * m] * twiddles[3 * k * stride];
+    let s5 = x[k] - s1;
+    let s0_plus = x[k] + s1;
+    let s3 = s0 + s2;
+    let s4 = s0 - s2;
+    x[k] = s0_plus + s3;
+    x[k + 2 * m] = s0_plus - s3;
+    x[k + m] = Complex::new(s5.re + s4.im, s5.im - s4.re);
+    x[k + 3 * m] = Complex::new(s5.re - s4.im, s5.im + s4.re);
+  }
+}
+
+fn butterfly5<T: Float>(x: &mut [Complex<T>], stride: usize, m: usize, twiddles: &[Complex<T>]) {
+  let ya = twiddles[stride * m];
+  let yb = twiddles[2 * stride * m];
+  for u in 0..m {
+    let s0 = x[u];
+    let s1 = x[u + m] * twiddles[u * stride];
+    let s2 = x[u + 2 * m] * twiddles[2 * u * stride];
+    let s3 = x[u + 3 * m] * twiddles[3 * u * stride];
+    let s4 = x[u + 4 * m] * twiddles[4 * u * stride];
+
+    let s7 = s1 + s4;
+    let s10 = s1 - s4;
+    let s8 = s2 + s3;
+    let s9 = s2 - s3;
+
+    x[u] = s0 + s7 + s8;
+
+    let s5 = Complex::new(s0.re + s7.re * ya.re + s8.re * yb.re,
+                          s0.im + s7.im * ya.re + s8.im * yb.re);
+    let s6 = Complex::new(s10.im * ya.im + s9.im * yb.im,
+                          -(s10.re * ya.im) - s9.re * yb.im);
+    x[u + m] = s5 - s6;
+    x[u + 4 * m] = s5 + s6;
+
+    let s11 = Complex::new(s0.re + s7.re * yb.re + s8.re * ya.re,
+                           s0.im + s7.im * yb.re + s8.im * ya.re);
+    let s12 = Complex::new(-(s10.im * yb.im) + s9.im * ya.im,
+                           s10.re * yb.im - s9.re * ya.im);
+    x[u + 2 * m] = s11 + s12;
+    x[u + 3 * m] = s11 - s12;
+  }
+}
+
+/// A direct DFT of length `p` for any radix.
+fn butterfly<T: Float>(x: &mut [Complex<T>],
+                       stride: usize,
+                       m: usize,
+                       p: usize,
+                       twiddles: &[Complex<T>],
+                       scratch: &mut [Complex<T>])
+{
+  let n = twiddles.len();
+  for u in 0..m {
+    for (q, s) in scratch.iter_mut().take(p).enumerate() {
+      *s = x[u + q * m];
+    }
+    for q1 in 0..p {
+      let k = u + q1 * m;
+      let mut index = 0;
+      let mut sum = scratch[0];
+      for s in scratch.iter().take(p).skip(1) {
+        index += stride * k;
+        if index >= n {
+          index -= n;
+        }
+        sum = sum + *s * twiddles[index];
+      }
+      x[k] = sum;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns the DFT of `x`, computed directly.
+  fn dft(x: &[Complex<f64>], sign: f64) -> Vec<Complex<f64>> {
+    let n = x.len() as f64;
+    (0..x.len()).map(|k| {
+      x.iter().enumerate().fold(Complex::new(0f64, 0f64), |sum, (i, x)| {
+        let phase = sign * 2f64 * ::std::f64::consts::PI * (k * i) as f64 / n;
+        sum + x * Complex::from_polar(&1f64, &phase)
+      })
+    }).collect()
+  }
+
+  fn signal(size: usize) -> Vec<Complex<f64>> {
+    (0..size)
+      .map(|i| Complex::new((i as f64 * 0.37f64).sin(), (i as f64 * 1.3f64).cos() - 0.2f64))
+      .collect()
+  }
+
+  #[test]
+  fn factors() {
+    assert_eq!(factorize(16), vec![(4, 4), (4, 1)]);
+    assert_eq!(factorize(8), vec![(4, 2), (2, 1)]);
+    assert_eq!(factorize(60), vec![(4, 15), (3, 5), (5, 1)]);
+    assert_eq!(factorize(7 * 11), vec![(7, 11), (11, 1)]);
+    assert_eq!(factorize(13), vec![(13, 1)]);
+  }
+
+  #[test]
+  fn forward() {
+    for size in [1usize, 2, 3, 4, 5, 6, 7, 8, 12, 15, 16, 30, 49, 60, 64, 77, 100, 127].iter() {
+      let x = signal(*size);
+      let expected = dft(&x, -1f64);
+      let mut actual = x.clone();
+      Fft::new(*size).forward(&mut actual);
+      for (expected, actual) in expected.iter().zip(actual.iter()) {
+        assert!((expected - actual).norm() < 1e-9, "{}: {} != {}", size, expected, actual);
+      }
+    }
+  }
+
+  #[test]
+  fn inverse() {
+    for size in [8usize, 45, 96].iter() {
+      let x = signal(*size);
+      let expected = dft(&x, 1f64);
+      let mut fft = Fft::new(*size);
+      let mut actual = x.clone();
+      fft.inverse(&mut actual);
+      for (expected, actual) in expected.iter().zip(actual.iter()) {
+        assert!((expected - actual).norm() < 1e-9);
+      }
+
+      // The round trip scales by the size
+      fft.forward(&mut actual);
+      for (x, actual) in x.iter().zip(actual.iter()) {
+        assert!((x * *size as f64 - actual).norm() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn forward_f32() {
+    let mut fft = Fft::new(1_024);
+    let mut buffer = vec![Complex::new(0f32, 0f32); 1_024];
+    for (i, x) in buffer.iter_mut().enumerate() {
+      x.re = (2f32 * ::std::f32::consts::PI * 10f32 * i as f32 / 1_024f32).cos();
+    }
+    fft.forward(&mut buffer);
+    for (k, x) in buffer.iter().enumerate() {
+      let expected = if k == 10 || k == 1_014 { 512f32 } else { 0f32 };
+      assert!((x.norm() - expected).abs() < 1e-3f32);
+    }
+  }
+}
@@ -0,0 +1,38 @@
+//! Fast Fourier transforms.
+//!
+//! `Fft` transforms complex sequences of any length and `RealFft` transforms
+//! real sequences of an even length, such as a block of samples after
+//! `window::apply_window()`. Both are plans: the twiddle factors and working
+//! memory are computed when the plan is created, and reused by every
+//! transform without allocating.
+//!
+//! Transforms follow the usual conventions:
+//!
+//! - the forward transform is `X[k] = sum(x[n] * exp(-2*pi*i*k*n / N))`
+//! - the inverse transform is `x[n] = sum(X[k] * exp(2*pi*i*k*n / N))`
+//!
+//! Neither direction is normalized, so a forward transform followed by an
+//! inverse transform scales the sequence by `N`.
+//!
+//! `Stft` and `Istft` build on `RealFft` for streaming analysis and
+//! resynthesis of overlapping, windowed frames.
+
+/* Notes on the mixed-radix transform
+  - The size is factored into radices of 4, 2, 3, 5, and then any other
+    factor, as in KISS FFT by Mark Borgerding
+  - The transform recursively decimates the input in time, then combines the
+    sub-transforms of each stage with a butterfly for its radix; radices
+    without a specialized butterfly use a direct DFT of length `p`, so prime
+    sizes are O(N^2)
+  - The inverse transform conjugates the input and output of the forward
+    transform, so both directions share the same twiddle factors
+*/
+
+mod complex;
+mod real;
+mod stft;
+
+pub use self::complex::Fft  as Fft;
+pub use self::real::RealFft as RealFft;
+pub use self::stft::Istft   as Istft;
+pub use self::stft::Stft    as Stft;
@@ -0,0 +1,189 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use fft::Fft;
+use traits::FloatConst;
+
+/* Notes on the real transform
+  - A real sequence `x` of length `N` is packed into the complex sequence
+    `z[n] = x[2n] + i*x[2n + 1]` of length `N/2`, which is transformed with
+    a complex `Fft` of half the size
+  - The transforms of the even and odd samples are separated with the
+    symmetry of real transforms, `E[k] = (Z[k] + conj(Z[N/2 - k])) / 2` and
+    `O[k] = -i*(Z[k] - conj(Z[N/2 - k])) / 2`, and then combined as
+    `X[k] = E[k] + exp(-2*pi*i*k / N)*O[k]`
+  - The inverse transform reverses these steps
+*/
+
+/// A fast Fourier transform of real sequences with an even length.
+///
+/// Only the non-negative frequencies of the transform are computed, since
+/// the others are their complex conjugates, so a spectrum has `size / 2 + 1`
+/// bins from DC to the Nyquist frequency.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate num;
+/// # extern crate rasp;
+/// # fn main() {
+/// use num::Complex;
+/// use rasp::fft::RealFft;
+/// use rasp::window::{self, Window};
+///
+/// let mut fft = RealFft::new(512);
+/// let mut samples = vec![1f32; 512];
+/// window::apply_window(&mut samples, Window::Hann);
+///
+/// let mut spectrum = vec![Complex::new(0f32, 0f32); fft.spectrum_size()];
+/// fft.forward(&samples, &mut spectrum);
+///
+/// // The inverse transform is not normalized
+/// fft.inverse(&spectrum, &mut samples);
+/// # assert!((samples[256] - 512f32).abs() < 1e-2f32);
+/// # }
+/// ```
+pub struct RealFft<T> {
+  size: usize,
+  fft: Fft<T>,
+  /// `exp(-2*pi*i*k / N)`, for `k` in `0..N/2`
+  twiddles: Vec<Complex<T>>,
+  buffer: Vec<Complex<T>>
+}
+
+impl<T> RealFft<T> where T: Float + FloatConst {
+  /// Creates a new `RealFft` plan for sequences of `size` samples, which must
+  /// be even.
+  pub fn new(size: usize) -> Self {
+    debug_assert!(size > 1 && size % 2 != 1);
+    let half = size / 2;
+    let n: T = num::cast(size).unwrap();
+    let twiddles = (0..half).map(|k| {
+      let k: T = num::cast(k).unwrap();
+      Complex::from_polar(&T::one(), &(-T::two() * T::pi() * k / n))
+    }).collect();
+    RealFft {
+      size: size,
+      fft: Fft::new(half),
+      twiddles: twiddles,
+      buffer: vec![Complex::new(T::zero(), T::zero()); half]
+    }
+  }
+
+  /// Returns the number of samples in a transform.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  /// Returns the number of bins in a spectrum, `size / 2 + 1`.
+  pub fn spectrum_size(&self) -> usize {
+    self.size / 2 + 1
+  }
+
+  /// Writes the discrete Fourier transform of `input` to `spectrum`, from DC
+  /// to the Nyquist frequency.
+  pub fn forward(&mut self, input: &[T], spectrum: &mut [Complex<T>]) {
+    debug_assert_eq!(input.len(), self.size);
+    debug_assert_eq!(spectrum.len(), self.spectrum_size());
+    for (z, pair) in self.buffer.iter_mut().zip(input.chunks(2)) {
+      *z = Complex::new(pair[0], pair[1]);
+    }
+    self.fft.forward(&mut self.buffer);
+
+    let half = self.size / 2;
+    let one_half: T = num::cast(0.5f64).unwrap();
+    for (k, x) in spectrum.iter_mut().take(half).enumerate() {
+      let z = self.buffer[k];
+      let mirror = self.buffer[(half - k) % half].conj();
+      let even = (z + mirror) * one_half;
+      let difference = (z - mirror) * one_half;
+      let odd = Complex::new(difference.im, -difference.re);
+      *x = even + self.twiddles[k] * odd;
+    }
+    let z = self.buffer[0];
+    spectrum[half] = Complex::new(z.re - z.im, T::zero());
+  }
+
+  /// Writes the inverse discrete Fourier transform of `spectrum`, from DC to
+  /// the Nyquist frequency, to `output`, scaled by the size of the
+  /// transform.
+  ///
+  /// The imaginary parts of the DC and Nyquist bins are ignored.
+  pub fn inverse(&mut self, spectrum: &[Complex<T>], output: &mut [T]) {
+    debug_assert_eq!(spectrum.len(), self.spectrum_size());
+    debug_assert_eq!(output.len(), self.size);
+    let half = self.size / 2;
+    let dc = Complex::new(spectrum[0].re, T::zero());
+    let nyquist = Complex::new(spectrum[half].re, T::zero());
+    for (k, z) in self.buffer.iter_mut().enumerate() {
+      let x = if k == 0 { dc } else { spectrum[k] };
+      let mirror = if k == 0 { nyquist } else { spectrum[half - k].conj() };
+      let even = x + mirror;
+      let odd = (x - mirror) * self.twiddles[k].conj();
+      *z = even + Complex::new(-odd.im, odd.re);
+    }
+    self.fft.inverse(&mut self.buffer);
+
+    for (z, pair) in self.buffer.iter().zip(output.chunks_mut(2)) {
+      pair[0] = z.re;
+      pair[1] = z.im;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn signal(size: usize) -> Vec<f64> {
+    (0..size).map(|i| (i as f64 * 0.37f64).sin() + 0.1f64 * (i as f64).cos()).collect()
+  }
+
+  #[test]
+  fn forward() {
+    for size in [2usize, 4, 6, 10, 16, 30, 64, 98].iter() {
+      let x = signal(*size);
+      let mut expected: Vec<Complex<f64>> = x.iter().map(|x| Complex::new(*x, 0f64)).collect();
+      Fft::new(*size).forward(&mut expected);
+
+      let mut fft = RealFft::new(*size);
+      let mut actual = vec![Complex::new(0f64, 0f64); fft.spectrum_size()];
+      fft.forward(&x, &mut actual);
+      for (expected, actual) in expected.iter().zip(actual.iter()) {
+        assert!((expected - actual).norm() < 1e-9, "{}: {} != {}", size, expected, actual);
+      }
+    }
+  }
+
+  #[test]
+  fn inverse() {
+    for size in [2usize, 8, 18, 256].iter() {
+      let x = signal(*size);
+      let mut fft = RealFft::new(*size);
+      let mut spectrum = vec![Complex::new(0f64, 0f64); fft.spectrum_size()];
+      fft.forward(&x, &mut spectrum);
+
+      let mut actual = vec![0f64; *size];
+      fft.inverse(&spectrum, &mut actual);
+      for (x, actual) in x.iter().zip(actual.iter()) {
+        assert!((x * *size as f64 - actual).abs() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn inverse_f32() {
+    let mut fft = RealFft::new(64);
+    let mut spectrum = vec![Complex::new(0f32, 0f32); 33];
+    spectrum[4] = Complex::new(0f32, -32f32);
+    let mut output = vec![0f32; 64];
+    fft.inverse(&spectrum, &mut output);
+
+    // The conjugate bin is implied, so this is a sine of amplitude 64
+    for (i, sample) in output.iter().enumerate() {
+      let expected = 64f32 * (2f32 * ::std::f32::consts::PI * 4f32 * i as f32 / 64f32).sin();
+      assert!((sample - expected).abs() < 1e-3f32);
+    }
+  }
+}
@@ -0,0 +1,365 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use std::collections::VecDeque;
+
+use fft::RealFft;
+use traits::FloatConst;
+use window::{self, Window};
+
+/* Notes on the short-time Fourier transform
+  - A frame of `frame_size` samples is analyzed every `hop_size` samples,
+    after the frame is multiplied by the analysis window
+  - The first frame is analyzed after `hop_size` samples, as if the input were
+    preceded by silence, so every output sample is reconstructed from every
+    frame that overlaps it
+  - Resynthesis multiplies each inverse transform by the synthesis window and
+    overlaps-and-adds it with the previous frames (WOLA)
+  - With the same window `w` for analysis and synthesis, an unmodified
+    spectrum is reconstructed when the synthesis window is divided by the sum
+    of `w^2` over the frames overlapping each sample, which only depends on
+    the sample's position within a hop
+    - This sum is constant when `w^2` has the constant-overlap-add property,
+      as with a Hann window at a hop of a quarter of the frame, but dividing
+      by it per position reconstructs any window whose sum is never zero
+  - The last sample of a frame is the newest input, and the first sample of
+    the resynthesized frame is output at the same time, so the latency is
+    `frame_size - 1` samples
+*/
+
+/// Returns the values of `window` for a frame of `size` samples.
+fn window_values<T: Float + FloatConst>(size: usize, window: Window) -> Vec<T> {
+  let mut values = vec![T::one(); size];
+  window::apply_window(&mut values, window);
+  values
+}
+
+/// A streaming short-time Fourier transform.
+///
+/// Samples are buffered until a frame is complete, and then the spectrum of
+/// the windowed frame is passed to a callback. Spectra are the output of
+/// `RealFft::forward()`, with `frame_size / 2 + 1` bins, and can be modified
+/// in place before they are resynthesized with an `Istft`.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::fft::{Istft, Stft};
+/// use rasp::window::Window;
+///
+/// let mut stft = Stft::new(1_024, 256, Window::Hann);
+/// let mut istft = Istft::new(1_024, 256, Window::Hann);
+///
+/// let input = vec![0.5f32; 4_096];
+/// let mut output = vec![0f32; 4_096];
+/// for (input, output) in input.iter().zip(output.iter_mut()) {
+///   stft.process(*input, |spectrum| {
+///     // A brick-wall low-pass filter
+///     for bin in spectrum.iter_mut().skip(100) {
+///       *bin = bin.scale(0f32);
+///     }
+///     istft.add_frame(spectrum);
+///   });
+///   *output = istft.read();
+/// }
+/// # assert!((output[3_000] - 0.5f32).abs() < 1e-4f32);
+/// ```
+pub struct Stft<T> {
+  hop_size: usize,
+  window: Vec<T>,
+  fft: RealFft<T>,
+  /// The last `frame_size` samples, circularly
+  history: Vec<T>,
+  index: usize,
+  /// The number of samples until the next frame
+  countdown: usize,
+  frame: Vec<T>,
+  spectrum: Vec<Complex<T>>
+}
+
+impl<T> Stft<T> where T: Float + FloatConst {
+  /// Creates a new `Stft`, analyzing frames of `frame_size` samples every
+  /// `hop_size` samples.
+  ///
+  /// `frame_size` must be even, and `hop_size` must not be greater than
+  /// `frame_size`.
+  pub fn new(frame_size: usize, hop_size: usize, window: Window) -> Self {
+    debug_assert!(hop_size > 0 && hop_size <= frame_size);
+    let fft = RealFft::new(frame_size);
+    let bins = fft.spectrum_size();
+    Stft {
+      hop_size: hop_size,
+      window: window_values(frame_size, window),
+      fft: fft,
+      history: vec![T::zero(); frame_size],
+      index: 0,
+      countdown: hop_size,
+      frame: vec![T::zero(); frame_size],
+      spectrum: vec![Complex::new(T::zero(), T::zero()); bins]
+    }
+  }
+
+  /// Returns the number of samples in a frame.
+  pub fn frame_size(&self) -> usize {
+    self.window.len()
+  }
+
+  /// Returns the number of samples between frames.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// Returns the number of bins in a spectrum, `frame_size / 2 + 1`.
+  pub fn spectrum_size(&self) -> usize {
+    self.spectrum.len()
+  }
+
+  /// Adds a sample to the current frame, and passes the spectrum of the frame
+  /// to `callback` if it is complete.
+  pub fn process<F>(&mut self, sample: T, mut callback: F)
+    where F: FnMut(&mut [Complex<T>])
+  {
+    let size = self.history.len();
+    self.history[self.index] = sample;
+    self.index = (self.index + 1) % size;
+    self.countdown -= 1;
+    if self.countdown > 0 {
+      return;
+    }
+    self.countdown = self.hop_size;
+
+    // The oldest sample is at the write index
+    let (newest, oldest) = self.history.split_at(self.index);
+    let samples = oldest.iter().chain(newest.iter());
+    for ((x, sample), gain) in self.frame.iter_mut().zip(samples).zip(self.window.iter()) {
+      *x = *sample * *gain;
+    }
+    self.fft.forward(&self.frame, &mut self.spectrum);
+    callback(&mut self.spectrum);
+  }
+
+  /// Adds a block of samples, calling `callback` with the spectrum of every
+  /// frame that is completed.
+  pub fn process_block<F>(&mut self, samples: &[T], mut callback: F)
+    where F: FnMut(&mut [Complex<T>])
+  {
+    for sample in samples.iter() {
+      self.process(*sample, &mut callback);
+    }
+  }
+
+  /// Clears the buffered samples.
+  pub fn clear(&mut self) {
+    for sample in self.history.iter_mut() {
+      *sample = T::zero();
+    }
+    self.index = 0;
+    self.countdown = self.hop_size;
+  }
+}
+
+/// A streaming inverse short-time Fourier transform.
+///
+/// Spectra from an `Stft` with the same frame size, hop size and window are
+/// added with `add_frame()`, and the resynthesized samples are read, one for
+/// each sample given to the `Stft`, with `read()` or `read_block()`. The
+/// output is the input of the `Stft` delayed by `latency()` samples.
+///
+/// Frames must be added before the samples they overlap are read, so when
+/// processing blocks, the `Stft` processes a block before the `Istft` reads
+/// it.
+pub struct Istft<T> {
+  hop_size: usize,
+  /// The synthesis window, normalized for the overlap-add
+  window: Vec<T>,
+  fft: RealFft<T>,
+  frame: Vec<T>,
+  /// The overlapped output, starting with the next sample to read
+  output: VecDeque<T>,
+  /// The position of the next frame in `output`
+  offset: usize
+}
+
+impl<T> Istft<T> where T: Float + FloatConst {
+  /// Creates a new `Istft`, resynthesizing frames of `frame_size` samples
+  /// every `hop_size` samples.
+  ///
+  /// `frame_size` must be even, and `hop_size` must not be greater than
+  /// `frame_size`.
+  pub fn new(frame_size: usize, hop_size: usize, window: Window) -> Self {
+    debug_assert!(hop_size > 0 && hop_size <= frame_size);
+    let analysis: Vec<T> = window_values(frame_size, window);
+
+    // The sum of the squared windows at each position within a hop
+    let mut sums = vec![T::zero(); hop_size];
+    for (n, gain) in analysis.iter().enumerate() {
+      sums[n % hop_size] = sums[n % hop_size] + *gain * *gain;
+    }
+    let size: T = num::cast(frame_size).unwrap();
+    let synthesis = analysis.iter().enumerate().map(|(n, gain)| {
+      let sum = sums[n % hop_size];
+      if sum > T::epsilon() { *gain / (sum * size) } else { T::zero() }
+    }).collect();
+
+    Istft {
+      hop_size: hop_size,
+      window: synthesis,
+      fft: RealFft::new(frame_size),
+      frame: vec![T::zero(); frame_size],
+      output: VecDeque::with_capacity(2 * frame_size),
+      offset: hop_size - 1
+    }
+  }
+
+  /// Returns the number of samples in a frame.
+  pub fn frame_size(&self) -> usize {
+    self.window.len()
+  }
+
+  /// Returns the number of samples between frames.
+  pub fn hop_size(&self) -> usize {
+    self.hop_size
+  }
+
+  /// Returns the latency, in samples, of an analysis and resynthesis.
+  pub fn latency(&self) -> usize {
+    self.window.len() - 1
+  }
+
+  /// Resynthesizes a spectrum, from DC to the Nyquist frequency, and adds it
+  /// to the output.
+  pub fn add_frame(&mut self, spectrum: &[Complex<T>]) {
+    self.fft.inverse(spectrum, &mut self.frame);
+    let size = self.frame.len();
+    while self.output.len() < self.offset + size {
+      self.output.push_back(T::zero());
+    }
+    for (n, (sample, gain)) in self.frame.iter().zip(self.window.iter()).enumerate() {
+      let output = &mut self.output[self.offset + n];
+      *output = *output + *sample * *gain;
+    }
+    self.offset += self.hop_size;
+  }
+
+  /// Returns the next resynthesized sample.
+  pub fn read(&mut self) -> T {
+    self.offset = self.offset.saturating_sub(1);
+    self.output.pop_front().unwrap_or_else(T::zero)
+  }
+
+  /// Fills a block with the next resynthesized samples.
+  pub fn read_block(&mut self, samples: &mut [T]) {
+    for sample in samples.iter_mut() {
+      *sample = self.read();
+    }
+  }
+
+  /// Clears the output.
+  pub fn clear(&mut self) {
+    self.output.clear();
+    self.offset = self.hop_size - 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn signal(length: usize) -> Vec<f64> {
+    (0..length)
+      .map(|i| (i as f64 * 0.05f64).sin() + 0.3f64 * (i as f64 * 0.71f64).cos())
+      .collect()
+  }
+
+  /// Returns the output of an unmodified analysis and resynthesis.
+  fn identity(input: &[f64], frame_size: usize, hop_size: usize, window: Window) -> Vec<f64> {
+    let mut stft = Stft::new(frame_size, hop_size, window);
+    let mut istft = Istft::new(frame_size, hop_size, window);
+    input.iter().map(|sample| {
+      stft.process(*sample, |spectrum| istft.add_frame(spectrum));
+      istft.read()
+    }).collect()
+  }
+
+  #[test]
+  fn reconstruction() {
+    let input = signal(2_000);
+    let windows = [
+      (Window::Hann, 4), (Window::Hamming, 2), (Window::Bartlett, 2),
+      (Window::Blackman, 4), (Window::Rectangular, 1), (Window::Hann, 3)
+    ];
+    for &(window, overlap) in windows.iter() {
+      let (frame_size, hop_size) = (64, 64 / overlap);
+      let output = identity(&input, frame_size, hop_size, window);
+      let latency = Istft::<f64>::new(frame_size, hop_size, window).latency();
+      assert_eq!(latency, 63);
+      for (n, actual) in output.iter().enumerate() {
+        let expected = if n >= latency { input[n - latency] } else { 0f64 };
+        assert!((expected - actual).abs() < 1e-9, "{:?} {}: {} != {}", window, n, expected, actual);
+      }
+    }
+  }
+
+  #[test]
+  fn blocks() {
+    let input = signal(1_000);
+    let expected = identity(&input, 32, 8, Window::Hann);
+
+    let mut stft = Stft::new(32, 8, Window::Hann);
+    let mut istft = Istft::new(32, 8, Window::Hann);
+    let mut output = vec![0f64; input.len()];
+    for (input, output) in input.chunks(100).zip(output.chunks_mut(100)) {
+      stft.process_block(input, |spectrum| istft.add_frame(spectrum));
+      istft.read_block(output);
+    }
+    for (expected, actual) in expected.iter().zip(output.iter()) {
+      assert!((expected - actual).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn frames() {
+    // A sine at the center of bin 4
+    let mut stft = Stft::new(64, 16, Window::Rectangular);
+    let mut frames = 0;
+    for i in 0..256 {
+      let phase = 2f32 * ::std::f32::consts::PI * 4f32 * i as f32 / 64f32;
+      stft.process(phase.cos(), |spectrum| {
+        frames += 1;
+        assert_eq!(spectrum.len(), 33);
+        if i >= 63 {
+          let peak = (0..33).max_by(|a, b| {
+            spectrum[*a].norm().partial_cmp(&spectrum[*b].norm()).unwrap()
+          }).unwrap();
+          assert_eq!(peak, 4);
+          assert!((spectrum[4].norm() - 32f32).abs() < 1e-3f32);
+        }
+      });
+    }
+    assert_eq!(frames, 16);
+
+    stft.clear();
+    stft.process_block(&[0f32; 15], |_| panic!());
+  }
+
+  #[test]
+  fn modification() {
+    // Halving every bin halves the output
+    let mut stft = Stft::new(128, 32, Window::Hann);
+    let mut istft = Istft::new(128, 32, Window::Hann);
+    let input: Vec<f32> = (0..1_000).map(|i| (i as f32 * 0.9f32).sin()).collect();
+    for (n, sample) in input.iter().enumerate() {
+      stft.process(*sample, |spectrum| {
+        for bin in spectrum.iter_mut() {
+          *bin = bin.scale(0.5f32);
+        }
+        istft.add_frame(spectrum);
+      });
+      let output = istft.read();
+      if n >= 127 {
+        assert!((output - 0.5f32 * input[n - 127]).abs() < 1e-5f32);
+      }
+    }
+  }
+}
@@ -0,0 +1,257 @@
+//! Bessel, or Bessel-Thomson, filter designs.
+//!
+//! A Bessel filter has a maximally flat group delay in the passband, so it
+//! preserves the shape of transients, at the cost of a slow rolloff. The
+//! meaning of the cutoff frequency depends on the chosen `Normalization`.
+//! Filters are returned as a `Cascade` of `Biquad2` sections.
+//!
+//! The bilinear transform compresses the frequency axis, so the group delay
+//! of the digital filter is only flat well below the Nyquist frequency.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+
+/// The normalization of a Bessel filter's cutoff frequency.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Normalization {
+  /// The phase response at the cutoff frequency is half of its total, the
+  /// same asymptotes as a Butterworth filter of the same order
+  Phase,
+  /// The group delay in the passband is `1 / (2 * pi * cutoff_frequency)`
+  /// seconds
+  Delay,
+  /// The magnitude response at the cutoff frequency is -3dB
+  Magnitude
+}
+
+/// Returns the coefficients of the reverse Bessel polynomial of the given
+/// `order`, in ascending order.
+fn polynomial(order: usize) -> Vec<f64> {
+  // a[k] = (2n - k)! / (2^(n - k) * k! * (n - k)!)
+  let n = order as f64;
+  let mut coefficients = vec![1f64; order + 1];
+  for k in (0..order).rev() {
+    let k_float = k as f64;
+    coefficients[k] = coefficients[k + 1]
+                    * (2f64 * n - k_float) * (k_float + 1f64)
+                    / (2f64 * (n - k_float));
+  }
+  coefficients
+}
+
+/// Returns the squared magnitude response of an all-pole filter, with a DC
+/// gain of one, at `frequency` in rad/s.
+fn magnitude_squared(poles: &[Complex<f64>], frequency: f64) -> f64 {
+  let s = Complex::new(0f64, frequency);
+  poles.iter().fold(1f64, |magnitude, pole| {
+    magnitude * pole.norm_sqr() / (s - pole).norm_sqr()
+  })
+}
+
+/// Returns the normalized analog prototype of the given `order`.
+fn prototype(order: usize, normalization: Normalization) -> Prototype {
+  let coefficients = polynomial(order);
+
+  // The roots are normalized for a group delay of one second
+  let poles = design::roots(&coefficients);
+  let scale = match normalization {
+    Normalization::Delay => 1f64,
+    Normalization::Phase => coefficients[0].powf(-1f64 / order as f64),
+    Normalization::Magnitude => {
+      // Bisection for the -3dB frequency, where the response is monotonic
+      let (mut low, mut high) = (0f64, 1f64);
+      while magnitude_squared(&poles, high) > 0.5f64 {
+        high *= 2f64;
+      }
+      for _ in 0..100 {
+        let middle = (low + high) / 2f64;
+        if magnitude_squared(&poles, middle) > 0.5f64 {
+          low = middle;
+        }
+        else {
+          high = middle;
+        }
+      }
+      1f64 / low
+    }
+  };
+
+  let poles: Vec<Complex<f64>> =
+    poles.iter().map(|pole| pole * scale).collect();
+  let gain = poles
+    .iter()
+    .fold(Complex::new(1f64, 0f64), |gain, pole| gain * -pole)
+    .re;
+
+  Prototype {
+    zeros: Vec::new(),
+    poles: poles,
+    gain: gain
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    normalization: Normalization,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  design::digitize(prototype(order, normalization),
+                   design::cast(sample_rate),
+                   transform)
+}
+
+/// Designs a low-pass Bessel filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::bessel::{self, Normalization};
+///
+/// let mut filter = bessel::lowpass(4, 44_100f32, 1_000f32, Normalization::Magnitude);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T,
+                         normalization: Normalization) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, normalization, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass Bessel filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          normalization: Normalization) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, normalization, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass Bessel filter between `low_frequency` and
+/// `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          normalization: Normalization) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, normalization, Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop Bessel filter between `low_frequency` and
+/// `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          normalization: Normalization) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, normalization, Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+  use filter::design::magnitude;
+  use ::traits::{FrequencyResponse, Processor};
+
+  #[test]
+  fn polynomials() {
+    assert_eq!(polynomial(1), vec![1f64, 1f64]);
+    assert_eq!(polynomial(2), vec![3f64, 3f64, 1f64]);
+    assert_eq!(polynomial(3), vec![15f64, 15f64, 6f64, 1f64]);
+    assert_eq!(polynomial(4), vec![105f64, 105f64, 45f64, 10f64, 1f64]);
+  }
+
+  #[test]
+  fn prototypes() {
+    // Roots of s^2 + 3s + 3
+    let delay = prototype(2, Normalization::Delay);
+    for pole in delay.poles.iter() {
+      assert!((pole.re - -1.5f64).abs() < 1e-12);
+      assert!((pole.im.abs() - 0.75f64.sqrt()).abs() < 1e-12);
+    }
+    assert!((delay.gain - 3f64).abs() < 1e-12);
+
+    // Scaled by 3^(-1/2)
+    let phase = prototype(2, Normalization::Phase);
+    for pole in phase.poles.iter() {
+      assert!((pole.re - -0.75f64.sqrt()).abs() < 1e-12);
+      assert!((pole.im.abs() - 0.5f64).abs() < 1e-12);
+    }
+
+    for order in 1..13 {
+      let magnitude = prototype(order, Normalization::Magnitude);
+      assert!((magnitude_squared(&magnitude.poles, 1f64) - 0.5f64).abs() < 1e-9);
+      for pole in magnitude.poles.iter() {
+        assert!(pole.re < 0f64);
+      }
+    }
+  }
+
+  #[test]
+  fn order() {
+    let normalization = Normalization::Phase;
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64, normalization).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64, normalization).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64, normalization).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64, normalization).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn magnitude_normalized() {
+    let sample_rate = 48_000f64;
+    for order in 1..11 {
+      let filter = lowpass(order, sample_rate, 1_000f64, Normalization::Magnitude);
+      assert!((magnitude(&filter, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&filter, 1_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+
+      let filter = highpass(order, sample_rate, 1_000f64, Normalization::Magnitude);
+      assert!((magnitude(&filter, 1_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn delay_normalized() {
+    // The group delay is flat, at 1 / (2 * pi * cutoff) seconds, well below
+    // the cutoff frequency
+    let sample_rate = 48_000f64;
+    let cutoff = 500f64;
+    let expected = sample_rate / (2f64 * PI * cutoff);
+    for order in 2..9 {
+      let filter = lowpass(order, sample_rate, cutoff, Normalization::Delay);
+      for frequency in [10f64, 50f64, 100f64].iter() {
+        let delay = filter.group_delay(*frequency, sample_rate);
+        println!("{} {:.6} - {:.6} = {:.6}", order, expected, delay, expected - delay);
+        assert!((expected - delay).abs() / expected < 1e-2f64);
+      }
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(8, 44_100f32, 1_000f32, Normalization::Phase);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 1f32).abs() < 1e-3f32);
+  }
+}
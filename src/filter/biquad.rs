@@ -1,9 +1,11 @@
 //! A biquad is a second-order recursive filter.
 
 use num;
+use num::Complex;
 use num::traits::Float;
 
-use traits::Processor;
+use filter::{response, zplane};
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /* Notes on biquads
   - A biquad is a recursive second-order IIR filter and is often used as a
@@ -69,6 +71,35 @@ impl<T> Biquad1<T> where T: Float {
     }
   }
 
+  /// Creates a new `Biquad1` filter from its zeros, poles, and gain.
+  ///
+  /// The filter has the transfer function
+  /// `gain * (1 - q1*z^-1)(1 - q2*z^-1) / ((1 - p1*z^-1)(1 - p2*z^-1))`,
+  /// where `q` are `zeros` and `p` are `poles`. There can be at most two of
+  /// each, which must be real or a complex conjugate pair.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # extern crate num;
+  /// # extern crate rasp;
+  /// use num::Complex;
+  /// use rasp::filter::Biquad1;
+  ///
+  /// // A resonator at a quarter of the sample rate
+  /// let pole = Complex::from_polar(&0.99f64, &(std::f64::consts::PI / 2f64));
+  /// let zeros = [Complex::new(1f64, 0f64), Complex::new(-1f64, 0f64)];
+  /// let filter = Biquad1::from_zpk(&zeros, &[pole, pole.conj()], 0.01f64);
+  /// assert!((filter.stability_margin() - 0.99f64).abs() < 1e-12);
+  /// ```
+  pub fn from_zpk(zeros: &[Complex<T>], poles: &[Complex<T>], gain: T) -> Self {
+    let (b1, b2) = zplane::coefficients(zeros);
+    let (a1, a2) = zplane::coefficients(poles);
+    let mut filter = Self::new();
+    filter.set_coefficients(gain, gain * b1, gain * b2, a1, a2);
+    filter
+  }
+
   /// Sets all filter coefficients at once.
   ///
   /// `b1`, `b2` are feedforwards, or zeroes, and `a1`, `a2` are feedbacks,
@@ -80,6 +111,44 @@ impl<T> Biquad1<T> where T: Float {
     self.a1 = a1;
     self.a2 = a2;
   }
+
+  /// Returns the poles of the filter, the roots of `1 + a1*z^-1 + a2*z^-2`.
+  pub fn poles(&self) -> Vec<Complex<T>> {
+    zplane::roots(T::one(), self.a1, self.a2)
+  }
+
+  /// Returns the zeros of the filter, the roots of `b0 + b1*z^-1 + b2*z^-2`.
+  ///
+  /// Zeros at infinity, when `b0` is zero, are not returned.
+  pub fn zeros(&self) -> Vec<Complex<T>> {
+    zplane::roots(self.b0, self.b1, self.b2)
+  }
+
+  /// Returns the largest pole radius.
+  ///
+  /// The filter is stable when this is less than one, and the closer it is
+  /// to one, the longer the filter rings.
+  pub fn stability_margin(&self) -> T {
+    zplane::radius(&self.poles())
+  }
+
+  /// Returns `true` if all poles are strictly inside the unit circle.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::filter::Biquad1;
+  ///
+  /// let mut filter = Biquad1::<f32>::new();
+  /// filter.set_coefficients(1f32, 0f32, 0f32, -1.8f32, 0.9f32);
+  /// assert!(filter.is_stable());
+  ///
+  /// filter.set_coefficients(1f32, 0f32, 0f32, -1.8f32, 1.1f32);
+  /// assert!(!filter.is_stable());
+  /// ```
+  pub fn is_stable(&self) -> bool {
+    self.stability_margin() < T::one()
+  }
 }
 
 impl<T> Processor<T> for Biquad1<T> where T: Float {
@@ -106,6 +175,16 @@ impl<T> Processor<T> for Biquad1<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for Biquad1<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0, self.b1, self.b2], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0, self.b1, self.b2], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+}
+
 /// A biquad filter in transposed direct form 2.
 ///
 /// This implementation uses a Transposed [Direct Form II](https://en.wikipedia.org/wiki/Digital_biquad_filter#Direct_Form_2)
@@ -156,6 +235,35 @@ impl<T> Biquad2<T> where T: Float {
     }
   }
 
+  /// Creates a new `Biquad2` filter from its zeros, poles, and gain.
+  ///
+  /// The filter has the transfer function
+  /// `gain * (1 - q1*z^-1)(1 - q2*z^-1) / ((1 - p1*z^-1)(1 - p2*z^-1))`,
+  /// where `q` are `zeros` and `p` are `poles`. There can be at most two of
+  /// each, which must be real or a complex conjugate pair.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # extern crate num;
+  /// # extern crate rasp;
+  /// use num::Complex;
+  /// use rasp::filter::Biquad2;
+  ///
+  /// // A resonator at a quarter of the sample rate
+  /// let pole = Complex::from_polar(&0.99f64, &(std::f64::consts::PI / 2f64));
+  /// let zeros = [Complex::new(1f64, 0f64), Complex::new(-1f64, 0f64)];
+  /// let filter = Biquad2::from_zpk(&zeros, &[pole, pole.conj()], 0.01f64);
+  /// assert!((filter.stability_margin() - 0.99f64).abs() < 1e-12);
+  /// ```
+  pub fn from_zpk(zeros: &[Complex<T>], poles: &[Complex<T>], gain: T) -> Self {
+    let (b1, b2) = zplane::coefficients(zeros);
+    let (a1, a2) = zplane::coefficients(poles);
+    let mut filter = Self::new();
+    filter.set_coefficients(gain, gain * b1, gain * b2, a1, a2);
+    filter
+  }
+
   /// Sets all filter coefficients at once.
   ///
   /// `b1`, `b2` are feedforwards, or zeroes, and `a1`, `a2` are feedbacks,
@@ -167,6 +275,44 @@ impl<T> Biquad2<T> where T: Float {
     self.a1 = a1;
     self.a2 = a2;
   }
+
+  /// Returns the poles of the filter, the roots of `1 + a1*z^-1 + a2*z^-2`.
+  pub fn poles(&self) -> Vec<Complex<T>> {
+    zplane::roots(T::one(), self.a1, self.a2)
+  }
+
+  /// Returns the zeros of the filter, the roots of `b0 + b1*z^-1 + b2*z^-2`.
+  ///
+  /// Zeros at infinity, when `b0` is zero, are not returned.
+  pub fn zeros(&self) -> Vec<Complex<T>> {
+    zplane::roots(self.b0, self.b1, self.b2)
+  }
+
+  /// Returns the largest pole radius.
+  ///
+  /// The filter is stable when this is less than one, and the closer it is
+  /// to one, the longer the filter rings.
+  pub fn stability_margin(&self) -> T {
+    zplane::radius(&self.poles())
+  }
+
+  /// Returns `true` if all poles are strictly inside the unit circle.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::filter::Biquad2;
+  ///
+  /// let mut filter = Biquad2::<f32>::new();
+  /// filter.set_coefficients(1f32, 0f32, 0f32, -1.8f32, 0.9f32);
+  /// assert!(filter.is_stable());
+  ///
+  /// filter.set_coefficients(1f32, 0f32, 0f32, -1.8f32, 1.1f32);
+  /// assert!(!filter.is_stable());
+  /// ```
+  pub fn is_stable(&self) -> bool {
+    self.stability_margin() < T::one()
+  }
 }
 
 impl<T> Processor<T> for Biquad2<T> where T: Float {
@@ -188,6 +334,16 @@ impl<T> Processor<T> for Biquad2<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for Biquad2<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0, self.b1, self.b2], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0, self.b1, self.b2], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+}
+
 #[cfg(test)]
 mod form1 {
   use super::*;
@@ -250,6 +406,35 @@ mod form1 {
       assert!((expected[i] - actual[i]).abs() < EPSILON);
     }
   }
+
+  #[test]
+  fn poles_and_zeros() {
+    use num::Complex;
+    let pole = Complex::from_polar(&0.8f64, &0.3f64);
+    let zeros = [Complex::new(-1f64, 0f64), Complex::new(0.5f64, 0f64)];
+    let filter = Biquad1::from_zpk(&zeros, &[pole.conj(), pole], 2f64);
+    assert!((filter.b0 - 2f64).abs() < 1e-12);
+    assert!((filter.b1 - 1f64).abs() < 1e-12);
+    assert!((filter.b2 - -1f64).abs() < 1e-12);
+
+    let poles = filter.poles();
+    assert!(poles.iter().any(|p| (p - pole).norm() < 1e-12));
+    assert!(poles.iter().any(|p| (p - pole.conj()).norm() < 1e-12));
+    let actual = filter.zeros();
+    for zero in zeros.iter() {
+      assert!(actual.iter().any(|z| (z - zero).norm() < 1e-12));
+    }
+    assert!((filter.stability_margin() - 0.8f64).abs() < 1e-12);
+    assert!(filter.is_stable());
+
+    // Poles on, or outside, the unit circle
+    let mut filter = Biquad1::<f32>::new();
+    filter.set_coefficients(1f32, 0f32, 0f32, -2f32, 1f32);
+    assert!(!filter.is_stable());
+    filter.set_coefficients(1f32, 0f32, 0f32, 0f32, -1.21f32);
+    assert!((filter.stability_margin() - 1.1f32).abs() < EPSILON);
+    assert!(!filter.is_stable());
+  }
 }
 
 #[cfg(test)]
@@ -314,4 +499,31 @@ mod form2 {
       assert!((expected[i] - actual[i]).abs() < EPSILON);
     }
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn from_zpk() {
+    use num::Complex;
+    // The zeros and poles are shared with `form1::poles_and_zeros`, and the
+    // transposed structure must give the same impulse response
+    let pole = Complex::from_polar(&0.8f64, &0.3f64);
+    let zeros = [Complex::new(-1f64, 0f64), Complex::new(0.5f64, 0f64)];
+    let mut filter = Biquad2::from_zpk(&zeros, &[pole.conj(), pole], 2f64);
+    let mut reference = Biquad1::from_zpk(&zeros, &[pole.conj(), pole], 2f64);
+    assert!((filter.stability_margin() - 0.8f64).abs() < 1e-12);
+    for n in 0..64 {
+      let sample = if n == 0 { 1f64 } else { 0f64 };
+      let output = filter.process(sample);
+      assert!((output - reference.process(sample)).abs() < 1e-12);
+      // The response decays with the pole radius
+      assert!(output.abs() < 10f64 * 0.8f64.powi(n));
+    }
+
+    // The zero at -1 removes the Nyquist frequency
+    filter.clear();
+    let mut output = 0f64;
+    for n in 0..200 {
+      output = filter.process(if n % 2 == 0 { 1f64 } else { -1f64 });
+    }
+    assert!(output.abs() < 1e-12);
+  }
+}
@@ -0,0 +1,196 @@
+//! Butterworth filter designs.
+//!
+//! A Butterworth filter has a maximally flat passband and a monotonic
+//! response, with a gain of -3dB at the cutoff frequency. Filters are
+//! designed from an analog prototype using the bilinear transform, and are
+//! returned as a `Cascade` of `Biquad2` sections.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+
+/// Returns the normalized analog prototype of the given `order`.
+fn prototype(order: usize) -> Prototype {
+  let n = order as f64;
+  let poles = (0..order)
+    .map(|k| {
+      let theta = PI * (2f64 * k as f64 + n + 1f64) / (2f64 * n);
+      Complex::new(theta.cos(), theta.sin())
+    })
+    .collect();
+
+  Prototype {
+    zeros: Vec::new(),
+    poles: poles,
+    gain: 1f64
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  design::digitize(prototype(order), design::cast(sample_rate), transform)
+}
+
+/// Designs a low-pass Butterworth filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::butterworth;
+///
+/// let mut filter = butterworth::lowpass(4, 44_100f32, 1_000f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass Butterworth filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass Butterworth filter, passing frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop Butterworth filter, rejecting frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+  use filter::design::magnitude;
+  use filter::rbj;
+  use ::traits::Processor;
+
+  #[test]
+  fn second_order_matches_rbj() {
+    let sample_rate = 44_100f32;
+    let cutoff = 1_200f32;
+
+    let mut expected = rbj::LowPass::new();
+    expected.set_coefficients(sample_rate, cutoff, 0.5f32.sqrt());
+    let mut actual = lowpass(2, sample_rate, cutoff);
+    assert_eq!(actual.order(), 2);
+
+    let input = vec![0.5f32, 0.4f32, 0.3f32, 0.2f32, 0.1f32];
+    for sample in input.iter() {
+      let expected = expected.process(*sample);
+      let actual = actual.process(*sample);
+      println!("{:.12} - {:.12} = {:.12}", expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-6f32);
+    }
+  }
+
+  #[test]
+  fn order() {
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let sample_rate = 48_000f64;
+    for order in 1..9 {
+      let filter = lowpass(order, sample_rate, 2_000f64);
+      assert!((magnitude(&filter, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!(magnitude(&filter, 23_999f64, sample_rate) < 1e-3);
+    }
+  }
+
+  #[test]
+  fn highpass_response() {
+    let sample_rate = 48_000f64;
+    for order in 1..9 {
+      let filter = highpass(order, sample_rate, 2_000f64);
+      assert!(magnitude(&filter, 0f64, sample_rate) < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!((magnitude(&filter, 24_000f64, sample_rate) - 1f64).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn bandpass_response() {
+    let sample_rate = 48_000f64;
+    for order in 1..9 {
+      let filter = bandpass(order, sample_rate, 1_000f64, 4_000f64);
+      let center = (design::warp(1_000f64, sample_rate)
+                  * design::warp(4_000f64, sample_rate)).sqrt();
+      let center = (center / 2f64).atan() * sample_rate / PI;
+      assert!(magnitude(&filter, 0f64, sample_rate) < 1e-9);
+      assert!((magnitude(&filter, center, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&filter, 1_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!((magnitude(&filter, 4_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!(magnitude(&filter, 24_000f64, sample_rate) < 1e-9);
+    }
+  }
+
+  #[test]
+  fn bandstop_response() {
+    let sample_rate = 48_000f64;
+    for order in 1..9 {
+      let filter = bandstop(order, sample_rate, 1_000f64, 4_000f64);
+      let center = (design::warp(1_000f64, sample_rate)
+                  * design::warp(4_000f64, sample_rate)).sqrt();
+      let center = (center / 2f64).atan() * sample_rate / PI;
+      assert!((magnitude(&filter, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!(magnitude(&filter, center, sample_rate) < 1e-6);
+      assert!((magnitude(&filter, 1_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!((magnitude(&filter, 4_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+      assert!((magnitude(&filter, 24_000f64, sample_rate) - 1f64).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn stable() {
+    let mut filter = lowpass(12, 44_100f32, 100f32);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 1f32).abs() < 1e-3f32);
+  }
+}
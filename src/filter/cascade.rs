@@ -0,0 +1,197 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use filter::Biquad2;
+use traits::{FloatConst, FrequencyResponse, Processor};
+
+/// A series of `Biquad2` sections processed one after another.
+///
+/// This is the processor type produced by the filter designers, such as
+/// `filter::butterworth`. Odd order designs end with a first-order section,
+/// which is a `Biquad2` where `b2` and `a2` are zero.
+pub struct Cascade<T> {
+  sections: Vec<Biquad2<T>>,
+  output: T
+}
+
+impl<T> Cascade<T> where T: Float {
+  /// Creates a new `Cascade` without any sections.
+  ///
+  /// An empty cascade does not alter the input signal.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// # #![allow(unused_mut)]
+  /// use rasp::filter::Cascade;
+  ///
+  /// let mut filter1: Cascade<f32> = Cascade::new();
+  /// let mut filter2: Cascade<f64> = Cascade::new();
+  /// let mut filter3 = Cascade::<f32>::new();
+  /// let mut filter4 = Cascade::<f64>::new();
+  /// ```
+  pub fn new() -> Self {
+    Cascade {
+      sections: Vec::new(),
+      output: num::zero()
+    }
+  }
+
+  /// Creates a new `Cascade` from the given sections, in processing order.
+  pub fn from_sections(sections: Vec<Biquad2<T>>) -> Self {
+    Cascade {
+      sections: sections,
+      output: num::zero()
+    }
+  }
+
+  /// Appends a section to the end of the cascade.
+  pub fn push(&mut self, section: Biquad2<T>) {
+    self.sections.push(section);
+  }
+
+  /// Returns the sections of the cascade, in processing order.
+  pub fn sections(&self) -> &[Biquad2<T>] {
+    &self.sections
+  }
+
+  /// Returns the sections of the cascade, in processing order, as mutable.
+  pub fn sections_mut(&mut self) -> &mut [Biquad2<T>] {
+    &mut self.sections
+  }
+
+  /// Returns the order of the cascade.
+  ///
+  /// Each section adds two to the order, unless it's a first-order section.
+  pub fn order(&self) -> usize {
+    self.sections.iter().fold(0, |order, section| {
+      if section.a2 == T::zero() && section.b2 == T::zero() {
+        order + 1
+      }
+      else {
+        order + 2
+      }
+    })
+  }
+}
+
+impl<T> Processor<T> for Cascade<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    self.output = self.sections
+      .iter_mut()
+      .fold(sample, |input, section| section.process(input));
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for section in self.sections.iter_mut() {
+      section.clear();
+    }
+    self.output = num::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+impl<T> FrequencyResponse<T> for Cascade<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    let one = Complex::new(T::one(), T::zero());
+    self.sections.iter().fold(one, |response, section| {
+      response * section.response(frequency, sample_rate)
+    })
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.sections.iter().fold(T::zero(), |delay, section| {
+      delay + section.group_delay(frequency, sample_rate)
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::EPSILON;
+  use filter::Biquad2;
+  use ::traits::{FrequencyResponse, Processor};
+
+  #[test]
+  fn process() {
+    let input = vec![0.55f32, -0.55f32, 0.55f32, -0.55f32, 0.25f32];
+    let mut cascade = Cascade::new();
+
+    for sample in input.iter() {
+      assert!((cascade.process(*sample) - sample).abs() < EPSILON);
+    }
+
+    let mut first  = Biquad2::new();
+    let mut second = Biquad2::new();
+    let mut expected = Biquad2::new();
+    let mut reference = Biquad2::new();
+    first.set_coefficients(0.5f32, 0.4f32, 0.3f32, 0.2f32, 0.1f32);
+    second.set_coefficients(0.9f32, 0.1f32, 0f32, -0.3f32, 0f32);
+    expected.set_coefficients(0.5f32, 0.4f32, 0.3f32, 0.2f32, 0.1f32);
+    reference.set_coefficients(0.9f32, 0.1f32, 0f32, -0.3f32, 0f32);
+
+    cascade = Cascade::from_sections(vec![first, second]);
+    assert_eq!(cascade.order(), 3);
+
+    for sample in input.iter() {
+      let output = reference.process(expected.process(*sample));
+      let actual = cascade.process(*sample);
+      println!("{:.12} - {:.12} = {:.12}", output, actual, output - actual);
+      assert!((output - actual).abs() < EPSILON);
+      assert!((cascade.last_out() - actual).abs() < EPSILON);
+    }
+
+    cascade.clear();
+    assert!((cascade.last_out() - 0f32).abs() < EPSILON);
+  }
+
+  #[test]
+  fn frequency_response() {
+    let mut first  = Biquad2::new();
+    let mut second = Biquad2::new();
+    first.set_coefficients(0.5f64, 0.4f64, 0.3f64, 0.2f64, 0.1f64);
+    second.set_coefficients(0.9f64, 0.1f64, 0f64, -0.3f64, 0f64);
+    let cascade = Cascade::from_sections(vec![first, second]);
+
+    // The DC gain is the ratio of the coefficient sums
+    let dc = (1.2f64 / 1.3f64) * (1f64 / 0.7f64);
+    assert!((cascade.magnitude(0f64, 48_000f64) - dc).abs() < 1e-12);
+    assert!(cascade.phase(0f64, 48_000f64).abs() < 1e-12);
+
+    // The group delay is the derivative of the phase
+    for frequency in [100f64, 1_000f64, 10_000f64].iter() {
+      let delta = 1e-3f64;
+      let w = 2f64 * ::std::f64::consts::PI * delta / 48_000f64;
+      let upper = cascade.response(frequency + delta, 48_000f64);
+      let lower = cascade.response(frequency - delta, 48_000f64);
+      let expected = -(upper / lower).arg() / (2f64 * w);
+      let actual = cascade.group_delay(*frequency, 48_000f64);
+      println!("{:.9} - {:.9} = {:.9}", expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-6);
+    }
+  }
+
+  #[test]
+  fn sweep() {
+    let mut section = Biquad2::new();
+    section.set_coefficients(0.5f32, 0.4f32, 0.3f32, 0.2f32, 0.1f32);
+    let cascade = Cascade::from_sections(vec![section]);
+
+    let sweep = cascade.sweep(48_000f32, 20f32, 20_000f32, 31);
+    assert_eq!(sweep.frequencies.len(), 31);
+    assert!((sweep.frequencies[0] - 20f32).abs() < 1e-3);
+    assert!((sweep.frequencies[30] - 20_000f32).abs() < 1e-1);
+    assert!((sweep.frequencies[10] - 200f32).abs() < 1e-2);
+    for (i, frequency) in sweep.frequencies.iter().enumerate() {
+      let expected = cascade.magnitude_db(*frequency, 48_000f32);
+      assert!((sweep.magnitude_db[i] - expected).abs() < EPSILON);
+      assert!(sweep.group_delay[i].is_finite());
+    }
+  }
+}
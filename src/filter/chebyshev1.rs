@@ -0,0 +1,207 @@
+//! Chebyshev type I filter designs.
+//!
+//! A Chebyshev type I filter has an equiripple passband and a monotonic
+//! stopband. It has a steeper transition than a Butterworth filter of the
+//! same order, at the cost of `ripple`, in dB, in the passband. The cutoff
+//! frequency is the edge of the passband, where the response last touches
+//! `-ripple` dB. Filters are returned as a `Cascade` of `Biquad2` sections.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+
+/// Returns the normalized analog prototype of the given `order` and passband
+/// `ripple`, in dB.
+fn prototype(order: usize, ripple: f64) -> Prototype {
+  let n = order as f64;
+  let epsilon = (10f64.powf(ripple / 10f64) - 1f64).sqrt();
+  let mu = (1f64 / epsilon).asinh() / n;
+
+  let poles: Vec<Complex<f64>> = (0..order)
+    .map(|k| {
+      let theta = PI * (2f64 * k as f64 - n + 1f64) / (2f64 * n);
+      -Complex::new(mu, theta).sinh()
+    })
+    .collect();
+
+  // Even orders start at the bottom of the passband ripple
+  let product = poles
+    .iter()
+    .fold(Complex::new(1f64, 0f64), |product, pole| product * -pole)
+    .re;
+  let gain =
+    if order % 2 == 1 {
+      product
+    }
+    else {
+      product / (1f64 + epsilon * epsilon).sqrt()
+    };
+
+  Prototype {
+    zeros: Vec::new(),
+    poles: poles,
+    gain: gain
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    ripple: T,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  debug_assert!(ripple > T::zero());
+  design::digitize(prototype(order, design::cast(ripple)),
+                   design::cast(sample_rate),
+                   transform)
+}
+
+/// Designs a low-pass Chebyshev type I filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::chebyshev1;
+///
+/// // 0.5dB of passband ripple
+/// let mut filter = chebyshev1::lowpass(4, 44_100f32, 1_000f32, 0.5f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T,
+                         ripple: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, ripple, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass Chebyshev type I filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          ripple: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, ripple, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass Chebyshev type I filter, passing frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          ripple: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, ripple, Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop Chebyshev type I filter, rejecting frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          ripple: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, ripple, Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::design::magnitude;
+  use ::traits::Processor;
+
+  #[test]
+  fn order() {
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64, 1f64).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64, 1f64).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64, 1f64).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64, 1f64).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let sample_rate = 48_000f64;
+    let ripple = 10f64.powf(-1f64 / 20f64);
+    for order in 1..9 {
+      let filter = lowpass(order, sample_rate, 2_000f64, 1f64);
+      let dc = if order % 2 == 1 { 1f64 } else { ripple };
+      assert!((magnitude(&filter, 0f64, sample_rate) - dc).abs() < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - ripple).abs() < 1e-9);
+
+      // The passband stays within the ripple
+      for i in 0..200 {
+        let frequency = 2_000f64 * i as f64 / 200f64;
+        let actual = magnitude(&filter, frequency, sample_rate);
+        assert!(actual < 1f64 + 1e-9 && actual > ripple - 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn steeper_than_butterworth() {
+    let sample_rate = 48_000f64;
+    for order in 2..9 {
+      let chebyshev = lowpass(order, sample_rate, 2_000f64, 3f64);
+      let butterworth = ::filter::butterworth::lowpass(order, sample_rate, 2_000f64);
+      assert!(magnitude(&chebyshev, 4_000f64, sample_rate)
+            < magnitude(&butterworth, 4_000f64, sample_rate));
+    }
+  }
+
+  #[test]
+  fn highpass_response() {
+    let sample_rate = 48_000f64;
+    let ripple = 10f64.powf(-0.5f64 / 20f64);
+    for order in 1..9 {
+      let filter = highpass(order, sample_rate, 2_000f64, 0.5f64);
+      let nyquist = if order % 2 == 1 { 1f64 } else { ripple };
+      assert!(magnitude(&filter, 0f64, sample_rate) < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - ripple).abs() < 1e-9);
+      assert!((magnitude(&filter, 24_000f64, sample_rate) - nyquist).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn band_response() {
+    let sample_rate = 48_000f64;
+    let ripple = 10f64.powf(-1f64 / 20f64);
+    for order in 1..9 {
+      let bandpass = bandpass(order, sample_rate, 1_000f64, 4_000f64, 1f64);
+      let bandstop = bandstop(order, sample_rate, 1_000f64, 4_000f64, 1f64);
+      for edge in [1_000f64, 4_000f64].iter() {
+        assert!((magnitude(&bandpass, *edge, sample_rate) - ripple).abs() < 1e-9);
+        assert!((magnitude(&bandstop, *edge, sample_rate) - ripple).abs() < 1e-9);
+      }
+      assert!(magnitude(&bandpass, 0f64, sample_rate) < 1e-9);
+      assert!(magnitude(&bandpass, 24_000f64, sample_rate) < 1e-9);
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(8, 44_100f32, 500f32, 1f32);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 10f32.powf(-1f32 / 20f32)).abs() < 1e-3f32);
+  }
+}
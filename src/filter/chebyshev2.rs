@@ -0,0 +1,192 @@
+//! Chebyshev type II, or inverse Chebyshev, filter designs.
+//!
+//! A Chebyshev type II filter has a monotonic passband and an equiripple
+//! stopband that is at least `attenuation` dB below the passband. The cutoff
+//! frequency is the edge of the stopband, where the response first reaches
+//! `-attenuation` dB. Filters are returned as a `Cascade` of `Biquad2`
+//! sections.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+
+/// Returns the normalized analog prototype of the given `order` and stopband
+/// `attenuation`, in dB.
+fn prototype(order: usize, attenuation: f64) -> Prototype {
+  let n = order as f64;
+  let delta = 1f64 / (10f64.powf(attenuation / 10f64) - 1f64).sqrt();
+  let mu = (1f64 / delta).asinh() / n;
+
+  // The zero at infinity, for odd orders, is left out
+  let zeros: Vec<Complex<f64>> = (0..order)
+    .map(|k| 2f64 * k as f64 - n + 1f64)
+    .filter(|m| *m != 0f64)
+    .map(|m| Complex::new(0f64, 1f64 / (PI * m / (2f64 * n)).sin()))
+    .collect();
+
+  let poles: Vec<Complex<f64>> = (0..order)
+    .map(|k| {
+      let theta = PI * (2f64 * k as f64 - n + 1f64) / (2f64 * n);
+      Complex::new(-mu.sinh() * theta.cos(), -mu.cosh() * theta.sin()).inv()
+    })
+    .collect();
+
+  let product = |roots: &[Complex<f64>]| {
+    roots.iter().fold(Complex::new(1f64, 0f64), |product, root| product * -root)
+  };
+  let gain = (product(&poles) / product(&zeros)).re;
+
+  Prototype {
+    zeros: zeros,
+    poles: poles,
+    gain: gain
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    attenuation: T,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  debug_assert!(attenuation > T::zero());
+  design::digitize(prototype(order, design::cast(attenuation)),
+                   design::cast(sample_rate),
+                   transform)
+}
+
+/// Designs a low-pass Chebyshev type II filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::chebyshev2;
+///
+/// // Stopband starts at 2kHz, and is attenuated by 60dB
+/// let mut filter = chebyshev2::lowpass(6, 44_100f32, 2_000f32, 60f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T,
+                         attenuation: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, attenuation, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass Chebyshev type II filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, attenuation, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass Chebyshev type II filter, where the stopbands start at
+/// `low_frequency` and `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, attenuation, Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop Chebyshev type II filter, where the stopband lies
+/// between `low_frequency` and `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, attenuation, Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::design::magnitude;
+  use ::traits::Processor;
+
+  #[test]
+  fn order() {
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64, 40f64).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64, 40f64).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64, 40f64).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64, 40f64).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let sample_rate = 48_000f64;
+    let attenuation = 10f64.powf(-40f64 / 20f64);
+    for order in 1..9 {
+      let filter = lowpass(order, sample_rate, 4_000f64, 40f64);
+      assert!((magnitude(&filter, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&filter, 4_000f64, sample_rate) - attenuation).abs() < 1e-9);
+
+      // The stopband stays below the attenuation
+      for i in 0..200 {
+        let frequency = 4_000f64 + 20_000f64 * i as f64 / 200f64;
+        assert!(magnitude(&filter, frequency, sample_rate) < attenuation + 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn highpass_response() {
+    let sample_rate = 48_000f64;
+    let attenuation = 10f64.powf(-60f64 / 20f64);
+    for order in 1..9 {
+      let filter = highpass(order, sample_rate, 2_000f64, 60f64);
+      assert!(magnitude(&filter, 0f64, sample_rate) < attenuation + 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - attenuation).abs() < 1e-9);
+      assert!((magnitude(&filter, 24_000f64, sample_rate) - 1f64).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn band_response() {
+    let sample_rate = 48_000f64;
+    let attenuation = 10f64.powf(-50f64 / 20f64);
+    for order in 1..9 {
+      let bandpass = bandpass(order, sample_rate, 1_000f64, 4_000f64, 50f64);
+      let bandstop = bandstop(order, sample_rate, 1_000f64, 4_000f64, 50f64);
+      for edge in [1_000f64, 4_000f64].iter() {
+        assert!((magnitude(&bandpass, *edge, sample_rate) - attenuation).abs() < 1e-9);
+        assert!((magnitude(&bandstop, *edge, sample_rate) - attenuation).abs() < 1e-9);
+      }
+      assert!((magnitude(&bandstop, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&bandstop, 24_000f64, sample_rate) - 1f64).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(8, 44_100f32, 2_000f32, 60f32);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 1f32).abs() < 1e-3f32);
+  }
+}
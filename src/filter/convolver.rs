@@ -0,0 +1,358 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use fft::RealFft;
+use traits::{FloatConst, Processor};
+
+/* Notes on the uniformly partitioned overlap-save convolution (UPOLS)
+  - The impulse response is split into partitions of `block_size` samples,
+    and each partition is zero-padded to twice the block size and transformed
+    once, when the impulse response is set
+  - Every `block_size` samples, the last two blocks of input are transformed,
+    and the spectrum is pushed into a frequency-domain delay line that holds
+    one spectrum per partition
+  - The spectrum of the output block is the sum of each delayed input spectrum
+    multiplied by the spectrum of the matching partition; the second half of
+    its inverse transform is the output block, the first half being circular
+    aliasing that is discarded
+  - The cost per sample is two transforms of twice the block size per block,
+    and one complex multiply-add per bin and partition, instead of one
+    multiply-add per tap
+  - Output blocks are read while the next input block is written, so the
+    latency is `block_size` samples
+  - When the impulse response is replaced with a crossfade, both filters are
+    applied to the same delay line until the crossfade ends
+*/
+
+/// A convolution processor for long impulse responses.
+///
+/// The impulse response is convolved with uniformly partitioned overlap-save
+/// convolution, which has a latency of `block_size` samples. Smaller blocks
+/// reduce the latency but increase the cost of long impulse responses.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::Convolver;
+///
+/// let impulse_response = vec![0.5f32; 4_000];
+/// let mut convolver = Convolver::new(&impulse_response, 256);
+/// assert_eq!(convolver.latency(), 256);
+///
+/// let mut output = 0f32;
+/// for _ in 0..256 {
+///   output = convolver.process(1f32);
+/// }
+/// assert_eq!(output, 0f32);
+/// output = convolver.process(1f32);
+/// # assert!((output - 0.5f32).abs() < 1e-5f32);
+/// ```
+pub struct Convolver<T> {
+  block_size: usize,
+  fft: RealFft<T>,
+  /// The spectra of the partitions of the impulse response
+  partitions: Vec<Vec<Complex<T>>>,
+  /// The partitions of an impulse response being crossfaded to
+  next: Option<Vec<Vec<Complex<T>>>>,
+  fade_length: usize,
+  fade_position: usize,
+  /// The frequency-domain delay line, as a circular buffer of spectra
+  spectra: Vec<Vec<Complex<T>>>,
+  newest: usize,
+  /// The previous and the current input blocks
+  input: Vec<T>,
+  frame: Vec<T>,
+  accumulator: Vec<Complex<T>>,
+  output: Vec<T>,
+  faded: Vec<T>,
+  index: usize,
+  last: T
+}
+
+impl<T> Convolver<T> where T: Float + FloatConst {
+  /// Creates a new `Convolver` with an `impulse_response`, processed in
+  /// blocks of `block_size` samples.
+  pub fn new(impulse_response: &[T], block_size: usize) -> Self {
+    debug_assert!(block_size > 0);
+    let mut fft = RealFft::new(2 * block_size);
+    let zero = Complex::new(T::zero(), T::zero());
+    let partitions = partition(&mut fft, impulse_response, block_size);
+    let bins = fft.spectrum_size();
+    Convolver {
+      block_size: block_size,
+      fft: fft,
+      spectra: vec![vec![zero; bins]; partitions.len()],
+      partitions: partitions,
+      next: None,
+      fade_length: 0,
+      fade_position: 0,
+      newest: 0,
+      input: vec![T::zero(); 2 * block_size],
+      frame: vec![T::zero(); 2 * block_size],
+      accumulator: vec![zero; bins],
+      output: vec![T::zero(); block_size],
+      faded: vec![T::zero(); block_size],
+      index: 0,
+      last: T::zero()
+    }
+  }
+
+  /// Returns the number of samples in a block.
+  pub fn block_size(&self) -> usize {
+    self.block_size
+  }
+
+  /// Returns the latency, in samples, which is the block size.
+  pub fn latency(&self) -> usize {
+    self.block_size
+  }
+
+  /// Returns the number of partitions of the impulse response.
+  pub fn partitions(&self) -> usize {
+    self.partitions.len()
+  }
+
+  /// Returns `true` if the impulse response is being crossfaded.
+  pub fn is_crossfading(&self) -> bool {
+    self.next.is_some()
+  }
+
+  /// Replaces the impulse response, crossfading from the previous one over
+  /// `crossfade` samples.
+  ///
+  /// The input history is kept, so the new impulse response applies to the
+  /// samples before the swap as well. When the new impulse response is longer
+  /// than any previous one, the history is only as long as the previous one,
+  /// so its tail fades in as new input arrives. This allocates and transforms
+  /// the new partitions, so it should not be called for every block.
+  ///
+  /// If a crossfade is in progress, it is completed first.
+  pub fn set_impulse_response(&mut self, impulse_response: &[T], crossfade: usize) {
+    if let Some(next) = self.next.take() {
+      self.partitions = next;
+    }
+    let partitions = partition(&mut self.fft, impulse_response, self.block_size);
+    self.resize(partitions.len());
+    if crossfade == 0 {
+      self.partitions = partitions;
+    }
+    else {
+      self.next = Some(partitions);
+      self.fade_length = crossfade;
+      self.fade_position = 0;
+    }
+  }
+
+  /// Grows the delay line to hold at least `length` spectra, keeping the
+  /// newest spectra.
+  fn resize(&mut self, length: usize) {
+    let current = self.spectra.len();
+    if length <= current {
+      return;
+    }
+    let zero = Complex::new(T::zero(), T::zero());
+    let mut spectra = vec![vec![zero; self.accumulator.len()]; length];
+    for age in 0..current {
+      let index = (self.newest + current - age) % current;
+      spectra[length - 1 - age] = self.spectra[index].clone();
+    }
+    self.spectra = spectra;
+    self.newest = length - 1;
+  }
+
+  /// Transforms the current input block and computes the next output block.
+  fn process_block_partitions(&mut self) {
+    let length = self.spectra.len();
+    self.newest = (self.newest + 1) % length;
+    self.fft.forward(&self.input, &mut self.spectra[self.newest]);
+    let (previous, current) = self.input.split_at_mut(self.block_size);
+    previous.copy_from_slice(current);
+
+    convolve(&mut self.fft, &self.spectra, self.newest, &self.partitions,
+             &mut self.accumulator, &mut self.frame, &mut self.output);
+
+    if let Some(next) = self.next.take() {
+      convolve(&mut self.fft, &self.spectra, self.newest, &next,
+               &mut self.accumulator, &mut self.frame, &mut self.faded);
+      let length: T = num::cast(self.fade_length).unwrap();
+      for (output, faded) in self.output.iter_mut().zip(self.faded.iter()) {
+        let position = self.fade_position.min(self.fade_length);
+        let gain: T = num::cast::<usize, T>(position).unwrap() / length;
+        *output = *output + (*faded - *output) * gain;
+        self.fade_position += 1;
+      }
+      if self.fade_position >= self.fade_length {
+        self.partitions = next;
+      }
+      else {
+        self.next = Some(next);
+      }
+    }
+  }
+}
+
+/// Returns the spectra of the partitions of `impulse_response`, scaled for
+/// the inverse transform.
+fn partition<T>(fft: &mut RealFft<T>,
+                impulse_response: &[T],
+                block_size: usize) -> Vec<Vec<Complex<T>>>
+  where T: Float + FloatConst
+{
+  debug_assert!(!impulse_response.is_empty());
+  let scale: T = T::one() / num::cast(2 * block_size).unwrap();
+  let mut frame = vec![T::zero(); 2 * block_size];
+  impulse_response.chunks(block_size).map(|chunk| {
+    for x in frame.iter_mut() {
+      *x = T::zero();
+    }
+    for (x, sample) in frame.iter_mut().zip(chunk.iter()) {
+      *x = *sample * scale;
+    }
+    let mut spectrum = vec![Complex::new(T::zero(), T::zero()); fft.spectrum_size()];
+    fft.forward(&frame, &mut spectrum);
+    spectrum
+  }).collect()
+}
+
+/// Writes the output block of the convolution of the delay line with
+/// `partitions` to `output`.
+fn convolve<T>(fft: &mut RealFft<T>,
+               spectra: &[Vec<Complex<T>>],
+               newest: usize,
+               partitions: &[Vec<Complex<T>>],
+               accumulator: &mut [Complex<T>],
+               frame: &mut [T],
+               output: &mut [T])
+  where T: Float + FloatConst
+{
+  for bin in accumulator.iter_mut() {
+    *bin = Complex::new(T::zero(), T::zero());
+  }
+  let length = spectra.len();
+  for (age, partition) in partitions.iter().enumerate() {
+    let spectrum = &spectra[(newest + length - age) % length];
+    for ((bin, x), h) in accumulator.iter_mut().zip(spectrum.iter()).zip(partition.iter()) {
+      *bin = *bin + *x * *h;
+    }
+  }
+  fft.inverse(accumulator, frame);
+  output.copy_from_slice(&frame[output.len()..]);
+}
+
+impl<T> Processor<T> for Convolver<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    self.input[self.block_size + self.index] = sample;
+    self.last = self.output[self.index];
+    self.index += 1;
+    if self.index == self.block_size {
+      self.process_block_partitions();
+      self.index = 0;
+    }
+    self.last
+  }
+
+  fn clear(&mut self) {
+    for spectrum in self.spectra.iter_mut() {
+      for bin in spectrum.iter_mut() {
+        *bin = Complex::new(T::zero(), T::zero());
+      }
+    }
+    for sample in self.input.iter_mut().chain(self.output.iter_mut()) {
+      *sample = T::zero();
+    }
+    self.index = 0;
+    self.last = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.last
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::Fir;
+
+  fn noise(length: usize, seed: u32) -> Vec<f64> {
+    let mut state = seed;
+    (0..length).map(|_| {
+      state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+      state as f64 / ::std::u32::MAX as f64 - 0.5f64
+    }).collect()
+  }
+
+  #[test]
+  fn direct_convolution() {
+    let input = noise(2_000, 1);
+    let sizes = [(1, 16, 1), (16, 16, 1), (100, 16, 7), (777, 64, 13), (300, 500, 1)];
+    for &(length, block_size, partitions) in sizes.iter() {
+      let impulse_response = noise(length, 2);
+      let mut convolver = Convolver::new(&impulse_response, block_size);
+      let mut fir = Fir::new(impulse_response.clone());
+      assert_eq!(convolver.partitions(), partitions);
+
+      let expected: Vec<f64> = input.iter().map(|x| fir.process(*x)).collect();
+      for (n, x) in input.iter().enumerate() {
+        let actual = convolver.process(*x);
+        let expected = if n >= block_size { expected[n - block_size] } else { 0f64 };
+        assert!((expected - actual).abs() < 1e-9, "{}: {} != {}", n, expected, actual);
+        assert_eq!(actual, convolver.last_out());
+      }
+    }
+  }
+
+  #[test]
+  fn crossfade() {
+    let input = noise(3_000, 3);
+    let first = noise(250, 4);
+    let second = noise(100, 5);
+    let mut old = Fir::new(first.clone());
+    let mut new = Fir::new(second.clone());
+    let old: Vec<f64> = input.iter().map(|x| old.process(*x)).collect();
+    let new: Vec<f64> = input.iter().map(|x| new.process(*x)).collect();
+
+    // The swap is after 1_000 samples, and takes effect with the next block
+    let (block_size, crossfade) = (32, 200);
+    let mut convolver = Convolver::new(&first, block_size);
+    for (n, x) in input.iter().enumerate() {
+      if n == 1_000 {
+        convolver.set_impulse_response(&second, crossfade);
+        assert!(convolver.is_crossfading());
+      }
+      let actual = convolver.process(*x);
+      if n < block_size {
+        continue;
+      }
+
+      let start = 1_024;
+      let m = n - block_size;
+      let expected = if n < start {
+        old[m]
+      }
+      else if n < start + crossfade {
+        let gain = (n - start) as f64 / crossfade as f64;
+        old[m] + (new[m] - old[m]) * gain
+      }
+      else {
+        new[m]
+      };
+      assert!((expected - actual).abs() < 1e-9, "{}: {} != {}", n, expected, actual);
+    }
+    assert!(!convolver.is_crossfading());
+    assert_eq!(convolver.partitions(), 4);
+  }
+
+  #[test]
+  fn swap() {
+    let mut convolver = Convolver::new(&[1f32], 4);
+    convolver.set_impulse_response(&[0f32, 0f32, 2f32], 0);
+    let impulse: Vec<f32> = (0..8).map(|n| convolver.process(if n == 0 { 1f32 } else { 0f32 })).collect();
+    assert_eq!(impulse, vec![0f32, 0f32, 0f32, 0f32, 0f32, 0f32, 2f32, 0f32]);
+
+    convolver.clear();
+    assert_eq!(convolver.process(1f32), 0f32);
+  }
+}
@@ -0,0 +1,359 @@
+//! The analog prototype to digital filter pipeline shared by the IIR filter
+//! designers.
+
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+use filter::{Biquad2, Cascade};
+
+/* Notes on the design pipeline
+  - Every IIR family is designed as a normalized analog low-pass prototype,
+    with a cutoff of 1 rad/s, described by its zeros, poles and gain
+  - The prototype is moved to the requested band in the s-plane, using band
+    edges that are pre-warped for the bilinear transform
+  - The bilinear transform maps the analog zeros and poles into the z-plane
+    - Zeros at infinity are placed at the Nyquist frequency, z = -1
+  - The zeros and poles are then grouped into second-order sections
+    - Poles closest to the unit circle are paired with their nearest zeros
+      first, and the most resonant section is processed last
+    - The overall gain is applied to the first section
+  - The whole design is done with `f64`, only the final coefficients are cast
+    to the sample type, so `f32` filters don't suffer from a less precise
+    design
+*/
+
+/// Roots with an imaginary part smaller than this are considered real.
+const TOLERANCE: f64 = 1e-9;
+
+/// An analog filter, described by its zeros, poles and gain.
+///
+/// Complex zeros and poles must be in conjugate pairs.
+pub struct Prototype {
+  pub zeros: Vec<Complex<f64>>,
+  pub poles: Vec<Complex<f64>>,
+  pub gain: f64
+}
+
+impl Prototype {
+  /// The number of zeros at infinity.
+  fn degree(&self) -> usize {
+    self.poles.len() - self.zeros.len()
+  }
+}
+
+/// The band a normalized low-pass prototype is transformed to.
+///
+/// All frequencies are in hertz.
+#[derive(Clone, Copy, Debug)]
+pub enum Transform {
+  LowPass(f64),
+  HighPass(f64),
+  BandPass(f64, f64),
+  BandStop(f64, f64)
+}
+
+/// Casts a design parameter to `f64`.
+pub fn cast<T: Float>(value: T) -> f64 {
+  num::cast(value).unwrap()
+}
+
+/// Pre-warps a frequency, in hertz, to an analog frequency for the bilinear
+/// transform with a sample rate of one half.
+pub fn warp(frequency: f64, sample_rate: f64) -> f64 {
+  2f64 * (PI * frequency / sample_rate).tan()
+}
+
+/// Transforms a normalized low-pass `prototype` to the given `transform` and
+/// returns the digital filter as a cascade of `Biquad2` sections.
+pub fn digitize<T: Float>(prototype: Prototype,
+                          sample_rate: f64,
+                          transform: Transform) -> Cascade<T>
+{
+  let analog = match transform {
+    Transform::LowPass(cutoff) =>
+      to_lowpass(prototype, warp(cutoff, sample_rate)),
+    Transform::HighPass(cutoff) =>
+      to_highpass(prototype, warp(cutoff, sample_rate)),
+    Transform::BandPass(low, high) =>
+      to_bandpass(prototype,
+                  warp(low, sample_rate),
+                  warp(high, sample_rate)),
+    Transform::BandStop(low, high) =>
+      to_bandstop(prototype,
+                  warp(low, sample_rate),
+                  warp(high, sample_rate))
+  };
+
+  to_sections(bilinear(analog))
+}
+
+fn product(roots: &[Complex<f64>], offset: f64) -> Complex<f64> {
+  roots.iter().fold(Complex::new(1f64, 0f64), |acc, root| {
+    acc * (Complex::new(offset, 0f64) - root)
+  })
+}
+
+fn to_lowpass(prototype: Prototype, cutoff: f64) -> Prototype {
+  let degree = prototype.degree() as i32;
+  Prototype {
+    zeros: prototype.zeros.iter().map(|zero| zero * cutoff).collect(),
+    poles: prototype.poles.iter().map(|pole| pole * cutoff).collect(),
+    gain: prototype.gain * cutoff.powi(degree)
+  }
+}
+
+fn to_highpass(prototype: Prototype, cutoff: f64) -> Prototype {
+  let degree = prototype.degree();
+  let gain = prototype.gain
+           * (product(&prototype.zeros, 0f64)
+              / product(&prototype.poles, 0f64)).re;
+
+  let mut zeros: Vec<Complex<f64>> =
+    prototype.zeros.iter().map(|zero| zero.inv() * cutoff).collect();
+  zeros.extend((0..degree).map(|_| Complex::new(0f64, 0f64)));
+
+  Prototype {
+    zeros: zeros,
+    poles: prototype.poles.iter().map(|pole| pole.inv() * cutoff).collect(),
+    gain: gain
+  }
+}
+
+/// Splits every root `r` into `r ± sqrt(r^2 - center^2)`.
+fn split(roots: &[Complex<f64>], center: f64) -> Vec<Complex<f64>> {
+  let mut split = Vec::with_capacity(roots.len() * 2);
+  for root in roots.iter() {
+    let offset = (root * root - center * center).sqrt();
+    split.push(root + offset);
+    split.push(root - offset);
+  }
+  split
+}
+
+fn to_bandpass(prototype: Prototype, low: f64, high: f64) -> Prototype {
+  let degree = prototype.degree();
+  let center = (low * high).sqrt();
+  let bandwidth = high - low;
+
+  let scale = |roots: &[Complex<f64>]| -> Vec<Complex<f64>> {
+    roots.iter().map(|root| root * (bandwidth / 2f64)).collect()
+  };
+
+  let mut zeros = split(&scale(&prototype.zeros), center);
+  zeros.extend((0..degree).map(|_| Complex::new(0f64, 0f64)));
+
+  Prototype {
+    zeros: zeros,
+    poles: split(&scale(&prototype.poles), center),
+    gain: prototype.gain * bandwidth.powi(degree as i32)
+  }
+}
+
+fn to_bandstop(prototype: Prototype, low: f64, high: f64) -> Prototype {
+  let degree = prototype.degree();
+  let center = (low * high).sqrt();
+  let bandwidth = high - low;
+  let gain = prototype.gain
+           * (product(&prototype.zeros, 0f64)
+              / product(&prototype.poles, 0f64)).re;
+
+  let invert = |roots: &[Complex<f64>]| -> Vec<Complex<f64>> {
+    roots.iter().map(|root| root.inv() * (bandwidth / 2f64)).collect()
+  };
+
+  let mut zeros = split(&invert(&prototype.zeros), center);
+  for _ in 0..degree {
+    zeros.push(Complex::new(0f64,  center));
+    zeros.push(Complex::new(0f64, -center));
+  }
+
+  Prototype {
+    zeros: zeros,
+    poles: split(&invert(&prototype.poles), center),
+    gain: gain
+  }
+}
+
+/// Maps an analog filter to the z-plane using `s = 2 * (z - 1) / (z + 1)`.
+fn bilinear(prototype: Prototype) -> Prototype {
+  let degree = prototype.degree();
+  let gain = prototype.gain
+           * (product(&prototype.zeros, 2f64)
+              / product(&prototype.poles, 2f64)).re;
+
+  let map = |roots: &[Complex<f64>]| -> Vec<Complex<f64>> {
+    roots.iter().map(|root| (root + 2f64) / (-root + 2f64)).collect()
+  };
+
+  let mut zeros = map(&prototype.zeros);
+  zeros.extend((0..degree).map(|_| Complex::new(-1f64, 0f64)));
+
+  Prototype {
+    zeros: zeros,
+    poles: map(&prototype.poles),
+    gain: gain
+  }
+}
+
+/// Groups roots into conjugate pairs, pairs of real roots, and a possible
+/// single real root.
+fn group(roots: &[Complex<f64>]) -> Vec<Vec<Complex<f64>>> {
+  let mut groups = Vec::new();
+  let mut reals = Vec::new();
+
+  for root in roots.iter() {
+    if root.im.abs() <= TOLERANCE * (1f64 + root.norm()) {
+      reals.push(root.re);
+    }
+    else if root.im > 0f64 {
+      groups.push(vec![*root, root.conj()]);
+    }
+  }
+
+  reals.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+  for pair in reals.chunks(2) {
+    groups.push(pair.iter().map(|re| Complex::new(*re, 0f64)).collect());
+  }
+
+  groups
+}
+
+/// Returns `[1, c1, c2]` for the polynomial with the given roots.
+fn polynomial(roots: &[Complex<f64>]) -> [f64; 3] {
+  match roots.len() {
+    0 => [1f64, 0f64, 0f64],
+    1 => [1f64, -roots[0].re, 0f64],
+    _ => [1f64, -(roots[0] + roots[1]).re, (roots[0] * roots[1]).re]
+  }
+}
+
+fn to_sections<T: Float>(prototype: Prototype) -> Cascade<T> {
+  let mut pole_groups = group(&prototype.poles);
+  let mut zero_groups = group(&prototype.zeros);
+
+  let radius = |poles: &Vec<Complex<f64>>| {
+    poles.iter().fold(0f64, |radius, pole| radius.max(pole.norm()))
+  };
+  pole_groups.sort_by(|a, b| {
+    radius(b).partial_cmp(&radius(a)).unwrap_or(Ordering::Equal)
+  });
+
+  let mut pairs = Vec::with_capacity(pole_groups.len());
+  for poles in pole_groups.into_iter() {
+    let distance = |zeros: &Vec<Complex<f64>>| {
+      zeros.iter().fold(f64::INFINITY, |distance, zero| {
+        distance.min((zero - poles[0]).norm())
+      })
+    };
+
+    let nearest = zero_groups
+      .iter()
+      .enumerate()
+      .filter(|&(_, zeros)| zeros.len() == poles.len())
+      .min_by(|&(_, a), &(_, b)| {
+        distance(a).partial_cmp(&distance(b)).unwrap_or(Ordering::Equal)
+      })
+      .map(|(index, _)| index);
+
+    let zeros = match nearest {
+      Some(index) => zero_groups.swap_remove(index),
+      None => Vec::new()
+    };
+    pairs.push((zeros, poles));
+  }
+  pairs.reverse();
+
+  let mut cascade = Cascade::new();
+  for (index, (zeros, poles)) in pairs.iter().enumerate() {
+    let mut b = polynomial(zeros);
+    let a = polynomial(poles);
+    if index == 0 {
+      for coefficient in b.iter_mut() {
+        *coefficient *= prototype.gain;
+      }
+    }
+
+    let mut section = Biquad2::new();
+    section.set_coefficients(num::cast(b[0]).unwrap(),
+                             num::cast(b[1]).unwrap(),
+                             num::cast(b[2]).unwrap(),
+                             num::cast(a[1]).unwrap(),
+                             num::cast(a[2]).unwrap());
+    cascade.push(section);
+  }
+  cascade
+}
+
+/// Evaluates a polynomial, with coefficients in ascending order, and its
+/// derivative at `x`.
+fn evaluate(coefficients: &[f64], x: Complex<f64>) -> (Complex<f64>, Complex<f64>) {
+  let zero = Complex::new(0f64, 0f64);
+  coefficients.iter().rev().fold((zero, zero), |(value, derivative), c| {
+    (value * x + c, derivative * x + value)
+  })
+}
+
+/// Returns the roots of a polynomial with coefficients in ascending order,
+/// using the Aberth-Ehrlich method.
+pub fn roots(coefficients: &[f64]) -> Vec<Complex<f64>> {
+  let mut coefficients = coefficients.to_vec();
+  while coefficients.last() == Some(&0f64) {
+    coefficients.pop();
+  }
+  if coefficients.len() < 2 {
+    return Vec::new();
+  }
+
+  let leading = *coefficients.last().unwrap();
+  for coefficient in coefficients.iter_mut() {
+    *coefficient /= leading;
+  }
+
+  // Start on a circle with the geometric mean radius of the roots
+  let degree = coefficients.len() - 1;
+  let radius = coefficients[0].abs().powf(1f64 / degree as f64).max(1e-3);
+  let mut roots: Vec<Complex<f64>> = (0..degree)
+    .map(|k| {
+      let theta = 2f64 * PI * k as f64 / degree as f64 + 0.4f64;
+      Complex::from_polar(&radius, &theta)
+    })
+    .collect();
+
+  for _ in 0..500 {
+    let mut converged = true;
+    for i in 0..degree {
+      let (value, derivative) = evaluate(&coefficients, roots[i]);
+      if value.norm() == 0f64 {
+        continue;
+      }
+
+      let ratio = value / derivative;
+      let repulsion = (0..degree)
+        .filter(|j| *j != i)
+        .fold(Complex::new(0f64, 0f64), |sum, j| sum + (roots[i] - roots[j]).inv());
+      let step = ratio / (-ratio * repulsion + 1f64);
+
+      roots[i] -= step;
+      if step.norm() > 1e-15 * (1f64 + roots[i].norm()) {
+        converged = false;
+      }
+    }
+
+    if converged {
+      break;
+    }
+  }
+
+  roots
+}
+
+/// Returns the magnitude response of a `Cascade` at `frequency`.
+#[cfg(test)]
+pub fn magnitude(filter: &Cascade<f64>, frequency: f64, sample_rate: f64) -> f64 {
+  use traits::FrequencyResponse;
+  filter.magnitude(frequency, sample_rate)
+}
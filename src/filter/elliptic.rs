@@ -0,0 +1,381 @@
+//! Elliptic, or Cauer, filter designs.
+//!
+//! An elliptic filter has an equiripple passband and an equiripple stopband,
+//! giving the steepest transition of any filter for a given order, passband
+//! `ripple` and stopband `attenuation`, both in dB. The cutoff frequency is
+//! the edge of the passband, where the response last touches `-ripple` dB.
+//! Filters are returned as a `Cascade` of `Biquad2` sections.
+//!
+//! Use `estimate_order` or `estimate_band_order` to find the minimum order
+//! that meets a specification.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+use filter::jacobi;
+
+/// Returns the normalized analog prototype of the given `order`, passband
+/// `ripple` and stopband `attenuation`, in dB.
+fn prototype(order: usize, ripple: f64, attenuation: f64) -> Prototype {
+  let epsilon_pass = (10f64.powf(ripple / 10f64) - 1f64).sqrt();
+  let epsilon_stop = (10f64.powf(attenuation / 10f64) - 1f64).sqrt();
+  let k1 = epsilon_pass / epsilon_stop;
+  let k = jacobi::degree(order, k1);
+
+  // The poles are offset from the zeros by `v0`, in units of K
+  let j = Complex::new(0f64, 1f64);
+  let v0 = (-j * jacobi::asne(j / epsilon_pass, k1) / order as f64).re;
+
+  let mut zeros = Vec::with_capacity(order);
+  let mut poles = Vec::with_capacity(order);
+  for i in 1..order / 2 + 1 {
+    let u = (2f64 * i as f64 - 1f64) / order as f64;
+    let zeta = jacobi::cde(Complex::new(u, 0f64), k).re;
+    let zero = Complex::new(0f64, 1f64 / (k * zeta));
+    let pole = j * jacobi::cde(Complex::new(u, -v0), k);
+    zeros.push(zero);
+    zeros.push(zero.conj());
+    poles.push(pole);
+    poles.push(pole.conj());
+  }
+  if order % 2 == 1 {
+    let pole = j * jacobi::sne(Complex::new(0f64, v0), k);
+    poles.push(Complex::new(pole.re, 0f64));
+  }
+
+  // Even orders start at the bottom of the passband ripple
+  let product = |roots: &[Complex<f64>]| {
+    roots.iter().fold(Complex::new(1f64, 0f64), |product, root| product * -root)
+  };
+  let dc_gain =
+    if order % 2 == 1 {
+      1f64
+    }
+    else {
+      10f64.powf(-ripple / 20f64)
+    };
+  let gain = dc_gain * (product(&poles) / product(&zeros)).re;
+
+  Prototype {
+    zeros: zeros,
+    poles: poles,
+    gain: gain
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    ripple: T,
+                    attenuation: T,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  debug_assert!(ripple > T::zero() && attenuation > ripple);
+  let prototype = prototype(order,
+                            design::cast(ripple),
+                            design::cast(attenuation));
+  design::digitize(prototype, design::cast(sample_rate), transform)
+}
+
+/// Returns the minimum prototype order for the selectivity `k`, the ratio of
+/// the analog passband and stopband edges.
+fn minimum_order(selectivity: f64, ripple: f64, attenuation: f64) -> usize {
+  let epsilon_pass = (10f64.powf(ripple / 10f64) - 1f64).sqrt();
+  let epsilon_stop = (10f64.powf(attenuation / 10f64) - 1f64).sqrt();
+  let k1 = epsilon_pass / epsilon_stop;
+
+  let order = jacobi::ellipk(selectivity) * jacobi::ellipk_prime(k1)
+            / (jacobi::ellipk_prime(selectivity) * jacobi::ellipk(k1));
+
+  // Guard against rounding up an exact order
+  (order - 1e-9).ceil().max(1f64) as usize
+}
+
+/// Estimates the minimum order of a low-pass or high-pass elliptic filter.
+///
+/// A low-pass filter is assumed when `passband_frequency` is less than
+/// `stopband_frequency`, otherwise a high-pass filter is assumed. The
+/// response will be within `ripple` dB up to the passband edge, and at least
+/// `attenuation` dB down from the stopband edge.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::filter::elliptic;
+///
+/// let sample_rate = 48_000f64;
+/// let order = elliptic::estimate_order(sample_rate, 20_000f64, 22_050f64, 0.1f64, 96f64);
+/// let filter = elliptic::lowpass(order, sample_rate, 20_000f64, 0.1f64, 96f64);
+/// # assert_eq!(order, 7);
+/// # assert_eq!(filter.order(), order);
+/// ```
+pub fn estimate_order<T: Float>(sample_rate: T,
+                                passband_frequency: T,
+                                stopband_frequency: T,
+                                ripple: T,
+                                attenuation: T) -> usize
+{
+  let sample_rate = design::cast(sample_rate);
+  let pass = design::warp(design::cast(passband_frequency), sample_rate);
+  let stop = design::warp(design::cast(stopband_frequency), sample_rate);
+  let selectivity = if pass < stop { pass / stop } else { stop / pass };
+  minimum_order(selectivity, design::cast(ripple), design::cast(attenuation))
+}
+
+/// Estimates the minimum prototype order of a band-pass or band-stop
+/// elliptic filter, where the band filter has twice this order.
+///
+/// A band-pass filter is assumed when the `stopband` edges enclose the
+/// `passband` edges, otherwise a band-stop filter is assumed. Each band is
+/// given as a `(low, high)` frequency pair.
+pub fn estimate_band_order<T: Float>(sample_rate: T,
+                                     passband: (T, T),
+                                     stopband: (T, T),
+                                     ripple: T,
+                                     attenuation: T) -> usize
+{
+  let sample_rate = design::cast(sample_rate);
+  let warp = |frequency: T| design::warp(design::cast(frequency), sample_rate);
+  let (pass_low, pass_high) = (warp(passband.0), warp(passband.1));
+  let (stop_low, stop_high) = (warp(stopband.0), warp(stopband.1));
+
+  // Maps the stopband edges to the low-pass prototype, where the passband
+  // edges are at 1 rad/s
+  let center = pass_low * pass_high;
+  let bandwidth = pass_high - pass_low;
+  let prototype = |frequency: f64| {
+    if stop_low < pass_low {
+      ((frequency * frequency - center) / (frequency * bandwidth)).abs()
+    }
+    else {
+      (frequency * bandwidth / (center - frequency * frequency)).abs()
+    }
+  };
+  let stop = prototype(stop_low).min(prototype(stop_high));
+
+  minimum_order(1f64 / stop, design::cast(ripple), design::cast(attenuation))
+}
+
+/// Designs a low-pass elliptic filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::elliptic;
+///
+/// // 0.5dB of passband ripple, and 60dB of stopband attenuation
+/// let mut filter = elliptic::lowpass(6, 44_100f32, 1_000f32, 0.5f32, 60f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T,
+                         ripple: T,
+                         attenuation: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, ripple, attenuation, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass elliptic filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          ripple: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, ripple, attenuation, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass elliptic filter, passing frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          ripple: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, ripple, attenuation,
+         Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop elliptic filter, where the passbands end at
+/// `low_frequency` and `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T,
+                          ripple: T,
+                          attenuation: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, ripple, attenuation,
+         Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f64::consts::PI;
+  use filter::design::magnitude;
+  use ::traits::Processor;
+
+  /// Returns the stopband edge, in hertz, of a low-pass design.
+  fn stopband_edge(order: usize,
+                   sample_rate: f64,
+                   cutoff: f64,
+                   ripple: f64,
+                   attenuation: f64) -> f64
+  {
+    let epsilon_pass = (10f64.powf(ripple / 10f64) - 1f64).sqrt();
+    let epsilon_stop = (10f64.powf(attenuation / 10f64) - 1f64).sqrt();
+    let k = jacobi::degree(order, epsilon_pass / epsilon_stop);
+    let stop = design::warp(cutoff, sample_rate) / k;
+    (stop / 2f64).atan() * sample_rate / PI
+  }
+
+  #[test]
+  fn order() {
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64, 1f64, 40f64).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64, 1f64, 40f64).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64, 1f64, 40f64).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64, 1f64, 40f64).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let sample_rate = 48_000f64;
+    let ripple = 10f64.powf(-0.5f64 / 20f64);
+    let attenuation = 10f64.powf(-60f64 / 20f64);
+    for order in 1..11 {
+      let filter = lowpass(order, sample_rate, 2_000f64, 0.5f64, 60f64);
+      let dc = if order % 2 == 1 { 1f64 } else { ripple };
+      assert!((magnitude(&filter, 0f64, sample_rate) - dc).abs() < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - ripple).abs() < 1e-9);
+
+      // The passband stays within the ripple
+      for i in 0..200 {
+        let frequency = 2_000f64 * i as f64 / 200f64;
+        let actual = magnitude(&filter, frequency, sample_rate);
+        assert!(actual < 1f64 + 1e-9 && actual > ripple - 1e-9);
+      }
+
+      // The stopband stays below the attenuation
+      let edge = stopband_edge(order, sample_rate, 2_000f64, 0.5f64, 60f64);
+      assert!((magnitude(&filter, edge, sample_rate) - attenuation).abs() < 1e-6);
+      for i in 0..200 {
+        let frequency = edge + (24_000f64 - edge) * i as f64 / 200f64;
+        assert!(magnitude(&filter, frequency, sample_rate) < attenuation + 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn steeper_than_chebyshev() {
+    let sample_rate = 48_000f64;
+    for order in 2..9 {
+      let elliptic = lowpass(order, sample_rate, 2_000f64, 1f64, 80f64);
+      let chebyshev = ::filter::chebyshev1::lowpass(order, sample_rate, 2_000f64, 1f64);
+      assert!(magnitude(&elliptic, 2_500f64, sample_rate)
+            < magnitude(&chebyshev, 2_500f64, sample_rate));
+    }
+  }
+
+  #[test]
+  fn band_response() {
+    let sample_rate = 48_000f64;
+    let ripple = 10f64.powf(-1f64 / 20f64);
+    for order in 1..9 {
+      let bandpass = bandpass(order, sample_rate, 1_000f64, 4_000f64, 1f64, 50f64);
+      let bandstop = bandstop(order, sample_rate, 1_000f64, 4_000f64, 1f64, 50f64);
+      for edge in [1_000f64, 4_000f64].iter() {
+        assert!((magnitude(&bandpass, *edge, sample_rate) - ripple).abs() < 1e-9);
+        assert!((magnitude(&bandstop, *edge, sample_rate) - ripple).abs() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn estimated_order() {
+    let sample_rate = 48_000f64;
+    let specs = vec![
+      (2_000f64, 2_400f64, 0.5f64, 60f64),
+      (10_000f64, 12_000f64, 0.1f64, 80f64),
+      (20_000f64, 22_050f64, 0.01f64, 100f64),
+      (4_000f64, 3_000f64, 1f64, 40f64),
+      (1_000f64, 500f64, 0.1f64, 90f64)
+    ];
+
+    for &(pass, stop, ripple, attenuation) in specs.iter() {
+      let order = estimate_order(sample_rate, pass, stop, ripple, attenuation);
+      let meets = |order: usize| {
+        let filter =
+          if pass < stop {
+            lowpass(order, sample_rate, pass, ripple, attenuation)
+          }
+          else {
+            highpass(order, sample_rate, pass, ripple, attenuation)
+          };
+        magnitude(&filter, stop, sample_rate)
+          <= 10f64.powf(-attenuation / 20f64) + 1e-9
+      };
+      assert!(meets(order));
+      assert!(!meets(order - 1));
+    }
+  }
+
+  #[test]
+  fn estimated_band_order() {
+    let sample_rate = 48_000f64;
+    let attenuation = 10f64.powf(-60f64 / 20f64) + 1e-9;
+
+    let order = estimate_band_order(sample_rate,
+                                    (1_000f64, 2_000f64),
+                                    (800f64, 2_400f64),
+                                    0.5f64,
+                                    60f64);
+    let meets = |order: usize| {
+      let filter = bandpass(order, sample_rate, 1_000f64, 2_000f64, 0.5f64, 60f64);
+      magnitude(&filter, 800f64, sample_rate) <= attenuation
+        && magnitude(&filter, 2_400f64, sample_rate) <= attenuation
+    };
+    assert!(meets(order));
+    assert!(!meets(order - 1));
+
+    let order = estimate_band_order(sample_rate,
+                                    (800f64, 2_400f64),
+                                    (1_000f64, 2_000f64),
+                                    0.5f64,
+                                    60f64);
+    let meets = |order: usize| {
+      let filter = bandstop(order, sample_rate, 800f64, 2_400f64, 0.5f64, 60f64);
+      magnitude(&filter, 1_000f64, sample_rate) <= attenuation
+        && magnitude(&filter, 2_000f64, sample_rate) <= attenuation
+    };
+    assert!(meets(order));
+    assert!(!meets(order - 1));
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(8, 44_100f32, 2_000f32, 0.1f32, 80f32);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 10f32.powf(-0.1f32 / 20f32)).abs() < 1e-3f32);
+  }
+}
@@ -0,0 +1,179 @@
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use filter::response;
+use traits::{FloatConst, FrequencyResponse, Processor};
+
+/* Notes on the circular history
+  - The history is twice the length of the kernel, and every sample is
+    written twice, `taps` samples apart
+  - The write index moves backwards, so the `taps` samples starting at it are
+    always contiguous and ordered from newest to oldest, and the output is a
+    dot product with the coefficients, without wrapping any index
+*/
+
+/// A finite impulse response (FIR) filter with an arbitrary number of taps.
+///
+/// A `Fir` filter uses the following equation:
+///
+/// `y[n] = b0*x[n] + b1*x[n-1] + ... + bN*x[n-N]`
+///
+/// Filter kernels can be designed with `filter::windowed_sinc`.
+pub struct Fir<T> {
+  coefficients: Vec<T>,
+  history: Vec<T>,
+  index: usize,
+  output: T
+}
+
+impl<T> Fir<T> where T: Float {
+  /// Creates a new `Fir` filter with the given `coefficients`, or taps.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::traits::Processor;
+  /// use rasp::filter::Fir;
+  ///
+  /// // A two-point moving average
+  /// let mut filter = Fir::new(vec![0.5f32; 2]);
+  /// assert_eq!(filter.process(3f32), 1.5f32);
+  /// assert_eq!(filter.process(3f32), 3f32);
+  /// ```
+  pub fn new(coefficients: Vec<T>) -> Self {
+    debug_assert!(!coefficients.is_empty());
+    let length = coefficients.len();
+    Fir {
+      coefficients: coefficients,
+      history: vec![T::zero(); 2 * length],
+      index: 0,
+      output: T::zero()
+    }
+  }
+
+  /// Returns the coefficients of the filter.
+  pub fn coefficients(&self) -> &[T] {
+    &self.coefficients
+  }
+
+  /// Sets the coefficients of the filter.
+  ///
+  /// If the number of coefficients is the same, the filter is not cleared.
+  /// Otherwise, the history is resized and cleared.
+  pub fn set_coefficients(&mut self, coefficients: &[T]) {
+    debug_assert!(!coefficients.is_empty());
+    if coefficients.len() != self.coefficients.len() {
+      self.history = vec![T::zero(); 2 * coefficients.len()];
+      self.index = 0;
+      self.output = T::zero();
+    }
+    self.coefficients.clear();
+    self.coefficients.extend_from_slice(coefficients);
+  }
+
+  /// Returns the number of taps of the filter.
+  pub fn taps(&self) -> usize {
+    self.coefficients.len()
+  }
+
+  /// Returns the latency, in samples, of a linear-phase kernel.
+  ///
+  /// This is `(taps - 1) / 2`, which is the group delay of a symmetric or
+  /// antisymmetric kernel at all frequencies.
+  pub fn latency(&self) -> T {
+    let taps: T = num::cast(self.taps() - 1).unwrap();
+    taps / (T::one() + T::one())
+  }
+}
+
+impl<T> Processor<T> for Fir<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    let length = self.coefficients.len();
+    self.index = if self.index == 0 { length - 1 } else { self.index - 1 };
+    self.history[self.index] = sample;
+    self.history[self.index + length] = sample;
+
+    let history = &self.history[self.index..self.index + length];
+    self.output = self.coefficients
+      .iter()
+      .zip(history.iter())
+      .fold(T::zero(), |output, (coefficient, sample)| {
+        output + *coefficient * *sample
+      });
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for sample in self.history.iter_mut() {
+      *sample = T::zero();
+    }
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+impl<T> FrequencyResponse<T> for Fir<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&self.coefficients, &[T::one()], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&self.coefficients, &[T::one()], frequency, sample_rate)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::EPSILON;
+  use ::traits::Processor;
+
+  #[test]
+  fn process() {
+    let input = vec![0.55f32, -0.55f32, 0.55f32, -0.55f32, 0.25f32, 0.1f32];
+    let coefficients = vec![0.5f32, 0.25f32, -0.125f32, 0.1f32];
+    let mut filter = Fir::new(coefficients.clone());
+
+    for n in 0..input.len() {
+      let expected = (0..coefficients.len())
+        .filter(|k| *k <= n)
+        .fold(0f32, |sum, k| sum + coefficients[k] * input[n - k]);
+      let actual = filter.process(input[n]);
+      println!("{:.12} - {:.12} = {:.12}", expected, actual, expected - actual);
+      assert!((expected - actual).abs() <= EPSILON);
+      assert_eq!(actual, filter.last_out());
+    }
+
+    filter.clear();
+    assert!((filter.process(1f32) - 0.5f32).abs() <= EPSILON);
+  }
+
+  #[test]
+  fn set_coefficients() {
+    let mut filter = Fir::new(vec![1f32, 0f32]);
+    filter.process(1f32);
+
+    // The history is kept for kernels of the same length
+    filter.set_coefficients(&[0f32, 1f32]);
+    assert_eq!(filter.process(0f32), 1f32);
+
+    filter.set_coefficients(&[0f32, 0f32, 1f32]);
+    assert_eq!(filter.taps(), 3);
+    assert_eq!(filter.process(1f32), 0f32);
+    assert_eq!(filter.process(0f32), 0f32);
+    assert_eq!(filter.process(0f32), 1f32);
+  }
+
+  #[test]
+  fn latency() {
+    assert_eq!(Fir::new(vec![1f32; 5]).latency(), 2f32);
+    assert_eq!(Fir::new(vec![1f64; 4]).latency(), 1.5f64);
+
+    let filter = Fir::new(vec![0.25f64, 0.5f64, 0.25f64]);
+    assert!((filter.group_delay(1_000f64, 48_000f64) - 1f64).abs() < 1e-12);
+  }
+}
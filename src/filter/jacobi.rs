@@ -0,0 +1,159 @@
+//! Jacobi elliptic functions and complete elliptic integrals.
+
+use num::Complex;
+
+use std::f64::consts::PI;
+
+/* Notes on the elliptic functions
+  - These are based on Sophocles J. Orfanidis's "Lecture Notes on Elliptic
+    Filter Design", which computes the functions with Landen's
+    transformation instead of series expansions
+  - All arguments `u` are normalized by the quarter period `K(k)`, so
+    `cde(u, k) = cd(u*K, k)` and `sne(u, k) = sn(u*K, k)`
+  - The modulus `k` is used throughout, not the parameter `m = k^2`
+*/
+
+/// The modulus where the descending Landen sequence is considered zero.
+const TOLERANCE: f64 = 1e-15;
+
+/// The maximum length of a descending Landen sequence.
+const MAX_ITERATIONS: usize = 32;
+
+/// Returns the descending Landen sequence of the modulus `k`, not including
+/// `k` itself.
+fn landen(k: f64) -> Vec<f64> {
+  let mut moduli = Vec::new();
+  let mut k = k;
+  while k > TOLERANCE && moduli.len() < MAX_ITERATIONS {
+    let complement = (1f64 - k * k).sqrt();
+    k = (k / (1f64 + complement)).powi(2);
+    moduli.push(k);
+  }
+  moduli
+}
+
+/// Returns the complete elliptic integral of the first kind, `K(k)`.
+pub fn ellipk(k: f64) -> f64 {
+  if k >= 1f64 {
+    return f64::INFINITY;
+  }
+  landen(k).iter().fold(PI / 2f64, |integral, modulus| {
+    integral * (1f64 + modulus)
+  })
+}
+
+/// Returns the complementary complete elliptic integral, `K'(k) = K(k')`.
+pub fn ellipk_prime(k: f64) -> f64 {
+  ellipk((1f64 - k * k).sqrt())
+}
+
+/// Ascends the Landen sequence from the modulus `0` function `w`.
+fn ascend(w: Complex<f64>, k: f64) -> Complex<f64> {
+  landen(k).iter().rev().fold(w, |w, modulus| {
+    w * (1f64 + modulus) / (w * w * *modulus + 1f64)
+  })
+}
+
+/// Returns the Jacobi elliptic function `cd(u*K, k)`.
+pub fn cde(u: Complex<f64>, k: f64) -> Complex<f64> {
+  ascend((u * (PI / 2f64)).cos(), k)
+}
+
+/// Returns the Jacobi elliptic function `sn(u*K, k)`.
+pub fn sne(u: Complex<f64>, k: f64) -> Complex<f64> {
+  ascend((u * (PI / 2f64)).sin(), k)
+}
+
+/// Symmetric remainder of `x` divided by `y`, in the range `[-y/2, y/2]`.
+fn srem(x: f64, y: f64) -> f64 {
+  let remainder = x % y;
+  if remainder.abs() > y / 2f64 {
+    remainder - y * remainder.signum()
+  }
+  else {
+    remainder
+  }
+}
+
+/// Returns the inverse of `cde`, such that `cde(acde(w, k), k) = w`.
+pub fn acde(w: Complex<f64>, k: f64) -> Complex<f64> {
+  let moduli = landen(k);
+  let mut w = w;
+  let mut previous = k;
+  for modulus in moduli.iter() {
+    let one = Complex::new(1f64, 0f64);
+    w = w / (one + (one - w * w * previous * previous).sqrt())
+          * (2f64 / (1f64 + modulus));
+    previous = *modulus;
+  }
+
+  let u = w.acos() * (2f64 / PI);
+  let ratio = ellipk_prime(k) / ellipk(k);
+  Complex::new(srem(u.re, 4f64), srem(u.im, 2f64 * ratio))
+}
+
+/// Returns the inverse of `sne`, such that `sne(asne(w, k), k) = w`.
+pub fn asne(w: Complex<f64>, k: f64) -> Complex<f64> {
+  -acde(w, k) + 1f64
+}
+
+/// Solves the degree equation for an elliptic filter, returning the
+/// selectivity modulus `k` for the given `order` and discrimination modulus
+/// `k1`.
+pub fn degree(order: usize, k1: f64) -> f64 {
+  let k1_prime = (1f64 - k1 * k1).sqrt();
+  let product = (1..order / 2 + 1).fold(1f64, |product, i| {
+    let u = (2f64 * i as f64 - 1f64) / order as f64;
+    product * sne(Complex::new(u, 0f64), k1_prime).re
+  });
+  let k_prime = k1_prime.powi(order as i32) * product.powi(4);
+  (1f64 - k_prime * k_prime).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use num::Complex;
+
+  #[test]
+  fn complete_integral() {
+    // Values are from the arithmetic-geometric mean, K(k) = pi / (2 * agm(1, k'))
+    assert!((ellipk(0f64) - PI / 2f64).abs() < 1e-14);
+    assert!((ellipk(0.5f64) - 1.685750354812596f64).abs() < 1e-14);
+    assert!((ellipk(0.9f64) - 2.280549138422770f64).abs() < 1e-14);
+    assert!((ellipk(0.99f64) - 3.356600523361192f64).abs() < 1e-13);
+  }
+
+  #[test]
+  fn elliptic_functions() {
+    // At u = 0, sn = 0 and cd = 1; at u = K, sn = 1 and cd = 0
+    for k in [0.1f64, 0.5f64, 0.9f64, 0.999f64].iter() {
+      let zero = Complex::new(0f64, 0f64);
+      let one = Complex::new(1f64, 0f64);
+      assert!((sne(zero, *k) - zero).norm() < 1e-12);
+      assert!((cde(zero, *k) - one).norm() < 1e-12);
+      assert!((sne(one, *k) - one).norm() < 1e-12);
+      assert!(cde(one, *k).norm() < 1e-12);
+    }
+
+    // sn^2 + cn^2 = 1 and dn^2 + k^2*sn^2 = 1, where cd = cn / dn
+    let k = 0.8f64;
+    let u = 0.3f64;
+    let sn = sne(Complex::new(u, 0f64), k).re;
+    let cd = cde(Complex::new(u, 0f64), k).re;
+    let cn = (1f64 - sn * sn).sqrt();
+    let dn = (1f64 - k * k * sn * sn).sqrt();
+    assert!((cd - cn / dn).abs() < 1e-12);
+  }
+
+  #[test]
+  fn inverse_functions() {
+    for k in [0.1f64, 0.5f64, 0.9f64].iter() {
+      for u in [0.1f64, 0.4f64, 0.7f64].iter() {
+        let u = Complex::new(*u, 0.2f64);
+        assert!((acde(cde(u, *k), *k) - u).norm() < 1e-9);
+        assert!((asne(sne(u, *k), *k) - u).norm() < 1e-9);
+      }
+    }
+  }
+}
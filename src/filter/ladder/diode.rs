@@ -0,0 +1,192 @@
+use num;
+use num::traits::Float;
+
+use traits::{FloatConst, Processor};
+
+/* Notes on the diode ladder
+  - Unlike the buffered stages of the Moog ladder, the capacitors of a diode
+    ladder are coupled, so every stage is loaded by the next one
+  - Normalized to the cutoff frequency, the linear model is:
+    - `y1' = (u - y1) - (y1 - y2)`
+    - `y2' = ((y1 - y2) - (y2 - y3)) / 2`
+    - `y3' = ((y2 - y3) - (y3 - y4)) / 2`
+    - `y4' = (y3 - y4) / 2`
+    where `u = x - k*y4` is the input with the feedback
+  - Trapezoidal integration gives `(I - g*A) * y = s + g*b*u`, a tridiagonal
+    system, so the outputs are `y = p + q*u` with `p` solved from the states
+    and `q` solved once per change in cutoff frequency
+  - The loop self-oscillates when `k` reaches `K_CRITICAL`, which is found
+    numerically from the phase crossover of the linear model; the bilinear
+    transform preserves it
+  - The input of the ladder is saturated with `tanh`, as the diodes are
+*/
+
+/// The feedback where the linear model self-oscillates.
+const K_CRITICAL: f64 = 22.104_938_271_571;
+
+/// A four-pole diode ladder filter, as in the Roland TB-303.
+///
+/// The coupled stages give a softer, less resonant response than the `Moog`
+/// ladder at the same cutoff frequency.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::ladder::DiodeLadder;
+///
+/// let mut filter = DiodeLadder::new();
+/// filter.set_coefficients(44_100f32, 1_000f32, 0.8f32);
+/// let output = filter.process(0.5f32);
+/// # assert!(output < 0.5f32);
+/// ```
+pub struct DiodeLadder<T> {
+  states: [T; 4],
+  diagonal: [T; 4],
+  lower: [T; 4],
+  upper: [T; 4],
+  q: [T; 4],
+  k: T,
+  output: T
+}
+
+/// Solves a tridiagonal system with the Thomas algorithm.
+///
+/// `lower[0]` and `upper[3]` are not used.
+fn solve<T: Float>(lower: &[T; 4],
+                   diagonal: &[T; 4],
+                   upper: &[T; 4],
+                   rhs: [T; 4]) -> [T; 4]
+{
+  let mut c = [T::zero(); 4];
+  let mut d = [T::zero(); 4];
+  c[0] = upper[0] / diagonal[0];
+  d[0] = rhs[0] / diagonal[0];
+  for i in 1..4 {
+    let denominator = diagonal[i] - lower[i] * c[i - 1];
+    c[i] = upper[i] / denominator;
+    d[i] = (rhs[i] - lower[i] * d[i - 1]) / denominator;
+  }
+
+  let mut x = d;
+  for i in (0..3).rev() {
+    x[i] = d[i] - c[i] * x[i + 1];
+  }
+  x
+}
+
+impl<T> DiodeLadder<T> where T: Float + FloatConst {
+  /// Creates a new `DiodeLadder` filter.
+  ///
+  /// The filter will be initialized with a cutoff frequency of a quarter of
+  /// the sample rate and no resonance.
+  pub fn new() -> Self {
+    let mut filter = DiodeLadder {
+      states: [T::zero(); 4],
+      diagonal: [T::zero(); 4],
+      lower: [T::zero(); 4],
+      upper: [T::zero(); 4],
+      q: [T::zero(); 4],
+      k: T::zero(),
+      output: T::zero()
+    };
+    let quarter: T = num::cast(0.25f64).unwrap();
+    filter.set_coefficients(T::one(), quarter, T::zero());
+    filter
+  }
+
+  /// Sets the filter coefficients, without clearing the filter.
+  ///
+  /// A `resonance` of one is the threshold of self-oscillation. This is cheap
+  /// enough to be called every sample.
+  pub fn set_coefficients(&mut self,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          resonance: T)
+  {
+    debug_assert!(resonance >= T::zero());
+    let one = T::one();
+    let two = T::two();
+    let g = (T::pi() * cutoff_frequency / sample_rate).tan();
+    let half = g / two;
+
+    // I - g*A
+    self.diagonal = [one + two * g, one + g, one + g, one + half];
+    self.lower = [T::zero(), -half, -half, -half];
+    self.upper = [-g, -half, -half, T::zero()];
+    self.q = solve(&self.lower, &self.diagonal, &self.upper,
+                   [g, T::zero(), T::zero(), T::zero()]);
+    self.k = resonance * num::cast(K_CRITICAL).unwrap();
+  }
+}
+
+impl<T> Processor<T> for DiodeLadder<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    let p = solve(&self.lower, &self.diagonal, &self.upper, self.states);
+    let u = (sample - self.k * p[3]) / (T::one() + self.k * self.q[3]);
+    let u = u.tanh();
+
+    let outputs = p.iter().zip(self.q.iter());
+    for (state, (p, q)) in self.states.iter_mut().zip(outputs) {
+      let output = *p + *q * u;
+      *state = output + output - *state;
+    }
+    self.output = p[3] + self.q[3] * u;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.states = [T::zero(); 4];
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::ladder::tests::{gain, modulated, ringing};
+
+  #[test]
+  fn response() {
+    let sample_rate = 48_000f64;
+    for resonance in [0f64, 0.5f64].iter() {
+      let k = K_CRITICAL * resonance;
+      let mut filter = DiodeLadder::new();
+      filter.set_coefficients(sample_rate, 1_000f64, *resonance);
+      let dc = gain(&mut filter, 2f64, sample_rate);
+      println!("{:.6} - {:.6} = {:.6}", 1f64 / (1f64 + k), dc, 1f64 / (1f64 + k) - dc);
+      assert!((dc - 1f64 / (1f64 + k)).abs() < 1e-3);
+    }
+
+    // A low-pass response
+    let mut filter = DiodeLadder::new();
+    filter.set_coefficients(sample_rate, 1_000f64, 0f64);
+    assert!(gain(&mut filter, 10_000f64, sample_rate) < 1e-3);
+  }
+
+  #[test]
+  fn self_oscillation() {
+    let sample_rate = 48_000f64;
+    let mut filter = DiodeLadder::new();
+    filter.set_coefficients(sample_rate, 1_000f64, 0.9f64);
+    assert!(ringing(&mut filter, sample_rate) < 1e-6);
+
+    filter.clear();
+    filter.set_coefficients(sample_rate, 1_000f64, 1.2f64);
+    let peak = ringing(&mut filter, sample_rate);
+    assert!(peak > 0.01f64 && peak < 2f64);
+  }
+
+  #[test]
+  fn modulation() {
+    let mut filter = DiodeLadder::new();
+    let peak = modulated(&mut filter, |filter, cutoff| {
+      filter.set_coefficients(48_000f32, cutoff, 1f32);
+    });
+    assert!(peak.is_finite() && peak < 4f32);
+  }
+}
@@ -0,0 +1,85 @@
+//! Nonlinear virtual-analog ladder and Sallen-Key filters.
+//!
+//! These are zero-delay feedback models of classic synthesizer low-pass
+//! filters, built from one-pole filters discretized with the
+//! topology-preserving transform. The feedback loops are solved without a
+//! unit delay, so the cutoff frequency can change every sample without
+//! detuning or destabilizing the filter.
+//!
+//! All filters are set with a `resonance` where `0` has no resonance and `1`
+//! is the threshold of self-oscillation. Higher values oscillate at the
+//! cutoff frequency, with the amplitude limited by the `tanh` saturation.
+//! Saturation depends on the level of the input, which should be within
+//! `[-1, 1]` for a mostly linear response.
+
+/* Notes on the zero-delay feedback solution
+  - A TPT one-pole low-pass filter with state `s` outputs
+    `y = G*x + (1 - G)*s`, where `G = g / (1 + g)` and `g = tan(pi*fc/fs)`,
+    and then updates its state to `s = 2*y - s`
+  - Outputs are linear in the input of the feedback loop, so the loop is
+    solved for that input with the saturation ignored, and the saturation is
+    then applied to the solved input (Zavalishin's "cheap" method)
+*/
+
+mod diode;
+mod moog;
+mod sallen_key;
+
+pub use self::diode::DiodeLadder    as DiodeLadder;
+pub use self::moog::Moog            as Moog;
+pub use self::sallen_key::SallenKey as SallenKey;
+
+#[cfg(test)]
+pub mod tests {
+  use ::traits::Processor;
+
+  /// Returns the gain of a small sine wave at `frequency` through `filter`.
+  pub fn gain<P>(filter: &mut P, frequency: f64, sample_rate: f64) -> f64
+    where P: Processor<f64>
+  {
+    let length = sample_rate as usize;
+    let (mut input, mut output) = (0f64, 0f64);
+    for i in 0..length {
+      let phase = 2f64 * ::std::f64::consts::PI * frequency * i as f64 / sample_rate;
+      let sample = 1e-4f64 * phase.sin();
+      let processed = filter.process(sample);
+      if i >= length / 2 {
+        input += sample * sample;
+        output += processed * processed;
+      }
+    }
+    (output / input).sqrt()
+  }
+
+  /// Returns the peak output in the last tenth of a second after an impulse.
+  pub fn ringing<P>(filter: &mut P, sample_rate: f64) -> f64
+    where P: Processor<f64>
+  {
+    let length = sample_rate as usize;
+    let mut peak = 0f64;
+    for i in 0..length {
+      let output = filter.process(if i == 0 { 0.1f64 } else { 0f64 });
+      if i >= length - length / 10 {
+        peak = peak.max(output.abs());
+      }
+    }
+    peak
+  }
+
+  /// Returns the peak output of a square wave while the cutoff frequency is
+  /// set to a random value every sample.
+  pub fn modulated<P, F>(filter: &mut P, set_cutoff: F) -> f32
+    where P: Processor<f32>, F: Fn(&mut P, f32)
+  {
+    let mut seed = 12_345u32;
+    let mut peak = 0f32;
+    for i in 0..48_000 {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+      let random = (seed >> 8) as f32 / (1u32 << 24) as f32;
+      set_cutoff(filter, 20f32 * 1_000f32.powf(random));
+      let sample = if (i / 50) % 2 == 0 { 1f32 } else { -1f32 };
+      peak = peak.max(filter.process(sample).abs());
+    }
+    peak
+  }
+}
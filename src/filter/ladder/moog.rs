@@ -0,0 +1,151 @@
+use num;
+use num::traits::Float;
+
+use traits::{FloatConst, Processor};
+
+/* Notes on the Moog ladder
+  - Four one-pole low-pass stages are in series, and the output is fed back
+    to the input, inverted and scaled by `k = 4 * resonance`
+  - Each stage has a gain of `1 / (1 + j)` at the cutoff frequency, so the
+    loop has a phase of -180 degrees and a gain of `k / 4` there, and it
+    self-oscillates when `k >= 4`
+  - The output of the last stage is `G^4 * u + S`, where `S` is the sum of
+    the states scaled through the following stages, so the input of the
+    first stage is `u = (x - k*S) / (1 + k*G^4)`
+  - The input of every stage is saturated with `tanh`, as the transistor
+    pairs of the ladder are
+  - The resonance reduces the passband gain to `1 / (1 + k)`
+*/
+
+/// A four-pole Moog transistor ladder filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::ladder::Moog;
+///
+/// let mut filter = Moog::new();
+/// filter.set_coefficients(44_100f32, 1_000f32, 0.8f32);
+/// let output = filter.process(0.5f32);
+/// # assert!(output < 0.5f32);
+/// ```
+pub struct Moog<T> {
+  states: [T; 4],
+  gain: T,
+  k: T,
+  output: T
+}
+
+impl<T> Moog<T> where T: Float + FloatConst {
+  /// Creates a new `Moog` filter.
+  ///
+  /// The filter will be initialized with a cutoff frequency of a quarter of
+  /// the sample rate and no resonance.
+  pub fn new() -> Self {
+    let mut filter = Moog {
+      states: [T::zero(); 4],
+      gain: T::zero(),
+      k: T::zero(),
+      output: T::zero()
+    };
+    let quarter: T = num::cast(0.25f64).unwrap();
+    filter.set_coefficients(T::one(), quarter, T::zero());
+    filter
+  }
+
+  /// Sets the filter coefficients, without clearing the filter.
+  ///
+  /// A `resonance` of one is the threshold of self-oscillation. This is cheap
+  /// enough to be called every sample.
+  pub fn set_coefficients(&mut self,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          resonance: T)
+  {
+    debug_assert!(resonance >= T::zero());
+    let four: T = num::cast(4f64).unwrap();
+    let g = (T::pi() * cutoff_frequency / sample_rate).tan();
+    self.gain = g / (T::one() + g);
+    self.k = four * resonance;
+  }
+}
+
+impl<T> Processor<T> for Moog<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    let g = self.gain;
+    let one_minus_g = T::one() - g;
+    let sum = self.states
+      .iter()
+      .fold(T::zero(), |sum, state| sum * g + one_minus_g * *state);
+    let g4 = g * g * g * g;
+    let u = (sample - self.k * sum) / (T::one() + self.k * g4);
+
+    let mut input = u;
+    for state in self.states.iter_mut() {
+      let v = (input.tanh() - *state) * g;
+      let output = v + *state;
+      *state = output + v;
+      input = output;
+    }
+    self.output = input;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.states = [T::zero(); 4];
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::ladder::tests::{gain, modulated, ringing};
+
+  #[test]
+  fn response() {
+    let sample_rate = 48_000f64;
+    for resonance in [0f64, 0.5f64].iter() {
+      let k = 4f64 * resonance;
+      let mut filter = Moog::new();
+      filter.set_coefficients(sample_rate, 1_000f64, *resonance);
+      let dc = gain(&mut filter, 10f64, sample_rate);
+      println!("{:.6} - {:.6} = {:.6}", 1f64 / (1f64 + k), dc, 1f64 / (1f64 + k) - dc);
+      assert!((dc - 1f64 / (1f64 + k)).abs() < 1e-3);
+
+      // |1 / (1 + j)|^4 = 1/4, with the feedback of -k/4
+      filter.clear();
+      let cutoff = gain(&mut filter, 1_000f64, sample_rate);
+      let expected = 0.25f64 / (1f64 - k / 4f64);
+      println!("{:.6} - {:.6} = {:.6}", expected, cutoff, expected - cutoff);
+      assert!((cutoff - expected).abs() < 1e-2 * expected);
+    }
+  }
+
+  #[test]
+  fn self_oscillation() {
+    let sample_rate = 48_000f64;
+    let mut filter = Moog::new();
+    filter.set_coefficients(sample_rate, 1_000f64, 0.9f64);
+    assert!(ringing(&mut filter, sample_rate) < 1e-6);
+
+    filter.clear();
+    filter.set_coefficients(sample_rate, 1_000f64, 1.2f64);
+    let peak = ringing(&mut filter, sample_rate);
+    assert!(peak > 0.1f64 && peak < 2f64);
+  }
+
+  #[test]
+  fn modulation() {
+    let mut filter = Moog::new();
+    let peak = modulated(&mut filter, |filter, cutoff| {
+      filter.set_coefficients(48_000f32, cutoff, 1f32);
+    });
+    assert!(peak.is_finite() && peak < 4f32);
+  }
+}
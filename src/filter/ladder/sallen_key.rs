@@ -0,0 +1,163 @@
+use num;
+use num::traits::Float;
+
+use traits::{FloatConst, Processor};
+
+/* Notes on the Sallen-Key filter
+  - This is the Korg35 low-pass filter of the Korg MS-20, as modeled by
+    Will Pirkle from Zavalishin's analysis
+  - The input is low-passed by a first one-pole filter, then goes through
+    a second low-pass whose output, scaled by `K = 2 * resonance`, is
+    high-passed and fed back positively to the input of the second stage
+  - The low-pass and high-pass each have a gain of `1 / sqrt(2)` at the
+    cutoff frequency, with phases of -45 and 45 degrees, so the loop
+    self-oscillates when `K >= 2`
+  - Solving the loop gives the input of the second stage,
+    `u = (y1 + S) / (1 - K*G*(1 - G))`, where `S` are the contributions of
+    the second low-pass and the high-pass states
+  - The output of the second low-pass is `y / K`, which has unity gain in
+    the passband at every resonance
+*/
+
+/// A two-pole Sallen-Key filter, as in the Korg MS-20.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::ladder::SallenKey;
+///
+/// let mut filter = SallenKey::new();
+/// filter.set_coefficients(44_100f32, 1_000f32, 0.8f32);
+/// let output = filter.process(0.5f32);
+/// # assert!(output < 0.5f32);
+/// ```
+pub struct SallenKey<T> {
+  lowpass1: T,
+  lowpass2: T,
+  highpass: T,
+  gain: T,
+  k: T,
+  output: T
+}
+
+impl<T> SallenKey<T> where T: Float + FloatConst {
+  /// Creates a new `SallenKey` filter.
+  ///
+  /// The filter will be initialized with a cutoff frequency of a quarter of
+  /// the sample rate and no resonance.
+  pub fn new() -> Self {
+    let mut filter = SallenKey {
+      lowpass1: T::zero(),
+      lowpass2: T::zero(),
+      highpass: T::zero(),
+      gain: T::zero(),
+      k: T::zero(),
+      output: T::zero()
+    };
+    let quarter: T = num::cast(0.25f64).unwrap();
+    filter.set_coefficients(T::one(), quarter, T::zero());
+    filter
+  }
+
+  /// Sets the filter coefficients, without clearing the filter.
+  ///
+  /// A `resonance` of one is the threshold of self-oscillation, and it must
+  /// be less than two. This is cheap enough to be called every sample.
+  pub fn set_coefficients(&mut self,
+                          sample_rate: T,
+                          cutoff_frequency: T,
+                          resonance: T)
+  {
+    debug_assert!(resonance >= T::zero() && resonance < T::two());
+    let g = (T::pi() * cutoff_frequency / sample_rate).tan();
+    self.gain = g / (T::one() + g);
+    self.k = T::two() * resonance;
+  }
+}
+
+impl<T> Processor<T> for SallenKey<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    let g = self.gain;
+    let one_minus_g = T::one() - g;
+
+    let v1 = (sample - self.lowpass1) * g;
+    let y1 = v1 + self.lowpass1;
+    self.lowpass1 = y1 + v1;
+
+    let feedback = self.k * one_minus_g * one_minus_g * self.lowpass2
+                 - one_minus_g * self.highpass;
+    let u = (y1 + feedback) / (T::one() - self.k * g * one_minus_g);
+    let u = u.tanh();
+
+    let v2 = (u - self.lowpass2) * g;
+    let y2 = v2 + self.lowpass2;
+    self.lowpass2 = y2 + v2;
+
+    // The high-pass is the input minus the one-pole low-pass of its state
+    let y = self.k * y2;
+    let v3 = (y - self.highpass) * g;
+    self.highpass = v3 + self.highpass + v3;
+
+    self.output = y2;
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.lowpass1 = T::zero();
+    self.lowpass2 = T::zero();
+    self.highpass = T::zero();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::ladder::tests::{gain, modulated, ringing};
+
+  #[test]
+  fn response() {
+    let sample_rate = 48_000f64;
+    for resonance in [0f64, 0.5f64].iter() {
+      let k = 2f64 * resonance;
+      let mut filter = SallenKey::new();
+      filter.set_coefficients(sample_rate, 1_000f64, *resonance);
+      let dc = gain(&mut filter, 10f64, sample_rate);
+      assert!((dc - 1f64).abs() < 1e-3);
+
+      // |1 / (1 + j)|^2 = 1/2, with the feedback of k/2
+      filter.clear();
+      let cutoff = gain(&mut filter, 1_000f64, sample_rate);
+      let expected = 0.5f64 / (1f64 - k / 2f64);
+      println!("{:.6} - {:.6} = {:.6}", expected, cutoff, expected - cutoff);
+      assert!((cutoff - expected).abs() < 1e-2 * expected);
+    }
+  }
+
+  #[test]
+  fn self_oscillation() {
+    let sample_rate = 48_000f64;
+    let mut filter = SallenKey::new();
+    filter.set_coefficients(sample_rate, 1_000f64, 0.9f64);
+    assert!(ringing(&mut filter, sample_rate) < 1e-6);
+
+    filter.clear();
+    filter.set_coefficients(sample_rate, 1_000f64, 1.2f64);
+    let peak = ringing(&mut filter, sample_rate);
+    assert!(peak > 0.1f64 && peak < 2f64);
+  }
+
+  #[test]
+  fn modulation() {
+    let mut filter = SallenKey::new();
+    let peak = modulated(&mut filter, |filter, cutoff| {
+      filter.set_coefficients(48_000f32, cutoff, 1f32);
+    });
+    assert!(peak.is_finite() && peak < 4f32);
+  }
+}
@@ -0,0 +1,265 @@
+//! Legendre, or Papoulis "optimum L", filter designs.
+//!
+//! A Legendre filter has the steepest rolloff of any filter with a
+//! monotonic magnitude response, between a Butterworth and a Chebyshev type
+//! I filter. The response is -3dB at the cutoff frequency. Filters are
+//! returned as a `Cascade` of `Biquad2` sections.
+//!
+//! Band-pass and band-stop designs have twice the given `order`.
+
+use num::Complex;
+use num::traits::Float;
+
+use filter::Cascade;
+use filter::design::{self, Prototype, Transform};
+
+/* Notes on the optimum L polynomials
+  - The squared magnitude response of the filter is `1 / (1 + L(w^2))`,
+    where `L` is a polynomial with `L(0) = 0` and `L(1) = 1`
+  - `L` is the integral, from -1 to `2w^2 - 1`, of the square of a weighted
+    sum of Legendre polynomials, with an additional factor of `(x + 1)` for
+    even orders
+  - The poles are the left half-plane roots of `1 + L(-s^2)`
+  - All polynomials are stored with their coefficients in ascending order
+*/
+
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+  let mut product = vec![0f64; a.len() + b.len() - 1];
+  for (i, x) in a.iter().enumerate() {
+    for (j, y) in b.iter().enumerate() {
+      product[i + j] += x * y;
+    }
+  }
+  product
+}
+
+fn add(a: &[f64], b: &[f64]) -> Vec<f64> {
+  let mut sum = vec![0f64; a.len().max(b.len())];
+  for (i, x) in a.iter().enumerate() {
+    sum[i] += *x;
+  }
+  for (i, y) in b.iter().enumerate() {
+    sum[i] += *y;
+  }
+  sum
+}
+
+/// Returns the Legendre polynomials up to, and including, `order`.
+fn legendre_polynomials(order: usize) -> Vec<Vec<f64>> {
+  let mut polynomials = vec![vec![1f64], vec![0f64, 1f64]];
+  for n in 1..order {
+    // (n + 1) * P[n+1](x) = (2n + 1) * x * P[n](x) - n * P[n-1](x)
+    let n_float = n as f64;
+    let next = add(
+      &multiply(&[0f64, (2f64 * n_float + 1f64) / (n_float + 1f64)], &polynomials[n]),
+      &multiply(&[-n_float / (n_float + 1f64)], &polynomials[n - 1])
+    );
+    polynomials.push(next);
+  }
+  polynomials.truncate(order + 1);
+  polynomials
+}
+
+/// Returns the optimum L polynomial of the given `order`, in `w^2`.
+fn optimum_l(order: usize) -> Vec<f64> {
+  let (k, even) = if order % 2 == 1 { ((order - 1) / 2, false) }
+                  else { ((order - 2) / 2, true) };
+  let legendre = legendre_polynomials(k);
+
+  let weighted = (0..k + 1).fold(vec![0f64], |sum, i| {
+    let weight =
+      if !even {
+        (2f64 * i as f64 + 1f64) / (2f64.sqrt() * (k as f64 + 1f64))
+      }
+      else if i % 2 == k % 2 {
+        (2f64 * i as f64 + 1f64) / ((k as f64 + 1f64) * (k as f64 + 2f64)).sqrt()
+      }
+      else {
+        0f64
+      };
+    add(&sum, &multiply(&[weight], &legendre[i]))
+  });
+
+  let mut integrand = multiply(&weighted, &weighted);
+  if even {
+    integrand = multiply(&integrand, &[1f64, 1f64]);
+  }
+
+  let mut integral = vec![0f64; integrand.len() + 1];
+  for (i, coefficient) in integrand.iter().enumerate() {
+    integral[i + 1] = coefficient / (i as f64 + 1f64);
+  }
+
+  // Substitute x = 2v - 1, then subtract the value at x = -1
+  let mut l = integral.iter().rev().fold(vec![0f64], |l, coefficient| {
+    add(&multiply(&l, &[-1f64, 2f64]), &[*coefficient])
+  });
+  let offset = l[0];
+  l[0] -= offset;
+  l.truncate(order + 1);
+  l
+}
+
+/// Returns the normalized analog prototype of the given `order`.
+fn prototype(order: usize) -> Prototype {
+  // 1 + L(-s^2)
+  let l = optimum_l(order);
+  let mut denominator = vec![0f64; 2 * order + 1];
+  for (m, coefficient) in l.iter().enumerate() {
+    denominator[2 * m] = if m % 2 == 1 { -coefficient } else { *coefficient };
+  }
+  denominator[0] += 1f64;
+
+  let poles: Vec<Complex<f64>> = design::roots(&denominator)
+    .into_iter()
+    .filter(|root| root.re < 0f64)
+    .collect();
+  let gain = poles
+    .iter()
+    .fold(Complex::new(1f64, 0f64), |gain, pole| gain * -pole)
+    .re;
+
+  Prototype {
+    zeros: Vec::new(),
+    poles: poles,
+    gain: gain
+  }
+}
+
+fn design<T: Float>(order: usize,
+                    sample_rate: T,
+                    transform: Transform) -> Cascade<T>
+{
+  debug_assert!(order > 0);
+  design::digitize(prototype(order), design::cast(sample_rate), transform)
+}
+
+/// Designs a low-pass Legendre filter.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::legendre;
+///
+/// let mut filter = legendre::lowpass(4, 44_100f32, 1_000f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T: Float>(order: usize,
+                         sample_rate: T,
+                         cutoff_frequency: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, Transform::LowPass(cutoff))
+}
+
+/// Designs a high-pass Legendre filter.
+pub fn highpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          cutoff_frequency: T) -> Cascade<T>
+{
+  let cutoff = design::cast(cutoff_frequency);
+  design(order, sample_rate, Transform::HighPass(cutoff))
+}
+
+/// Designs a band-pass Legendre filter, passing frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandpass<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, Transform::BandPass(low, high))
+}
+
+/// Designs a band-stop Legendre filter, rejecting frequencies between
+/// `low_frequency` and `high_frequency`.
+pub fn bandstop<T: Float>(order: usize,
+                          sample_rate: T,
+                          low_frequency: T,
+                          high_frequency: T) -> Cascade<T>
+{
+  let low  = design::cast(low_frequency);
+  let high = design::cast(high_frequency);
+  design(order, sample_rate, Transform::BandStop(low, high))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::butterworth;
+  use filter::design::magnitude;
+  use ::traits::Processor;
+
+  #[test]
+  fn polynomials() {
+    let cases = vec![
+      (1, vec![0f64, 1f64]),
+      (2, vec![0f64, 0f64, 1f64]),
+      (3, vec![0f64, 1f64, -3f64, 3f64]),
+      (4, vec![0f64, 0f64, 3f64, -8f64, 6f64])
+    ];
+
+    for &(order, ref expected) in cases.iter() {
+      let actual = optimum_l(order);
+      assert_eq!(actual.len(), expected.len());
+      for (actual, expected) in actual.iter().zip(expected.iter()) {
+        println!("{:.12} - {:.12} = {:.12}", expected, actual, expected - actual);
+        assert!((expected - actual).abs() < 1e-12);
+      }
+    }
+  }
+
+  #[test]
+  fn order() {
+    for order in 1..9 {
+      assert_eq!(lowpass(order, 44_100f64, 1_000f64).order(), order);
+      assert_eq!(highpass(order, 44_100f64, 1_000f64).order(), order);
+      assert_eq!(bandpass(order, 44_100f64, 500f64, 2_000f64).order(), 2 * order);
+      assert_eq!(bandstop(order, 44_100f64, 500f64, 2_000f64).order(), 2 * order);
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let sample_rate = 48_000f64;
+    for order in 1..11 {
+      let filter = lowpass(order, sample_rate, 2_000f64);
+      assert!((magnitude(&filter, 0f64, sample_rate) - 1f64).abs() < 1e-9);
+      assert!((magnitude(&filter, 2_000f64, sample_rate) - 0.5f64.sqrt()).abs() < 1e-9);
+
+      // The response is monotonic
+      let mut previous = 1f64 + 1e-12;
+      for i in 0..400 {
+        let frequency = 24_000f64 * i as f64 / 400f64;
+        let actual = magnitude(&filter, frequency, sample_rate);
+        assert!(actual <= previous);
+        previous = actual;
+      }
+    }
+  }
+
+  #[test]
+  fn steeper_than_butterworth() {
+    let sample_rate = 48_000f64;
+    for order in 3..11 {
+      let legendre = lowpass(order, sample_rate, 2_000f64);
+      let butterworth = butterworth::lowpass(order, sample_rate, 2_000f64);
+      assert!(magnitude(&legendre, 3_000f64, sample_rate)
+            < magnitude(&butterworth, 3_000f64, sample_rate));
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(8, 44_100f32, 1_000f32);
+    let mut output = 0f32;
+    for _ in 0..44_100 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 1f32).abs() < 1e-3f32);
+  }
+}
@@ -1,14 +1,37 @@
+pub mod bessel;
+pub mod butterworth;
+pub mod chebyshev1;
+pub mod chebyshev2;
+pub mod elliptic;
+pub mod ladder;
+pub mod legendre;
+pub mod optimal;
 pub mod rbj;
+pub mod windowed_sinc;
 
 mod biquad;
+mod cascade;
+mod convolver;
+mod design;
+mod fir;
+mod jacobi;
 mod one_pole;
 mod one_zero;
+mod response;
+mod svf;
 mod two_pole;
 mod two_zero;
+mod zplane;
 
-pub use self::biquad::Biquad1   as Biquad1;
-pub use self::biquad::Biquad2   as Biquad2;
-pub use self::one_pole::OnePole as OnePole;
-pub use self::one_zero::OneZero as OneZero;
-pub use self::two_pole::TwoPole as TwoPole;
-pub use self::two_zero::TwoZero as TwoZero;
+pub use self::biquad::Biquad1      as Biquad1;
+pub use self::biquad::Biquad2      as Biquad2;
+pub use self::cascade::Cascade     as Cascade;
+pub use self::convolver::Convolver as Convolver;
+pub use self::fir::Fir             as Fir;
+pub use self::one_pole::OnePole    as OnePole;
+pub use self::one_zero::OneZero    as OneZero;
+pub use self::svf::Svf             as Svf;
+pub use self::svf::SvfMode         as SvfMode;
+pub use self::svf::SvfOutput       as SvfOutput;
+pub use self::two_pole::TwoPole    as TwoPole;
+pub use self::two_zero::TwoZero    as TwoZero;
@@ -1,7 +1,9 @@
 use num;
+use num::Complex;
 use num::traits::Float;
 
-use traits::Processor;
+use filter::response;
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A single channel, one pole digital filter.
 ///
@@ -10,7 +12,7 @@ use traits::Processor;
 /// `y[n] = b0*x[n] - a1*y[n-1]`
 ///
 /// It has one feedback coefficient, `a1`. 
-pub struct OnePole<T: Float> {
+pub struct OnePole<T> {
   y_z1: T,
   pub b0: T,
   pub a1: T
@@ -66,6 +68,16 @@ impl<T> Processor<T> for OnePole<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for OnePole<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0], &[T::one(), self.a1], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0], &[T::one(), self.a1], frequency, sample_rate)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
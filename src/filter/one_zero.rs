@@ -1,7 +1,9 @@
 use num;
+use num::Complex;
 use num::traits::Float;
 
-use traits::Processor;
+use filter::response;
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A single channel, one zero digital filter.
 ///
@@ -68,6 +70,16 @@ impl<T> Processor<T> for OneZero<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for OneZero<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0, self.b1], &[T::one()], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0, self.b1], &[T::one()], frequency, sample_rate)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -0,0 +1,627 @@
+//! Optimal linear-phase FIR filter designs.
+//!
+//! Unlike `filter::windowed_sinc`, these designs specify the response as a
+//! set of `Band`s, each with a constant gain and a weight, and leave the
+//! transition bands between them unconstrained. Both designers return a
+//! symmetric kernel for `filter::Fir`.
+//!
+//! - `remez()` minimizes the maximum weighted error, with the
+//!   Parks-McClellan algorithm, so the error ripples with equal amplitude
+//!   across every band
+//! - `least_squares()` minimizes the integral of the squared weighted error,
+//!   which trades a larger ripple at the band edges for less error overall
+//!
+//! With an even number of `taps`, the response is always zero at the Nyquist
+//! frequency, so a band there must have a gain of zero.
+
+use num;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+/* Notes on the designs
+  - A symmetric kernel of `N` taps has the zero-phase amplitude response
+    - `A(w) = sum(a[k] * cos(k*w))`, for `k` in `0..(N+1)/2`, when `N` is
+      odd (type I)
+    - `A(w) = cos(w/2) * sum(a[k] * cos(k*w))`, for `k` in `0..N/2`, when
+      `N` is even (type II), which is rewritten in the same form as type I
+      by dividing the desired response by, and multiplying the weight by,
+      `cos(w/2)`
+  - The Remez exchange finds the polynomial in `x = cos(w)` that alternates
+    its weighted error at `r + 1` extremal frequencies, with a dense grid of
+    frequencies and barycentric Lagrange interpolation, as in the original
+    Parks-McClellan program
+  - The least-squares design solves the normal equations, where the
+    integrals of products of cosines over each band have closed forms
+*/
+
+/// A band of an optimal FIR design.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Band<T> {
+  /// The start of the band, in hertz
+  pub start: T,
+  /// The end of the band, in hertz
+  pub end: T,
+  /// The desired gain, as a ratio, across the band
+  pub gain: T,
+  /// The relative weight of the error in the band
+  pub weight: T
+}
+
+impl<T> Band<T> where T: Float {
+  /// Creates a new `Band`.
+  pub fn new(start: T, end: T, gain: T, weight: T) -> Self {
+    Band {
+      start: start,
+      end: end,
+      gain: gain,
+      weight: weight
+    }
+  }
+}
+
+/// The number of grid points per extremal frequency.
+const GRID_DENSITY: usize = 16;
+
+/// The maximum number of Remez exchange iterations.
+const MAX_ITERATIONS: usize = 64;
+
+/// A band with frequencies in radians per sample.
+struct Normalized {
+  start: f64,
+  end: f64,
+  gain: f64,
+  weight: f64
+}
+
+fn normalize<T: Float>(sample_rate: T, bands: &[Band<T>]) -> Vec<Normalized> {
+  debug_assert!(!bands.is_empty());
+  let sample_rate: f64 = num::cast(sample_rate).unwrap();
+  let normalized: Vec<Normalized> = bands.iter().map(|band| {
+    let frequency = |f: T| 2f64 * PI * num::cast::<T, f64>(f).unwrap() / sample_rate;
+    Normalized {
+      start: frequency(band.start),
+      end: frequency(band.end),
+      gain: num::cast(band.gain).unwrap(),
+      weight: num::cast(band.weight).unwrap()
+    }
+  }).collect();
+
+  let mut previous = 0f64;
+  for band in normalized.iter() {
+    debug_assert!(band.start >= previous && band.end > band.start);
+    debug_assert!(band.end <= PI + 1e-12 && band.weight > 0f64);
+    previous = band.end;
+  }
+  normalized
+}
+
+/// Returns the kernel from the cosine coefficients `a` of `P(w)`.
+fn kernel<T: Float>(taps: usize, a: &[f64]) -> Vec<T> {
+  let mut h = vec![0f64; taps];
+  if taps % 2 == 1 {
+    let middle = taps / 2;
+    h[middle] = a[0];
+    for (k, coefficient) in a.iter().enumerate().skip(1) {
+      h[middle - k] = coefficient / 2f64;
+      h[middle + k] = coefficient / 2f64;
+    }
+  }
+  else {
+    // cos(w/2) * cos(k*w) = (cos((k + 1/2)*w) + cos((k - 1/2)*w)) / 2
+    let r = a.len();
+    let mut b = vec![0f64; r + 1];
+    for (k, coefficient) in a.iter().enumerate() {
+      b[k + 1] += coefficient / 2f64;
+      if k == 0 {
+        b[1] += coefficient / 2f64;
+      }
+      else {
+        b[k] += coefficient / 2f64;
+      }
+    }
+    let middle = taps / 2;
+    for j in 1..r + 1 {
+      h[middle - j] = b[j] / 2f64;
+      h[middle + j - 1] = b[j] / 2f64;
+    }
+  }
+  h.into_iter().map(|x| num::cast(x).unwrap()).collect()
+}
+
+/// Returns the cosine coefficients of the polynomial `p`, of degree less
+/// than `r`, in `x = cos(w)`.
+fn cosine_coefficients<F: Fn(f64) -> f64>(r: usize, p: F) -> Vec<f64> {
+  // A DCT of the polynomial at the Chebyshev nodes, which is exact
+  let nodes: Vec<f64> = (0..r).map(|m| PI * (m as f64 + 0.5f64) / r as f64).collect();
+  let values: Vec<f64> = nodes.iter().map(|w| p(w.cos())).collect();
+  (0..r).map(|k| {
+    let sum = nodes.iter().zip(values.iter()).fold(0f64, |sum, (w, value)| {
+      sum + value * (k as f64 * w).cos()
+    });
+    if k == 0 { sum / r as f64 } else { 2f64 * sum / r as f64 }
+  }).collect()
+}
+
+/// Returns the barycentric weights of the interpolation points `x`.
+fn barycentric_weights(x: &[f64]) -> Vec<f64> {
+  // The products over- or underflow for long filters, so they are summed as
+  // logarithms, and all weights are scaled equally by the largest one
+  let logarithms: Vec<(f64, f64)> = (0..x.len()).map(|k| {
+    (0..x.len()).filter(|i| *i != k).fold((0f64, 1f64), |(sum, sign), i| {
+      let difference = x[k] - x[i];
+      (sum - difference.abs().ln(), sign * difference.signum())
+    })
+  }).collect();
+  let largest = logarithms.iter().fold(f64::NEG_INFINITY, |largest, w| largest.max(w.0));
+  logarithms.iter().map(|&(sum, sign)| sign * (sum - largest).exp()).collect()
+}
+
+/// Designs an equiripple FIR kernel with the Parks-McClellan algorithm.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::Fir;
+/// use rasp::filter::optimal::{self, Band};
+///
+/// // A low-pass with 20 times less ripple in the stopband
+/// let bands = [
+///   Band::new(0f64, 4_000f64, 1f64, 1f64),
+///   Band::new(6_000f64, 24_000f64, 0f64, 20f64)
+/// ];
+/// let mut filter = Fir::new(optimal::remez(63, 48_000f64, &bands));
+/// let output = filter.process(1f64);
+/// # assert!(output < 1f64);
+/// ```
+pub fn remez<T: Float>(taps: usize, sample_rate: T, bands: &[Band<T>]) -> Vec<T> {
+  debug_assert!(taps > 2);
+  let bands = normalize(sample_rate, bands);
+  let odd = taps % 2 == 1;
+  let r = if odd { taps / 2 + 1 } else { taps / 2 };
+
+  // The dense grid, with points distributed by band width
+  let total = bands.iter().fold(0f64, |total, band| total + band.end - band.start);
+  let spacing = total / (GRID_DENSITY * r) as f64;
+  let mut grid = Vec::new();
+  let mut desired = Vec::new();
+  let mut weight = Vec::new();
+  let mut edges = Vec::new();
+  for band in bands.iter() {
+    let mut end = band.end;
+    if !odd && end > PI - spacing / 2f64 {
+      // The response of a type II kernel is always zero at the Nyquist
+      end = PI - spacing / 2f64;
+    }
+    let points = (((end - band.start) / spacing).ceil() as usize).max(2);
+    edges.push(grid.len());
+    for i in 0..points {
+      let w = band.start + (end - band.start) * i as f64 / (points - 1) as f64;
+      let q = if odd { 1f64 } else { (w / 2f64).cos() };
+      grid.push(w);
+      desired.push(band.gain / q);
+      weight.push(band.weight * q);
+    }
+  }
+  edges.push(grid.len());
+  let x: Vec<f64> = grid.iter().map(|w| w.cos()).collect();
+
+  let mut extremal: Vec<usize> = (0..r + 1)
+    .map(|k| k * (grid.len() - 1) / r)
+    .collect();
+  let mut coefficients = Vec::new();
+
+  for _ in 0..MAX_ITERATIONS {
+    // The deviation and the values at the extremal frequencies
+    let points: Vec<f64> = extremal.iter().map(|i| x[*i]).collect();
+    let weights = barycentric_weights(&points);
+    let (mut numerator, mut denominator) = (0f64, 0f64);
+    for (k, i) in extremal.iter().enumerate() {
+      let sign = if k % 2 == 1 { -1f64 } else { 1f64 };
+      numerator += weights[k] * desired[*i];
+      denominator += sign * weights[k] / weight[*i];
+    }
+    let delta = numerator / denominator;
+    let values: Vec<f64> = extremal.iter().enumerate().map(|(k, i)| {
+      let sign = if k % 2 == 1 { -1f64 } else { 1f64 };
+      desired[*i] - sign * delta / weight[*i]
+    }).collect();
+
+    // The interpolating polynomial through the first `r` values
+    let nodes = &points[..r];
+    let node_weights = barycentric_weights(nodes);
+    let interpolate = |x: f64| {
+      let (mut numerator, mut denominator) = (0f64, 0f64);
+      for k in 0..r {
+        let difference = x - nodes[k];
+        if difference == 0f64 {
+          return values[k];
+        }
+        let term = node_weights[k] / difference;
+        numerator += term * values[k];
+        denominator += term;
+      }
+      numerator / denominator
+    };
+    coefficients = cosine_coefficients(r, interpolate);
+
+    let error: Vec<f64> = (0..grid.len())
+      .map(|i| weight[i] * (desired[i] - interpolate(x[i])))
+      .collect();
+
+    // The local extrema of the error, within each band
+    let mut candidates: Vec<usize> = Vec::new();
+    for band in edges.windows(2) {
+      for i in band[0]..band[1] {
+        let e = error[i];
+        let previous = if i > band[0] { error[i - 1] } else { -e };
+        let next = if i + 1 < band[1] { error[i + 1] } else { -e };
+        let maximum = e > 0f64 && e >= previous && e >= next;
+        let minimum = e < 0f64 && e <= previous && e <= next;
+        if maximum || minimum {
+          candidates.push(i);
+        }
+      }
+    }
+
+    // Keeps the largest of consecutive extrema with the same sign
+    let mut alternating: Vec<usize> = Vec::new();
+    for i in candidates {
+      match alternating.last().cloned() {
+        Some(last) if (error[last] > 0f64) == (error[i] > 0f64) => {
+          if error[i].abs() > error[last].abs() {
+            *alternating.last_mut().unwrap() = i;
+          }
+        },
+        _ => alternating.push(i)
+      }
+    }
+    if alternating.len() < r + 1 {
+      break;
+    }
+
+    // Removes the smallest extrema until there are `r + 1`
+    while alternating.len() > r + 1 {
+      let smallest = (0..alternating.len())
+        .min_by(|a, b| {
+          error[alternating[*a]].abs()
+            .partial_cmp(&error[alternating[*b]].abs())
+            .unwrap()
+        })
+        .unwrap();
+      let last = alternating.len() - 1;
+      if smallest == 0 || smallest == last || alternating.len() == r + 2 {
+        let end = if error[alternating[0]].abs() < error[alternating[last]].abs() { 0 }
+                  else { last };
+        let index = if smallest == 0 || smallest == last { smallest } else { end };
+        alternating.remove(index);
+      }
+      else {
+        // Removing a neighbor too keeps the signs alternating
+        let neighbor =
+          if error[alternating[smallest - 1]].abs() < error[alternating[smallest + 1]].abs() {
+            smallest - 1
+          }
+          else {
+            smallest + 1
+          };
+        alternating.remove(smallest.max(neighbor));
+        alternating.remove(smallest.min(neighbor));
+      }
+    }
+
+    let maximum = error.iter().fold(0f64, |maximum, e| maximum.max(e.abs()));
+    let converged = (maximum - delta.abs()) <= 1e-9 * maximum;
+    let unchanged = alternating == extremal;
+    extremal = alternating;
+    if converged || unchanged {
+      break;
+    }
+  }
+
+  kernel(taps, &coefficients)
+}
+
+/// Returns the integral of `cos(c*w)` from `start` to `end`.
+fn integral(c: f64, start: f64, end: f64) -> f64 {
+  if c == 0f64 {
+    end - start
+  }
+  else {
+    ((c * end).sin() - (c * start).sin()) / c
+  }
+}
+
+/// Solves the linear system `a * x = b` with Gaussian elimination.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+  let n = b.len();
+  for column in 0..n {
+    let pivot = (column..n)
+      .max_by(|i, j| a[*i][column].abs().partial_cmp(&a[*j][column].abs()).unwrap())
+      .unwrap();
+    a.swap(column, pivot);
+    b.swap(column, pivot);
+    let pivot = a[column].clone();
+    for row in column + 1..n {
+      let factor = a[row][column] / pivot[column];
+      for (x, p) in a[row][column..].iter_mut().zip(pivot[column..].iter()) {
+        *x -= factor * p;
+      }
+      b[row] -= factor * b[column];
+    }
+  }
+
+  let mut x = vec![0f64; n];
+  for row in (0..n).rev() {
+    let sum = (row + 1..n).fold(b[row], |sum, k| sum - a[row][k] * x[k]);
+    x[row] = sum / a[row][row];
+  }
+  x
+}
+
+/// Designs a least-squares FIR kernel, as `firls` does.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::filter::Fir;
+/// use rasp::filter::optimal::{self, Band};
+///
+/// let bands = [
+///   Band::new(0f32, 4_000f32, 1f32, 1f32),
+///   Band::new(6_000f32, 24_000f32, 0f32, 1f32)
+/// ];
+/// let filter = Fir::new(optimal::least_squares(63, 48_000f32, &bands));
+/// # assert_eq!(filter.taps(), 63);
+/// ```
+pub fn least_squares<T: Float>(taps: usize, sample_rate: T, bands: &[Band<T>]) -> Vec<T> {
+  debug_assert!(taps > 1);
+  let bands = normalize(sample_rate, bands);
+
+  // The basis functions are cos(c[k]*w)
+  let odd = taps % 2 == 1;
+  let basis: Vec<f64> =
+    if odd { (0..taps / 2 + 1).map(|k| k as f64).collect() }
+    else { (1..taps / 2 + 1).map(|k| k as f64 - 0.5f64).collect() };
+  let n = basis.len();
+
+  let mut gram = vec![vec![0f64; n]; n];
+  let mut projection = vec![0f64; n];
+  for band in bands.iter() {
+    for i in 0..n {
+      for j in 0..n {
+        let product = integral(basis[i] - basis[j], band.start, band.end)
+                    + integral(basis[i] + basis[j], band.start, band.end);
+        gram[i][j] += band.weight * product / 2f64;
+      }
+      projection[i] += band.weight * band.gain * integral(basis[i], band.start, band.end);
+    }
+  }
+
+  // The coefficients of cos(c[k]*w), where h[middle -/+ c[k]] = b[k] / 2
+  let b = solve(gram, projection);
+  let mut h = vec![0f64; taps];
+  let middle = (taps - 1) as f64 / 2f64;
+  for (c, coefficient) in basis.iter().zip(b.iter()) {
+    let (low, high) = ((middle - c) as usize, (middle + c) as usize);
+    if low == high {
+      h[low] = *coefficient;
+    }
+    else {
+      h[low] = coefficient / 2f64;
+      h[high] = coefficient / 2f64;
+    }
+  }
+  h.into_iter().map(|x| num::cast(x).unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::Fir;
+  use ::traits::FrequencyResponse;
+
+  fn lowpass_bands(stop_weight: f64) -> Vec<Band<f64>> {
+    vec![
+      Band::new(0f64, 0.2f64, 1f64, 1f64),
+      Band::new(0.3f64, 0.5f64, 0f64, stop_weight)
+    ]
+  }
+
+  /// Returns the largest errors in the passband and stopband of a low-pass
+  /// filter designed with `lowpass_bands()`.
+  fn ripples(taps: &[f64]) -> (f64, f64) {
+    let filter = Fir::new(taps.to_vec());
+    let (mut pass, mut stop) = (0f64, 0f64);
+    for i in 0..1_001 {
+      let frequency = 0.5f64 * i as f64 / 1_000f64;
+      let magnitude = filter.magnitude(frequency, 1f64);
+      if frequency <= 0.2f64 {
+        pass = pass.max((magnitude - 1f64).abs());
+      }
+      else if frequency >= 0.3f64 {
+        stop = stop.max(magnitude);
+      }
+    }
+    (pass, stop)
+  }
+
+  fn assert_symmetric(taps: &[f64]) {
+    for (a, b) in taps.iter().zip(taps.iter().rev()) {
+      assert!((a - b).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn integrals() {
+    assert!((integral(0f64, 0.5f64, 2f64) - 1.5f64).abs() < 1e-12);
+    assert!((integral(2f64, 0f64, PI / 4f64) - 0.5f64).abs() < 1e-12);
+  }
+
+  #[test]
+  fn polynomial_coefficients() {
+    // 1 + 2x + 4x^2 = 1 + 2cos(w) + 2(1 + cos(2w)) = 3 + 2cos(w) + 2cos(2w)
+    let actual = cosine_coefficients(3, |x| 1f64 + 2f64 * x + 4f64 * x * x);
+    for (expected, actual) in [3f64, 2f64, 2f64].iter().zip(actual.iter()) {
+      assert!((expected - actual).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn all_pass() {
+    // A gain of one everywhere is a delayed impulse
+    let bands = [Band::new(0f64, 0.5f64, 1f64, 1f64)];
+    for taps in [remez(11, 1f64, &bands), least_squares(11, 1f64, &bands)].iter() {
+      for (n, tap) in taps.iter().enumerate() {
+        let expected = if n == 5 { 1f64 } else { 0f64 };
+        assert!((tap - expected).abs() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn remez_equiripple() {
+    for taps in [31usize, 32usize, 51usize].iter() {
+      let kernel = remez(*taps, 1f64, &lowpass_bands(1f64));
+      assert_eq!(kernel.len(), *taps);
+      assert_symmetric(&kernel);
+      let (pass, stop) = ripples(&kernel);
+      println!("{} {:.9} {:.9}", taps, pass, stop);
+      // Peaks between the points of the dense grid are slightly larger
+      assert!((pass - stop).abs() < 1e-2 * pass);
+    }
+
+    // The ripple is inversely proportional to the weight
+    let kernel = remez(41, 1f64, &lowpass_bands(10f64));
+    let (pass, stop) = ripples(&kernel);
+    println!("{:.9} {:.9}", pass, stop);
+    assert!((pass - 10f64 * stop).abs() < 1e-2 * pass);
+  }
+
+  #[test]
+  fn remez_halfband() {
+    // The bands are symmetric about a quarter of the sample rate, so every
+    // other tap is zero
+    let kernel = remez(31, 1f64, &[
+      Band::new(0f64, 0.2f64, 1f64, 1f64),
+      Band::new(0.3f64, 0.5f64, 0f64, 1f64)
+    ]);
+    assert!((kernel[15] - 0.5f64).abs() < 1e-6);
+    for k in 1..8 {
+      assert!(kernel[15 + 2 * k].abs() < 1e-6);
+    }
+  }
+
+  #[test]
+  fn least_squares_halfband() {
+    let kernel = least_squares(31, 1f64, &lowpass_bands(1f64));
+    assert_symmetric(&kernel);
+    assert!((kernel[15] - 0.5f64).abs() < 1e-9);
+    for k in 1..8 {
+      assert!(kernel[15 + 2 * k].abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn least_squares_error() {
+    // The least-squares design has a larger peak error than the equiripple
+    // design, but a smaller squared error
+    let bands = lowpass_bands(1f64);
+    let squares = least_squares(32, 1f64, &bands);
+    let equiripple = remez(32, 1f64, &bands);
+    assert_symmetric(&squares);
+
+    let squared_error = |taps: &[f64]| {
+      let filter = Fir::new(taps.to_vec());
+      (0..1_001).fold(0f64, |sum, i| {
+        let frequency = 0.5f64 * i as f64 / 1_000f64;
+        let magnitude = filter.magnitude(frequency, 1f64);
+        if frequency <= 0.2f64 { sum + (magnitude - 1f64).powi(2) }
+        else if frequency >= 0.3f64 { sum + magnitude.powi(2) }
+        else { sum }
+      })
+    };
+    let (squares_pass, _) = ripples(&squares);
+    let (equiripple_pass, _) = ripples(&equiripple);
+    assert!(squares_pass > equiripple_pass);
+    assert!(squared_error(&squares) < squared_error(&equiripple));
+  }
+
+  /// The bands of the reference designs, with frequencies relative to the
+  /// Nyquist frequency, as octave specifies them.
+  fn reference_bands() -> Vec<Band<f64>> {
+    vec![
+      Band::new(0f64, 0.3f64, 1f64, 1f64),
+      Band::new(0.45f64, 1f64, 0f64, 5f64)
+    ]
+  }
+
+  // Values are from a port of octave's `firls` function, for
+  // `firls(20, [0 0.3 0.45 1], [1 1 0 0], [1 5])`
+  #[test]
+  fn least_squares_reference() {
+    let expected = [
+      -0.00905720765335f64, -0.0115318902722f64, 0.00429335211911f64, 0.0270188341503f64,
+      0.0224208906693f64, -0.0240992960162f64, -0.0680740253756f64, -0.0315821375477f64,
+      0.112782555764f64, 0.288832302267f64, 0.368520309224f64, 0.288832302267f64,
+      0.112782555764f64, -0.0315821375477f64, -0.0680740253756f64, -0.0240992960162f64,
+      0.0224208906693f64, 0.0270188341503f64, 0.00429335211911f64, -0.0115318902722f64,
+      -0.00905720765335f64
+    ];
+    let actual = least_squares(21, 2f64, &reference_bands());
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+      println!("{:.12} - {:.12} = {:.12}", expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-11);
+    }
+  }
+
+  // Values are from a port of octave's `remez` function, for
+  // `remez(20, [0 0.3 0.45 1], [1 1 0 0], [1 5])` and `remez(21, ...)`;
+  // octave stops exchanging once the errors at the extremal frequencies
+  // agree within 1e-4 of each other, which leaves its taps up to 7.3e-5
+  // from the converged design here
+  #[test]
+  fn remez_reference() {
+    let odd = [
+      -0.011882564913f64, -0.00684484524469f64, 0.014456453146f64, 0.0368475016077f64,
+      0.0261895320485f64, -0.0251768974802f64, -0.0677555432068f64, -0.0271153189762f64,
+      0.117203296031f64, 0.288438381436f64, 0.36510941558f64, 0.288438381436f64,
+      0.117203296031f64, -0.0271153189762f64, -0.0677555432068f64, -0.0251768974802f64,
+      0.0261895320485f64, 0.0368475016077f64, 0.014456453146f64, -0.00684484524469f64,
+      -0.011882564913f64
+    ];
+    let even = [
+      -0.013382745083f64, -0.0189866514808f64, -0.00665623832752f64, 0.0210363149859f64,
+      0.0327885364609f64, 0.000548229433985f64, -0.0550583876917f64, -0.0631101136795f64,
+      0.0337267386707f64, 0.206721295223f64, 0.344505140752f64, 0.344505140752f64,
+      0.206721295223f64, 0.0337267386707f64, -0.0631101136795f64, -0.0550583876917f64,
+      0.000548229433985f64, 0.0327885364609f64, 0.0210363149859f64, -0.00665623832752f64,
+      -0.0189866514808f64, -0.013382745083f64
+    ];
+    for expected in [&odd[..], &even[..]].iter() {
+      let actual = remez(expected.len(), 2f64, &reference_bands());
+      assert_eq!(actual.len(), expected.len());
+      for (actual, expected) in actual.iter().zip(expected.iter()) {
+        println!("{:.12} - {:.12} = {:.12}", expected, actual, expected - actual);
+        assert!((expected - actual).abs() < 8e-5);
+      }
+    }
+  }
+
+  #[test]
+  fn bandpass() {
+    let bands = [
+      Band::new(0f64, 4_000f64, 0f64, 1f64),
+      Band::new(6_000f64, 10_000f64, 1f64, 1f64),
+      Band::new(12_000f64, 24_000f64, 0f64, 1f64)
+    ];
+    for kernel in [remez(61, 48_000f64, &bands), least_squares(61, 48_000f64, &bands)].iter() {
+      let filter = Fir::new(kernel.clone());
+      assert!(filter.magnitude_db(0f64, 48_000f64) < -30f64);
+      assert!((filter.magnitude(8_000f64, 48_000f64) - 1f64).abs() < 0.05f64);
+      assert!(filter.magnitude_db(20_000f64, 48_000f64) < -30f64);
+    }
+  }
+}
@@ -1,7 +1,8 @@
+use num::Complex;
 use num::traits::Float;
 
 use filter::Biquad2;
-use traits::{FloatConst, Processor};
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// An all-pass biquad filter.
 pub struct AllPass<T> {
@@ -65,3 +66,13 @@ impl<T> Processor<T> for AllPass<T> where T: Float {
     self.biquad.last_out()
   }
 }
+
+impl<T> FrequencyResponse<T> for AllPass<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    self.biquad.response(frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.biquad.group_delay(frequency, sample_rate)
+  }
+}
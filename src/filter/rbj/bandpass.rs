@@ -1,7 +1,8 @@
+use num::Complex;
 use num::traits::Float;
 
 use filter::Biquad2;
-use traits::{FloatConst, Processor};
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A band-pass biquad filter.
 ///
@@ -69,6 +70,16 @@ impl<T> Processor<T> for BandPass1<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for BandPass1<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    self.biquad.response(frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.biquad.group_delay(frequency, sample_rate)
+  }
+}
+
 /// A band-pass biquad filter.
 ///
 /// This filter has a constant peak gain at 0db.
@@ -133,3 +144,13 @@ impl<T> Processor<T> for BandPass2<T> where T: Float {
     self.biquad.last_out()
   }
 }
+
+impl<T> FrequencyResponse<T> for BandPass2<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    self.biquad.response(frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.biquad.group_delay(frequency, sample_rate)
+  }
+}
@@ -1,7 +1,8 @@
+use num::Complex;
 use num::traits::Float;
 
 use filter::Biquad2;
-use traits::{FloatConst, Processor};
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A band-stop biquad filter.
 ///
@@ -67,3 +68,13 @@ impl<T> Processor<T> for BandStop<T> where T: Float {
     self.biquad.last_out()
   }
 }
+
+impl<T> FrequencyResponse<T> for BandStop<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    self.biquad.response(frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.biquad.group_delay(frequency, sample_rate)
+  }
+}
@@ -0,0 +1,162 @@
+//! Parameter glides for click-free coefficient updates.
+
+use num;
+use num::traits::Float;
+
+/* Notes on gliding parameters
+  - Recalculating the coefficients keeps the filter state, so the output
+    stays continuous while the parameters change
+  - The first parameter is a frequency, in radians per sample, so that the
+    filters can update without `FloatConst`; it is interpolated
+    logarithmically, so a glide sounds even across octaves, and the others,
+    such as q, gain in dB, and shelf slope, are interpolated linearly
+  - Filters with fewer parameters leave the unused ones at zero
+*/
+
+/// A set of filter parameters gliding to a target over a number of samples.
+pub struct Glide<T> {
+  length: usize,
+  remaining: usize,
+  sample_rate: T,
+  current: Option<[T; 3]>,
+  target: [T; 3],
+  step: [T; 3]
+}
+
+impl<T> Glide<T> where T: Float {
+  /// Creates a new `Glide` with a length of zero.
+  pub fn new() -> Self {
+    Glide {
+      length: 0,
+      remaining: 0,
+      sample_rate: T::zero(),
+      current: None,
+      target: [T::zero(); 3],
+      step: [T::zero(); 3]
+    }
+  }
+
+  /// Returns the number of samples a glide takes.
+  pub fn length(&self) -> usize {
+    self.length
+  }
+
+  /// Sets the number of samples following glides take.
+  pub fn set_length(&mut self, length: usize) {
+    self.length = length;
+  }
+
+  /// Returns `true` if the parameters have not reached the target.
+  pub fn is_active(&self) -> bool {
+    self.remaining > 0
+  }
+
+  /// Starts a glide to `target`, from the current parameters.
+  ///
+  /// Returns `false` if the parameters should be applied immediately, which
+  /// is when the length is zero, when there are no previous parameters, or
+  /// when the sample rate changes.
+  pub fn start(&mut self, sample_rate: T, target: [T; 3]) -> bool {
+    self.target = target;
+    let current = match self.current {
+      Some(current) if self.length > 0 && sample_rate == self.sample_rate => {
+        current
+      },
+      _ => {
+        self.sample_rate = sample_rate;
+        self.current = Some(target);
+        self.remaining = 0;
+        return false;
+      }
+    };
+
+    let length: T = num::cast(self.length).unwrap();
+    self.step = [((target[0] / current[0]).ln() / length).exp(),
+                 (target[1] - current[1]) / length,
+                 (target[2] - current[2]) / length];
+    self.remaining = self.length;
+    true
+  }
+
+  /// Advances the glide by one sample, returning the new parameters if they
+  /// changed.
+  pub fn next(&mut self) -> Option<[T; 3]> {
+    if self.remaining == 0 {
+      return None;
+    }
+    self.remaining -= 1;
+
+    let current = self.current.unwrap_or(self.target);
+    let next =
+      if self.remaining == 0 {
+        self.target
+      }
+      else {
+        [current[0] * self.step[0],
+         current[1] + self.step[1],
+         current[2] + self.step[2]]
+      };
+    self.current = Some(next);
+    Some(next)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn immediate() {
+    let mut glide = Glide::new();
+    assert!(!glide.start(48_000f64, [1_000f64, 0.7f64, 0f64]));
+    assert!(glide.next().is_none());
+
+    // The first parameters are always applied immediately
+    let mut glide = Glide::new();
+    glide.set_length(64);
+    assert!(!glide.start(48_000f64, [1_000f64, 0.7f64, 0f64]));
+    assert!(!glide.is_active());
+
+    // As are changes in sample rate
+    assert!(!glide.start(44_100f64, [2_000f64, 0.7f64, 0f64]));
+    assert!(!glide.is_active());
+  }
+
+  #[test]
+  fn interpolation() {
+    let mut glide = Glide::new();
+    glide.set_length(4);
+    glide.start(48_000f64, [100f64, 1f64, -6f64]);
+    assert!(glide.start(48_000f64, [1_600f64, 2f64, 6f64]));
+
+    let expected = [
+      [200f64, 1.25f64, -3f64],
+      [400f64, 1.5f64, 0f64],
+      [800f64, 1.75f64, 3f64],
+      [1_600f64, 2f64, 6f64]
+    ];
+    for parameters in expected.iter() {
+      assert!(glide.is_active());
+      let actual = glide.next().unwrap();
+      for (expected, actual) in parameters.iter().zip(actual.iter()) {
+        assert!((expected - actual).abs() < 1e-9);
+      }
+    }
+    assert!(!glide.is_active());
+    assert!(glide.next().is_none());
+  }
+
+  #[test]
+  fn retarget() {
+    let mut glide = Glide::new();
+    glide.set_length(2);
+    glide.start(48_000f64, [100f64, 1f64, 0f64]);
+    glide.start(48_000f64, [400f64, 1f64, 0f64]);
+    assert!((glide.next().unwrap()[0] - 200f64).abs() < 1e-9);
+
+    // Glides continue from the current parameters
+    glide.start(48_000f64, [50f64, 1f64, 0f64]);
+    assert!((glide.next().unwrap()[0] - 100f64).abs() < 1e-9);
+    assert!((glide.next().unwrap()[0] - 50f64).abs() < 1e-9);
+  }
+}
@@ -1,19 +1,23 @@
 use num;
+use num::Complex;
 use num::traits::Float;
 
 use filter::Biquad2;
-use traits::{FloatConst, Processor};
+use super::glide::Glide;
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A low-shelf biquad filter.
 pub struct LowShelf<T> {
-  biquad: Biquad2<T>
+  biquad: Biquad2<T>,
+  glide: Glide<T>
 }
 
 impl<T> LowShelf<T> where T: Float + FloatConst {
   /// Creates a new `LowShelf` biquad filter.
   pub fn new() -> Self {
     LowShelf {
-      biquad: Biquad2::<T>::new()
+      biquad: Biquad2::<T>::new(),
+      glide: Glide::new()
     }
   }
 
@@ -22,20 +26,54 @@ impl<T> LowShelf<T> where T: Float + FloatConst {
   /// `Biquad2` coefficients are calculated from the `sample_rate`,
   /// `cutoff_frequency`, `db_gain`, and `shelf_slope` factor. These values
   /// are not validated.
+  ///
+  /// With a glide length, the cutoff frequency, gain, and shelf slope glide.
   // TODO: Explain value ranges of parameters
   pub fn set_coefficients(&mut self,
                           sample_rate: T,
                           cutoff_frequency: T,
                           db_gain: T,
                           shelf_slope: T)
+  {
+    let w0 = T::two() * T::pi() * cutoff_frequency / sample_rate;
+    let parameters = [w0, db_gain, shelf_slope];
+    if !self.glide.start(sample_rate, parameters) {
+      self.update(w0, db_gain, shelf_slope);
+      self.clear();
+    }
+  }
+
+  /// Sets the number of samples over which following parameter changes
+  /// glide, which is zero by default.
+  pub fn set_glide_length(&mut self, samples: usize) {
+    self.glide.set_length(samples);
+  }
+
+  /// Returns the number of samples a parameter change glides over.
+  pub fn glide_length(&self) -> usize {
+    self.glide.length()
+  }
+
+  /// Returns `true` if the parameters are gliding to new values.
+  pub fn is_gliding(&self) -> bool {
+    self.glide.is_active()
+  }
+}
+
+impl<T> LowShelf<T> where T: Float {
+  /// Calculates and sets the coefficients from the angular frequency `w0`,
+  /// keeping the filter state.
+  fn update(&mut self,
+            w0: T,
+            db_gain: T,
+            shelf_slope: T)
   {
     let one: T = T::one();
-    let two: T = T::two();
+    let two: T = num::cast(2f64).unwrap();
     let ten: T = num::cast(10f64).unwrap();
     let forty: T = num::cast(40f64).unwrap();
 
     let a  = ten.powf(db_gain / forty);
-    let w0 = two * T::pi() * cutoff_frequency / sample_rate;
     let cos_w0 = w0.cos();
     let alpha = w0.sin() / two
               * ((a + one/a) * (one/shelf_slope - one) + two).sqrt();
@@ -58,12 +96,14 @@ impl<T> LowShelf<T> where T: Float + FloatConst {
     a2 = a2 / a0;
 
     self.biquad.set_coefficients(b0, b1, b2, a1, a2);
-    self.clear();
   }
 }
 
 impl<T> Processor<T> for LowShelf<T> where T: Float {
   fn process(&mut self, sample: T) -> T {
+    if let Some(parameters) = self.glide.next() {
+      self.update(parameters[0], parameters[1], parameters[2]);
+    }
     self.biquad.process(sample)
   }
 
@@ -75,3 +115,13 @@ impl<T> Processor<T> for LowShelf<T> where T: Float {
     self.biquad.last_out()
   }
 }
+
+impl<T> FrequencyResponse<T> for LowShelf<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    self.biquad.response(frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    self.biquad.group_delay(frequency, sample_rate)
+  }
+}
@@ -4,6 +4,14 @@
 //! [Audio EQ Cookbook by Robert Bristow-Johnson](http://www.musicdsp.org/files/Audio-EQ-Cookbook.txt).
 //! Generally, the Q factor should be set to `0.7071f32` to have no additional
 //! effect on the filter.
+//!
+//! `LowPass`, `HighPass`, `Peak`, `LowShelf`, and `HighShelf` can glide to
+//! new parameters. With a length set by `set_glide_length()`, a call to
+//! `set_coefficients()` keeps the filter state, and interpolates the
+//! frequency logarithmically, and the other parameters linearly, over that
+//! many samples; `is_gliding()` is `true` until they arrive. With a length
+//! of zero, which is the default, changes apply immediately and clear the
+//! filter.
 
 /* Q factor vs Bandwdith as filter parameters
   
@@ -19,6 +27,7 @@
 mod allpass;
 mod bandpass;
 mod bandstop;
+mod glide;
 mod highpass;
 mod highshelf;
 mod lowpass;
@@ -34,3 +43,73 @@ pub use self::highshelf::HighShelf as HighShelf;
 pub use self::lowpass::LowPass     as LowPass;
 pub use self::lowshelf::LowShelf   as LowShelf;
 pub use self::peak::Peak           as Peak;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use traits::Processor;
+
+  const RATE: f32 = 44_100f32;
+
+  /// Returns the largest step of the output of `filter`, settled on `input`,
+  /// over the 32 samples after `retune`.
+  fn largest_step<P, F>(filter: &mut P, input: f32, retune: F) -> f32
+    where P: Processor<f32>, F: FnOnce(&mut P)
+  {
+    for _ in 0..2_000 {
+      filter.process(input);
+    }
+    retune(filter);
+    let mut previous = filter.last_out();
+    (0..32).fold(0f32, |step, _| {
+      let output = filter.process(input);
+      let step = step.max((output - previous).abs());
+      previous = output;
+      step
+    })
+  }
+
+  #[test]
+  fn glide() {
+    // The state is kept while the parameters glide, so a settled output does
+    // not jump, where a cleared filter would step by about the input
+    let mut lowpass = LowPass::new();
+    lowpass.set_glide_length(32);
+    lowpass.set_coefficients(RATE, 500f32, 0.71f32);
+    let mut highpass = HighPass::new();
+    highpass.set_glide_length(32);
+    highpass.set_coefficients(RATE, 100f32, 0.71f32);
+    let mut peak = Peak::new();
+    peak.set_glide_length(32);
+    peak.set_coefficients(RATE, 1_000f32, 0f32, 0.71f32);
+    let mut lowshelf = LowShelf::new();
+    lowshelf.set_glide_length(32);
+    lowshelf.set_coefficients(RATE, 500f32, 0f32, 1f32);
+    let mut highshelf = HighShelf::new();
+    highshelf.set_glide_length(32);
+    highshelf.set_coefficients(RATE, 2_000f32, 0f32, 1f32);
+
+    let steps = [
+      (largest_step(&mut lowpass, 1f32, |f| f.set_coefficients(RATE, 8_000f32, 2f32)),
+       lowpass.is_gliding()),
+      (largest_step(&mut highpass, 1f32, |f| f.set_coefficients(RATE, 2_000f32, 2f32)),
+       highpass.is_gliding()),
+      (largest_step(&mut peak, 1f32, |f| f.set_coefficients(RATE, 200f32, 12f32, 2f32)),
+       peak.is_gliding()),
+      (largest_step(&mut lowshelf, 1f32, |f| f.set_coefficients(RATE, 500f32, 6f32, 1f32)),
+       lowshelf.is_gliding()),
+      (largest_step(&mut highshelf, 1f32, |f| f.set_coefficients(RATE, 8_000f32, 12f32, 0.5f32)),
+       highshelf.is_gliding())
+    ];
+    for &(step, gliding) in steps.iter() {
+      println!("{:.6}", step);
+      assert!(step < 0.1f32 && !gliding);
+    }
+
+    // Without a glide, the filter is cleared
+    lowpass.set_glide_length(0);
+    lowpass.set_coefficients(RATE, 500f32, 0.71f32);
+    assert!(!lowpass.is_gliding());
+    assert_eq!(lowpass.last_out(), 0f32);
+  }
+}
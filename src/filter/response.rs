@@ -0,0 +1,101 @@
+//! Frequency response evaluation of direct form transfer functions.
+
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use traits::FloatConst;
+
+/* Notes on the evaluation
+  - Coefficients are in ascending powers of `z^-1`, with `a[0]` being the
+    normalized `1` for the denominator
+  - The group delay of a polynomial `C(z)` is `Re(sum(k*c[k]*z^-k) / C(z))`,
+    so the group delay of `B(z) / A(z)` is the difference of the two
+  - A zero on the unit circle has an undefined group delay, so it is
+    ignored instead of returning a non-finite value
+*/
+
+/// Returns `e^-jw` for `frequency`.
+fn unit_delay<T>(frequency: T, sample_rate: T) -> Complex<T>
+  where T: Float + FloatConst
+{
+  let w = T::two() * T::pi() * frequency / sample_rate;
+  Complex::new(w.cos(), -w.sin())
+}
+
+/// Returns the value of the polynomial `c` and of its derivative scaled by
+/// `k`, at the delay `z1`.
+fn polynomial<T: Float>(c: &[T], z1: Complex<T>) -> (Complex<T>, Complex<T>) {
+  let zero = Complex::new(T::zero(), T::zero());
+  let mut power = Complex::new(T::one(), T::zero());
+  let mut value = zero;
+  let mut ramped = zero;
+  for (k, coefficient) in c.iter().enumerate() {
+    let term = power * *coefficient;
+    value = value + term;
+    ramped = ramped + term * num::cast::<usize, T>(k).unwrap();
+    power = power * z1;
+  }
+  (value, ramped)
+}
+
+/// Returns the complex response of `b / a` at `frequency`.
+pub fn evaluate<T>(b: &[T], a: &[T], frequency: T, sample_rate: T) -> Complex<T>
+  where T: Float + FloatConst
+{
+  let z1 = unit_delay(frequency, sample_rate);
+  polynomial(b, z1).0 / polynomial(a, z1).0
+}
+
+/// Returns the group delay of `b / a` at `frequency`, in samples.
+pub fn group_delay<T>(b: &[T], a: &[T], frequency: T, sample_rate: T) -> T
+  where T: Float + FloatConst
+{
+  let z1 = unit_delay(frequency, sample_rate);
+  let delay = |c: &[T]| {
+    let (value, ramped) = polynomial(c, z1);
+    if value.norm_sqr() > T::epsilon() {
+      (ramped / value).re
+    }
+    else {
+      T::zero()
+    }
+  };
+  delay(b) - delay(a)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pure_delay() {
+    let b = [0f64, 0f64, 0f64, 1f64];
+    let a = [1f64];
+    for frequency in [0f64, 100f64, 12_000f64].iter() {
+      let response = evaluate(&b, &a, *frequency, 48_000f64);
+      assert!((response.norm() - 1f64).abs() < 1e-12);
+      assert!((group_delay(&b, &a, *frequency, 48_000f64) - 3f64).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn first_order() {
+    // H(z) = 1 / (1 - 0.5z^-1), so H(1) = 2 and H(-1) = 2/3
+    let b = [1f64];
+    let a = [1f64, -0.5f64];
+    assert!((evaluate(&b, &a, 0f64, 48_000f64).re - 2f64).abs() < 1e-12);
+    assert!((evaluate(&b, &a, 24_000f64, 48_000f64).re - 2f64 / 3f64).abs() < 1e-12);
+
+    // The group delay at DC is p / (1 - p) samples for a pole at p
+    assert!((group_delay(&b, &a, 0f64, 48_000f64) - 1f64).abs() < 1e-12);
+  }
+
+  #[test]
+  fn zero_on_unit_circle() {
+    let b = [1f64, 1f64];
+    let a = [1f64];
+    assert!(evaluate(&b, &a, 24_000f64, 48_000f64).norm() < 1e-12);
+    assert!(group_delay(&b, &a, 24_000f64, 48_000f64).is_finite());
+  }
+}
@@ -0,0 +1,383 @@
+//! A state variable filter using the topology-preserving transform.
+
+use num;
+use num::Complex;
+use num::traits::Float;
+
+use filter::response;
+use traits::{FloatConst, FrequencyResponse, Processor};
+
+/* Notes on the state variable filter
+  - This is Andrew Simper's "linear trapezoidal integrated SVF", derived
+    with Vadim Zavalishin's topology-preserving transform (TPT)
+  - The integrators are discretized with the trapezoidal rule, so the filter
+    is the bilinear transform of the analog state variable filter, with the
+    cutoff frequency prewarped
+  - The states are the integrator outputs, not past inputs and outputs like
+    a direct form filter, so changing the coefficients every sample neither
+    clicks nor destabilizes the filter
+  - All responses are mixes of the input and the two integrator outputs:
+    - `low = v2`, `band = v1`, and `high = v0 - k*v1 - v2`
+    - The bell and shelves use the same integrators, so their bandwidth
+      narrows as the gain increases; the shelves are centered at the
+      geometric mean of the cutoff frequency and the frequency `A` times it
+*/
+
+/// A response of a `Svf`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SvfMode {
+  LowPass,
+  HighPass,
+  BandPass,
+  Notch,
+  /// The difference of the low-pass and high-pass responses, which
+  /// resonates at the cutoff frequency
+  Peak,
+  AllPass,
+  /// A peaking equalizer, boosting or cutting at the cutoff frequency
+  Bell,
+  LowShelf,
+  HighShelf
+}
+
+/// All responses of a `Svf` for a single input sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvfOutput<T> {
+  pub lowpass: T,
+  pub highpass: T,
+  pub bandpass: T,
+  pub notch: T,
+  pub peak: T,
+  pub allpass: T,
+  pub bell: T,
+  pub lowshelf: T,
+  pub highshelf: T
+}
+
+/// A state variable filter, using the topology-preserving transform.
+///
+/// Unlike a direct form biquad, the cutoff frequency and resonance can be
+/// changed every sample, such as by an envelope or at audio rate, without
+/// zipper noise or instability. All responses are computed from the same
+/// states with `process_all()`; `process()` outputs the response of the
+/// current `SvfMode`.
+pub struct Svf<T> {
+  ic1eq: T,
+  ic2eq: T,
+  g: T,
+  k: T,
+  a1: T,
+  a2: T,
+  a3: T,
+  gain: T,
+  mode: SvfMode,
+  output: T
+}
+
+impl<T> Svf<T> where T: Float + FloatConst {
+  /// Creates a new `Svf` filter.
+  ///
+  /// The filter will be initialized as a 0dB `SvfMode::Bell`, which does not
+  /// alter the input signal.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::traits::Processor;
+  /// use rasp::filter::{Svf, SvfMode};
+  ///
+  /// let mut filter = Svf::new();
+  /// filter.set_mode(SvfMode::LowPass);
+  ///
+  /// // Sweeps the cutoff frequency every sample
+  /// for i in 0..1_000 {
+  ///   let cutoff = 100f32 + 10f32 * i as f32;
+  ///   filter.set_coefficients(44_100f32, cutoff, 4f32);
+  ///   filter.process(0.5f32);
+  /// }
+  /// ```
+  pub fn new() -> Self {
+    let mut filter = Svf {
+      ic1eq: num::zero(),
+      ic2eq: num::zero(),
+      g: num::zero(),
+      k: num::zero(),
+      a1: num::zero(),
+      a2: num::zero(),
+      a3: num::zero(),
+      gain: num::one(),
+      mode: SvfMode::Bell,
+      output: num::zero()
+    };
+    let quarter: T = num::cast(0.25f64).unwrap();
+    filter.set_coefficients(T::one(), quarter, T::one() / T::two().sqrt());
+    filter
+  }
+
+  /// Sets the filter coefficients, without clearing the filter.
+  ///
+  /// The `q` factor must be positive. This is cheap enough to be called
+  /// every sample.
+  pub fn set_coefficients(&mut self, sample_rate: T, cutoff_frequency: T, q: T) {
+    debug_assert!(q > T::zero());
+    self.g = (T::pi() * cutoff_frequency / sample_rate).tan();
+    self.k = T::one() / q;
+    self.a1 = T::one() / (T::one() + self.g * (self.g + self.k));
+    self.a2 = self.g * self.a1;
+    self.a3 = self.g * self.a2;
+  }
+
+  /// Sets the gain of the bell and shelf responses, in dB.
+  pub fn set_gain(&mut self, db_gain: T) {
+    let forty: T = num::cast(40f64).unwrap();
+    self.gain = num::cast::<f64, T>(10f64).unwrap().powf(db_gain / forty);
+  }
+
+  /// Returns the response `process()` outputs.
+  pub fn mode(&self) -> SvfMode {
+    self.mode
+  }
+
+  /// Sets the response `process()` outputs.
+  pub fn set_mode(&mut self, mode: SvfMode) {
+    self.mode = mode;
+  }
+
+  /// Processes a sample, returning all responses.
+  ///
+  /// The last output is set to the response of the current mode.
+  pub fn process_all(&mut self, sample: T) -> SvfOutput<T> {
+    let v3 = sample - self.ic2eq;
+    let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+    let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+    self.ic1eq = T::two() * v1 - self.ic1eq;
+    self.ic2eq = T::two() * v2 - self.ic2eq;
+
+    let a = self.gain;
+    let band = self.k * v1;
+    let low  = v2;
+    let high = sample - band - v2;
+
+    let output = SvfOutput {
+      lowpass: low,
+      highpass: high,
+      bandpass: v1,
+      notch: low + high,
+      peak: low - high,
+      allpass: low + high - band,
+      bell: sample + (a * a - T::one()) * band,
+      lowshelf: high + a * band + a * a * low,
+      highshelf: a * a * high + a * band + low
+    };
+    self.output = select(&output, self.mode);
+    output
+  }
+
+  /// Returns the numerator of the analog prototype of `mode`, in ascending
+  /// powers of `s`.
+  fn numerator(&self, mode: SvfMode) -> [T; 3] {
+    let (zero, one) = (T::zero(), T::one());
+    let a = self.gain;
+    let k = self.k;
+    match mode {
+      SvfMode::LowPass   => [one, zero, zero],
+      SvfMode::HighPass  => [zero, zero, one],
+      SvfMode::BandPass  => [zero, one, zero],
+      SvfMode::Notch     => [one, zero, one],
+      SvfMode::Peak      => [one, zero, -one],
+      SvfMode::AllPass   => [one, -k, one],
+      SvfMode::Bell      => [one, k * a * a, one],
+      SvfMode::LowShelf  => [a * a, k * a, one],
+      SvfMode::HighShelf => [one, k * a, a * a]
+    }
+  }
+
+  /// Returns the numerator and denominator of `mode`, in ascending powers of
+  /// `z^-1`, from `s = (1 - z^-1) / (g * (1 + z^-1))`, multiplied through by
+  /// `(g * (1 + z^-1))^2`.
+  fn coefficients(&self, mode: SvfMode) -> ([T; 3], [T; 3]) {
+    let g = self.g;
+    let transform = |c: [T; 3]| {
+      let (low, band, high) = (g * g * c[0], g * c[1], c[2]);
+      [low + band + high, T::two() * (low - high), low - band + high]
+    };
+    (transform(self.numerator(mode)), transform([T::one(), self.k, T::one()]))
+  }
+}
+
+/// Returns the response of `mode` from `output`.
+fn select<T: Copy>(output: &SvfOutput<T>, mode: SvfMode) -> T {
+  match mode {
+    SvfMode::LowPass   => output.lowpass,
+    SvfMode::HighPass  => output.highpass,
+    SvfMode::BandPass  => output.bandpass,
+    SvfMode::Notch     => output.notch,
+    SvfMode::Peak      => output.peak,
+    SvfMode::AllPass   => output.allpass,
+    SvfMode::Bell      => output.bell,
+    SvfMode::LowShelf  => output.lowshelf,
+    SvfMode::HighShelf => output.highshelf
+  }
+}
+
+impl<T> Processor<T> for Svf<T> where T: Float + FloatConst {
+  fn process(&mut self, sample: T) -> T {
+    self.process_all(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.ic1eq = num::zero();
+    self.ic2eq = num::zero();
+    self.output = num::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+impl<T> FrequencyResponse<T> for Svf<T> where T: Float + FloatConst {
+  /// Returns the response of the current mode.
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    let (b, a) = self.coefficients(self.mode);
+    response::evaluate(&b, &a, frequency, sample_rate)
+  }
+
+  /// Returns the group delay of the current mode.
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    let (b, a) = self.coefficients(self.mode);
+    response::group_delay(&b, &a, frequency, sample_rate)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use filter::rbj;
+  use ::traits::{FrequencyResponse, Processor};
+
+  #[test]
+  fn new() {
+    let input = vec![0.55f32, -0.55f32, 0.55f32, -0.55f32, 0.25f32];
+    let mut filter = Svf::new();
+    for sample in input.iter() {
+      assert!((filter.process(*sample) - sample).abs() < 1e-6f32);
+    }
+  }
+
+  #[test]
+  fn matches_rbj() {
+    // The RBJ filters are also prewarped bilinear transforms of the same
+    // analog prototypes
+    let (sample_rate, cutoff, q) = (44_100f64, 2_000f64, 2f64);
+    let mut lowpass = rbj::LowPass::new();
+    let mut highpass = rbj::HighPass::new();
+    let mut bandpass = rbj::BandPass2::new();
+    let mut notch = rbj::BandStop::new();
+    let mut allpass = rbj::AllPass::new();
+    lowpass.set_coefficients(sample_rate, cutoff, q);
+    highpass.set_coefficients(sample_rate, cutoff, q);
+    bandpass.set_coefficients(sample_rate, cutoff, q);
+    notch.set_coefficients(sample_rate, cutoff, q);
+    allpass.set_coefficients(sample_rate, cutoff, q);
+
+    let mut filter = Svf::new();
+    filter.set_coefficients(sample_rate, cutoff, q);
+    for i in 0..64 {
+      let sample = if i == 0 { 1f64 } else { 0f64 };
+      let output = filter.process_all(sample);
+      assert!((output.lowpass - lowpass.process(sample)).abs() < 1e-12);
+      assert!((output.highpass - highpass.process(sample)).abs() < 1e-12);
+      assert!((output.bandpass / q - bandpass.process(sample)).abs() < 1e-12);
+      assert!((output.notch - notch.process(sample)).abs() < 1e-12);
+      assert!((output.allpass - allpass.process(sample)).abs() < 1e-12);
+    }
+  }
+
+  #[test]
+  fn responses() {
+    let sample_rate = 48_000f64;
+    let mut filter = Svf::new();
+    filter.set_coefficients(sample_rate, 1_000f64, 0.5f64.sqrt());
+    filter.set_gain(6f64);
+
+    let cases = vec![
+      (SvfMode::LowPass, 0f64, 0f64),
+      (SvfMode::LowPass, 1_000f64, -3.0103f64),
+      (SvfMode::HighPass, 1_000f64, -3.0103f64),
+      (SvfMode::BandPass, 1_000f64, -3.0103f64),
+      (SvfMode::AllPass, 5_000f64, 0f64),
+      (SvfMode::Bell, 1_000f64, 6f64),
+      (SvfMode::Bell, 10f64, 0f64),
+      (SvfMode::LowShelf, 10f64, 6f64),
+      (SvfMode::LowShelf, 20_000f64, 0f64),
+      (SvfMode::HighShelf, 10f64, 0f64),
+      (SvfMode::HighShelf, 24_000f64, 6f64)
+    ];
+    for &(mode, frequency, expected) in cases.iter() {
+      filter.set_mode(mode);
+      let actual = filter.magnitude_db(frequency, sample_rate);
+      println!("{:?} {} {:.4} - {:.4} = {:.4}", mode, frequency, expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-2);
+    }
+  }
+
+  #[test]
+  fn group_delay() {
+    // The group delay matches the equivalent RBJ biquad, in single precision
+    let mut filter = Svf::new();
+    filter.set_mode(SvfMode::LowPass);
+    filter.set_coefficients(48_000f32, 1_000f32, 0.71f32);
+    let mut lowpass = rbj::LowPass::new();
+    lowpass.set_coefficients(48_000f64, 1_000f64, 0.71f64);
+    for frequency in [100f32, 1_000f32, 10_000f32].iter() {
+      let expected = lowpass.group_delay(*frequency as f64, 48_000f64);
+      let actual = filter.group_delay(*frequency, 48_000f32) as f64;
+      println!("{} {:.6} - {:.6} = {:.6}", frequency, expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-3 * expected);
+    }
+  }
+
+  #[test]
+  fn process_matches_mode() {
+    let mut filter = Svf::new();
+    filter.set_coefficients(44_100f32, 500f32, 1f32);
+    filter.set_gain(-12f32);
+    let modes = [SvfMode::LowPass, SvfMode::HighPass, SvfMode::BandPass,
+                 SvfMode::Notch, SvfMode::Peak, SvfMode::AllPass,
+                 SvfMode::Bell, SvfMode::LowShelf, SvfMode::HighShelf];
+    for mode in modes.iter() {
+      let mut all = Svf::new();
+      all.set_coefficients(44_100f32, 500f32, 1f32);
+      all.set_gain(-12f32);
+      filter.clear();
+      filter.set_mode(*mode);
+      for i in 0..32 {
+        let sample = (i as f32 * 0.3f32).sin();
+        let expected = select(&all.process_all(sample), *mode);
+        assert_eq!(filter.process(sample), expected);
+        assert_eq!(filter.last_out(), expected);
+      }
+    }
+  }
+
+  #[test]
+  fn audio_rate_modulation() {
+    // A resonant filter stays bounded while its cutoff jumps every sample
+    let mut filter = Svf::new();
+    filter.set_mode(SvfMode::LowPass);
+    let mut seed = 12_345u32;
+    let mut peak = 0f32;
+    for i in 0..48_000 {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+      let random = (seed >> 8) as f32 / (1u32 << 24) as f32;
+      let cutoff = 20f32 * 1_000f32.powf(random);
+      filter.set_coefficients(48_000f32, cutoff, 10f32);
+      let sample = if (i / 50) % 2 == 0 { 1f32 } else { -1f32 };
+      peak = peak.max(filter.process(sample).abs());
+    }
+    assert!(peak.is_finite());
+    assert!(peak < 100f32);
+  }
+}
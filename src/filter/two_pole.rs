@@ -1,7 +1,9 @@
 use num;
+use num::Complex;
 use num::traits::Float;
 
-use traits::Processor;
+use filter::{response, zplane};
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A single channel, two pole digital filter.
 ///
@@ -45,6 +47,18 @@ impl<T> TwoPole<T> where T: Float {
     }
   }
 
+  /// Creates a new `TwoPole` filter from its poles and gain.
+  ///
+  /// The filter has the transfer function
+  /// `gain / ((1 - p1*z^-1)(1 - p2*z^-1))`, where `p` are `poles`. There can
+  /// be at most two poles, which must be real or a complex conjugate pair.
+  pub fn from_poles(poles: &[Complex<T>], gain: T) -> Self {
+    let (a1, a2) = zplane::coefficients(poles);
+    let mut filter = Self::new();
+    filter.set_coefficients(gain, a1, a2);
+    filter
+  }
+
   /// Sets all filter coefficients at once.
   ///
   /// `a1` and `a2` are feedbacks, or poles.
@@ -53,6 +67,44 @@ impl<T> TwoPole<T> where T: Float {
     self.a1 = a1;
     self.a2 = a2;
   }
+
+  /// Returns the poles of the filter, the roots of `1 + a1*z^-1 + a2*z^-2`.
+  pub fn poles(&self) -> Vec<Complex<T>> {
+    zplane::roots(T::one(), self.a1, self.a2)
+  }
+
+  /// Returns the zeros of the filter, which are both at the origin.
+  ///
+  /// Zeros at infinity, when `b0` is zero, are not returned.
+  pub fn zeros(&self) -> Vec<Complex<T>> {
+    zplane::roots(self.b0, T::zero(), T::zero())
+  }
+
+  /// Returns the largest pole radius.
+  ///
+  /// The filter is stable when this is less than one, and the closer it is
+  /// to one, the longer the filter rings.
+  pub fn stability_margin(&self) -> T {
+    zplane::radius(&self.poles())
+  }
+
+  /// Returns `true` if all poles are strictly inside the unit circle.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::filter::TwoPole;
+  ///
+  /// let mut filter = TwoPole::<f32>::new();
+  /// filter.set_coefficients(1f32, -1.8f32, 0.9f32);
+  /// assert!(filter.is_stable());
+  ///
+  /// filter.set_coefficients(1f32, -1.8f32, 1.1f32);
+  /// assert!(!filter.is_stable());
+  /// ```
+  pub fn is_stable(&self) -> bool {
+    self.stability_margin() < T::one()
+  }
 }
 
 impl<T> Processor<T> for TwoPole<T> where T: Float {
@@ -73,6 +125,16 @@ impl<T> Processor<T> for TwoPole<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for TwoPole<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0], &[T::one(), self.a1, self.a2], frequency, sample_rate)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -136,4 +198,22 @@ mod tests {
       assert!((expected[i] - actual[i]).abs() <= EPSILON);
     }
   }
+
+  #[test]
+  fn poles() {
+    use num::Complex;
+    let pole = Complex::from_polar(&0.95f64, &1.2f64);
+    let filter = TwoPole::from_poles(&[pole, pole.conj()], 0.5f64);
+    assert!((filter.b0 - 0.5f64).abs() < 1e-12);
+    assert!((filter.a2 - 0.9025f64).abs() < 1e-12);
+    assert!(filter.poles().iter().all(|p| (p.norm() - 0.95f64).abs() < 1e-12));
+    assert_eq!(filter.zeros().len(), 2);
+    assert!(filter.zeros().iter().all(|z| z.norm() == 0f64));
+    assert!(filter.is_stable());
+
+    let mut filter = TwoPole::<f32>::new();
+    assert_eq!(filter.stability_margin(), 0f32);
+    filter.set_coefficients(0.9f32, 0.2, -1.3f32);
+    assert!(!filter.is_stable());
+  }
 }
@@ -1,7 +1,9 @@
 use num;
+use num::Complex;
 use num::traits::Float;
 
-use traits::Processor;
+use filter::response;
+use traits::{FloatConst, FrequencyResponse, Processor};
 
 /// A single channel, two zero digital filter.
 ///
@@ -75,6 +77,16 @@ impl<T> Processor<T> for TwoZero<T> where T: Float {
   }
 }
 
+impl<T> FrequencyResponse<T> for TwoZero<T> where T: Float + FloatConst {
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T> {
+    response::evaluate(&[self.b0, self.b1, self.b2], &[T::one()], frequency, sample_rate)
+  }
+
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    response::group_delay(&[self.b0, self.b1, self.b2], &[T::one()], frequency, sample_rate)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -0,0 +1,240 @@
+//! Windowed-sinc FIR filter designs.
+//!
+//! The ideal low-pass kernel is an infinitely long sinc function. These
+//! designs truncate it to `taps` coefficients and apply a `window::Window`,
+//! which trades the width of the transition band for stopband attenuation.
+//! For example, a `Window::Hamming` has about 53dB of attenuation and a
+//! `Window::BlackmanHarris` about 92dB, but the latter needs roughly twice
+//! the taps for the same transition width.
+//!
+//! Kernels are symmetric, so filters have a linear phase and a latency of
+//! `(taps - 1) / 2` samples, reported by `Fir::latency()`. High-pass and
+//! band-stop designs need an odd number of `taps`, since a symmetric kernel
+//! with an even length always has a zero at the Nyquist frequency.
+
+use num;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+use filter::Fir;
+use traits::FloatConst;
+use window::{self, Window};
+
+/// Returns `sin(pi*x) / (pi*x)`.
+fn sinc(x: f64) -> f64 {
+  if x == 0f64 {
+    1f64
+  }
+  else {
+    (PI * x).sin() / (PI * x)
+  }
+}
+
+/// Returns the windowed low-pass kernel with a DC gain of one, with
+/// `cutoff` normalized to the sample rate.
+fn lowpass_kernel(taps: usize, cutoff: f64, window: Window) -> Vec<f64> {
+  debug_assert!(taps > 1);
+  debug_assert!(cutoff > 0f64 && cutoff < 0.5f64);
+  let middle = (taps - 1) as f64 / 2f64;
+  let mut kernel: Vec<f64> = (0..taps)
+    .map(|n| 2f64 * cutoff * sinc(2f64 * cutoff * (n as f64 - middle)))
+    .collect();
+  window::apply_window(&mut kernel, window);
+
+  let sum = kernel.iter().fold(0f64, |sum, coefficient| sum + coefficient);
+  for coefficient in kernel.iter_mut() {
+    *coefficient /= sum;
+  }
+  kernel
+}
+
+/// Returns the spectral inversion of `kernel`, `delta[n - middle] - kernel`.
+fn invert(kernel: &mut [f64]) {
+  debug_assert!(kernel.len() % 2 == 1);
+  for coefficient in kernel.iter_mut() {
+    *coefficient = -*coefficient;
+  }
+  kernel[kernel.len() / 2] += 1f64;
+}
+
+/// Returns the gain of `kernel` at `frequency`, normalized to the sample
+/// rate.
+fn gain(kernel: &[f64], frequency: f64) -> f64 {
+  let middle = (kernel.len() - 1) as f64 / 2f64;
+  kernel.iter().enumerate().fold(0f64, |gain, (n, coefficient)| {
+    gain + coefficient * (2f64 * PI * frequency * (n as f64 - middle)).cos()
+  })
+}
+
+fn normalize<T: Float>(frequency: T, sample_rate: T) -> f64 {
+  num::cast::<T, f64>(frequency / sample_rate).unwrap()
+}
+
+fn build<T: Float>(kernel: Vec<f64>) -> Fir<T> {
+  Fir::new(kernel.into_iter().map(|x| num::cast(x).unwrap()).collect())
+}
+
+/// Designs a low-pass FIR filter, with a gain of one at DC.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::windowed_sinc;
+/// use rasp::window::Window;
+///
+/// let mut filter = windowed_sinc::lowpass(101, 44_100f32, 1_000f32, Window::Blackman);
+/// assert_eq!(filter.latency(), 50f32);
+/// let output = filter.process(1f32);
+/// # assert!(output < 1f32);
+/// ```
+pub fn lowpass<T>(taps: usize,
+                  sample_rate: T,
+                  cutoff_frequency: T,
+                  window: Window) -> Fir<T>
+  where T: Float + FloatConst
+{
+  let cutoff = normalize(cutoff_frequency, sample_rate);
+  build(lowpass_kernel(taps, cutoff, window))
+}
+
+/// Designs a high-pass FIR filter, with a gain of one at the Nyquist
+/// frequency. `taps` must be odd.
+pub fn highpass<T>(taps: usize,
+                   sample_rate: T,
+                   cutoff_frequency: T,
+                   window: Window) -> Fir<T>
+  where T: Float + FloatConst
+{
+  let cutoff = normalize(cutoff_frequency, sample_rate);
+  let mut kernel = lowpass_kernel(taps, cutoff, window);
+  invert(&mut kernel);
+  build(kernel)
+}
+
+/// Designs a band-pass FIR filter, passing frequencies between
+/// `low_frequency` and `high_frequency`, with a gain of one at the center of
+/// the band.
+pub fn bandpass<T>(taps: usize,
+                   sample_rate: T,
+                   low_frequency: T,
+                   high_frequency: T,
+                   window: Window) -> Fir<T>
+  where T: Float + FloatConst
+{
+  let low  = normalize(low_frequency, sample_rate);
+  let high = normalize(high_frequency, sample_rate);
+  debug_assert!(low < high);
+
+  let mut kernel: Vec<f64> = lowpass_kernel(taps, high, window)
+    .iter()
+    .zip(lowpass_kernel(taps, low, window).iter())
+    .map(|(high, low)| high - low)
+    .collect();
+  let center = gain(&kernel, (low + high) / 2f64);
+  for coefficient in kernel.iter_mut() {
+    *coefficient /= center;
+  }
+  build(kernel)
+}
+
+/// Designs a band-stop FIR filter, rejecting frequencies between
+/// `low_frequency` and `high_frequency`, with a gain of one at DC. `taps`
+/// must be odd.
+pub fn bandstop<T>(taps: usize,
+                   sample_rate: T,
+                   low_frequency: T,
+                   high_frequency: T,
+                   window: Window) -> Fir<T>
+  where T: Float + FloatConst
+{
+  let low  = normalize(low_frequency, sample_rate);
+  let high = normalize(high_frequency, sample_rate);
+  debug_assert!(low < high);
+
+  // The sum of a low-pass at `low` and a high-pass at `high`
+  let mut highpass = lowpass_kernel(taps, high, window);
+  invert(&mut highpass);
+  let kernel = lowpass_kernel(taps, low, window)
+    .iter()
+    .zip(highpass.iter())
+    .map(|(low, high)| low + high)
+    .collect();
+  build(kernel)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::{FrequencyResponse, Processor};
+
+  const WINDOWS: [Window; 7] = [
+    Window::Rectangular, Window::Triangular, Window::Bartlett, Window::Hann,
+    Window::Hamming, Window::Blackman, Window::BlackmanHarris
+  ];
+
+  #[test]
+  fn linear_phase() {
+    for window in WINDOWS.iter() {
+      let filters = vec![
+        lowpass(64, 48_000f64, 4_000f64, *window),
+        highpass(63, 48_000f64, 4_000f64, *window),
+        bandpass(64, 48_000f64, 2_000f64, 6_000f64, *window),
+        bandstop(63, 48_000f64, 2_000f64, 6_000f64, *window)
+      ];
+      for filter in filters.iter() {
+        let coefficients = filter.coefficients();
+        for (a, b) in coefficients.iter().zip(coefficients.iter().rev()) {
+          assert!((a - b).abs() < 1e-12);
+        }
+        let expected = (coefficients.len() - 1) as f64 / 2f64;
+        assert_eq!(filter.latency(), expected);
+        assert!((filter.group_delay(1_000f64, 48_000f64) - expected).abs() < 1e-6);
+      }
+    }
+  }
+
+  #[test]
+  fn lowpass_response() {
+    let filter = lowpass(101, 48_000f64, 4_000f64, Window::Blackman);
+    assert!((filter.magnitude(0f64, 48_000f64) - 1f64).abs() < 1e-12);
+    assert!((filter.magnitude(2_000f64, 48_000f64) - 1f64).abs() < 1e-3);
+    assert!((filter.magnitude_db(4_000f64, 48_000f64) - -6.0206f64).abs() < 0.1);
+    for i in 0..100 {
+      let frequency = 7_000f64 + 17_000f64 * i as f64 / 100f64;
+      assert!(filter.magnitude_db(frequency, 48_000f64) < -70f64);
+    }
+  }
+
+  #[test]
+  fn highpass_response() {
+    let filter = highpass(101, 48_000f64, 4_000f64, Window::Hamming);
+    assert!(filter.magnitude(0f64, 48_000f64) < 1e-12);
+    assert!((filter.magnitude(24_000f64, 48_000f64) - 1f64).abs() < 1e-3);
+    assert!(filter.magnitude_db(1_000f64, 48_000f64) < -50f64);
+  }
+
+  #[test]
+  fn band_response() {
+    let bandpass = bandpass(201, 48_000f64, 4_000f64, 8_000f64, Window::Blackman);
+    assert!((bandpass.magnitude(6_000f64, 48_000f64) - 1f64).abs() < 1e-12);
+    assert!(bandpass.magnitude_db(0f64, 48_000f64) < -70f64);
+    assert!(bandpass.magnitude_db(16_000f64, 48_000f64) < -70f64);
+
+    let bandstop = bandstop(201, 48_000f64, 4_000f64, 8_000f64, Window::Blackman);
+    assert!((bandstop.magnitude(0f64, 48_000f64) - 1f64).abs() < 1e-12);
+    assert!((bandstop.magnitude(24_000f64, 48_000f64) - 1f64).abs() < 1e-3);
+    assert!(bandstop.magnitude_db(6_000f64, 48_000f64) < -70f64);
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut filter = lowpass(63, 44_100f32, 1_000f32, Window::Hann);
+    let mut output = 0f32;
+    for _ in 0..1_000 {
+      output = filter.process(1f32);
+    }
+    assert!((output - 1f32).abs() < 1e-5f32);
+  }
+}
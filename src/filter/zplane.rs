@@ -0,0 +1,107 @@
+//! Conversions between second-order coefficients and z-plane roots.
+
+use num::Complex;
+use num::traits::Float;
+
+/* Notes on the z-plane
+  - A second-order polynomial in `z^-1`, `c0 + c1*z^-1 + c2*z^-2`, has the
+    same roots as `c0*z^2 + c1*z + c2`, so roots are found in `z`
+  - With real coefficients, the roots are either both real or a complex
+    conjugate pair
+  - A filter is stable when all of its poles are inside the unit circle
+*/
+
+/// Returns the roots, in `z`, of `c0 + c1*z^-1 + c2*z^-2`.
+///
+/// If `c0` is zero, the roots at infinity are not returned.
+pub fn roots<T: Float>(c0: T, c1: T, c2: T) -> Vec<Complex<T>> {
+  let two = T::one() + T::one();
+  let four = two + two;
+
+  if c0 == T::zero() {
+    return if c1 == T::zero() { Vec::new() }
+           else { vec![Complex::new(-c2 / c1, T::zero())] };
+  }
+
+  let discriminant = c1 * c1 - four * c0 * c2;
+  if discriminant < T::zero() {
+    let re = -c1 / (two * c0);
+    let im = (-discriminant).sqrt() / (two * c0);
+    vec![Complex::new(re, im), Complex::new(re, -im)]
+  }
+  else {
+    // Avoids cancellation between `c1` and the root of the discriminant
+    let sign = if c1 < T::zero() { -T::one() } else { T::one() };
+    let q = -(c1 + sign * discriminant.sqrt()) / two;
+    let first = q / c0;
+    let second = if q == T::zero() { T::zero() } else { c2 / q };
+    vec![Complex::new(first, T::zero()), Complex::new(second, T::zero())]
+  }
+}
+
+/// Returns the coefficients `(c1, c2)` of `1 + c1*z^-1 + c2*z^-2` with the
+/// given roots.
+///
+/// There can be at most two `roots`, which must be real or a complex
+/// conjugate pair.
+pub fn coefficients<T: Float>(roots: &[Complex<T>]) -> (T, T) {
+  debug_assert!(roots.len() <= 2);
+  match roots.len() {
+    0 => (T::zero(), T::zero()),
+    1 => {
+      debug_assert!(roots[0].im == T::zero());
+      (-roots[0].re, T::zero())
+    },
+    _ => {
+      let sum = roots[0] + roots[1];
+      let product = roots[0] * roots[1];
+      let tolerance = T::epsilon().sqrt() * (T::one() + product.norm());
+      debug_assert!(sum.im.abs() <= tolerance && product.im.abs() <= tolerance);
+      (-sum.re, product.re)
+    }
+  }
+}
+
+/// Returns the largest radius of `roots`, or zero if there are none.
+pub fn radius<T: Float>(roots: &[Complex<T>]) -> T {
+  roots.iter().fold(T::zero(), |radius, root| radius.max(root.norm()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use num::Complex;
+
+  #[test]
+  fn real_roots() {
+    // (1 - 0.5z^-1)(1 + 0.25z^-1)
+    let actual = roots(1f64, -0.25f64, -0.125f64);
+    assert_eq!(actual.len(), 2);
+    assert!(actual.iter().any(|root| (root.re - 0.5f64).abs() < 1e-12));
+    assert!(actual.iter().any(|root| (root.re + 0.25f64).abs() < 1e-12));
+    assert!(actual.iter().all(|root| root.im == 0f64));
+  }
+
+  #[test]
+  fn complex_roots() {
+    let pole = Complex::from_polar(&0.9f64, &1f64);
+    let (c1, c2) = coefficients(&[pole, pole.conj()]);
+    assert!((c1 - -2f64 * 0.9f64 * 1f64.cos()).abs() < 1e-12);
+    assert!((c2 - 0.81f64).abs() < 1e-12);
+
+    let actual = roots(1f64, c1, c2);
+    assert!((actual[0] - pole).norm() < 1e-12);
+    assert!((actual[1] - pole.conj()).norm() < 1e-12);
+    assert!((radius(&actual) - 0.9f64).abs() < 1e-12);
+  }
+
+  #[test]
+  fn degenerate_roots() {
+    assert!(roots(0f64, 0f64, 1f64).is_empty());
+    assert_eq!(roots(0f64, 2f64, 1f64), vec![Complex::new(-0.5f64, 0f64)]);
+    assert_eq!(roots(1f64, 0f64, 0f64), vec![Complex::new(0f64, 0f64); 2]);
+    assert_eq!(coefficients::<f64>(&[]), (0f64, 0f64));
+    assert_eq!(coefficients(&[Complex::new(0.5f64, 0f64)]), (-0.5f64, 0f64));
+    assert_eq!(radius::<f64>(&[]), 0f64);
+  }
+}
@@ -1,8 +1,15 @@
 extern crate num;
 
 pub mod analysis;
+pub mod fft;
 pub mod filter;
 pub mod delay;
+pub mod effects;
+pub mod resample;
+pub mod reverb;
 pub mod traits;
 pub mod util;
 pub mod window;
+
+#[cfg(test)]
+mod test_util;
@@ -0,0 +1,282 @@
+use num;
+use num::traits::Float;
+
+use std::f64::consts::PI;
+
+use delay::Delay;
+use filter::Fir;
+use filter::optimal::{self, Band};
+use traits::{FloatConst, Processor};
+
+/* Notes on the half-band filters
+  - A half-band filter has a cutoff of a quarter of its sample rate, and a
+    response symmetric about it, so one of its two polyphase branches is a
+    pure delay; upsampling and downsampling by two run both branches at the
+    lower rate
+  - The FIR filters are equiripple designs, with every other tap zero and a
+    center tap of one half; the polyphase branches are the other taps, and
+    a delay of `(center - 1) / 2` samples for the center tap
+  - The other taps are a type II low-pass at the lower rate, so they are
+    designed directly, as in Vaidyanathan and Nguyen's half-band trick
+  - The IIR filters are sums of two cascades of first-order all-pass filters
+    in `z^2`, `H(z) = (A0(z^2) + z^-1*A1(z^2)) / 2`, designed as elliptic
+    filters by Olli Niemitalo's method, as in Laurent de Soras' HIIR
+  - All-pass filters have a unity magnitude, so the IIR filters have no
+    ripple in the passband, but their phase is not linear, and their latency
+    is the group delay at DC
+  - The passband of each stage extends to 0.454 of the base Nyquist
+    frequency, which is 20 kHz at 44.1 kHz, so later stages, whose images are
+    further away, need fewer taps or coefficients
+*/
+
+/// The end of the passband, relative to the sample rate of the first stage.
+const PASSBAND: f64 = 0.227;
+
+/// Returns `q` and `k` of the elliptic half-band with a `transition` width
+/// relative to its sample rate.
+fn transition_parameters(transition: f64) -> (f64, f64) {
+  let k = ((1f64 - 2f64 * transition) * PI / 4f64).tan().powi(2);
+  let root = (1f64 - k * k).sqrt().sqrt();
+  let e = 0.5f64 * (1f64 - root) / (1f64 + root);
+  let e4 = e.powi(4);
+  let q = e * (1f64 + e4 * (2f64 + e4 * (15f64 + 150f64 * e4)));
+  (k, q)
+}
+
+/// Returns the all-pass coefficients of an elliptic half-band filter.
+fn iir_coefficients(count: usize, transition: f64) -> Vec<f64> {
+  let (k, q) = transition_parameters(transition);
+  let order = (2 * count + 1) as f64;
+  (0..count).map(|index| {
+    let c = (index + 1) as f64;
+
+    let mut numerator = 0f64;
+    let mut i = 0;
+    loop {
+      let term = q.powi(i * (i + 1)) * ((2 * i + 1) as f64 * c * PI / order).sin();
+      numerator += if i % 2 == 1 { -term } else { term };
+      i += 1;
+      if term.abs() <= 1e-100 {
+        break;
+      }
+    }
+
+    let mut denominator = 0f64;
+    let mut i = 1;
+    loop {
+      let term = q.powi(i * i) * ((2 * i) as f64 * c * PI / order).cos();
+      denominator += if i % 2 == 1 { -term } else { term };
+      i += 1;
+      if term.abs() <= 1e-100 {
+        break;
+      }
+    }
+
+    let w = numerator * q.sqrt().sqrt() / (denominator + 0.5f64);
+    let w2 = w * w;
+    let x = ((1f64 - w2 * k) * (1f64 - w2 / k)).sqrt() / (1f64 + w2);
+    (1f64 - x) / (1f64 + x)
+  }).collect()
+}
+
+/// A cascade of first-order all-pass filters, `(a + z^-1) / (1 + a*z^-1)`.
+struct AllpassChain<T> {
+  coefficients: Vec<T>,
+  inputs: Vec<T>,
+  outputs: Vec<T>
+}
+
+impl<T> AllpassChain<T> where T: Float {
+  fn new(coefficients: &[f64]) -> Self {
+    AllpassChain {
+      coefficients: coefficients.iter().map(|a| num::cast(*a).unwrap()).collect(),
+      inputs: vec![T::zero(); coefficients.len()],
+      outputs: vec![T::zero(); coefficients.len()]
+    }
+  }
+
+  /// Returns the group delay at DC, in samples.
+  fn delay(&self) -> f64 {
+    self.coefficients.iter().fold(0f64, |delay, a| {
+      let a: f64 = num::cast(*a).unwrap();
+      delay + (1f64 - a) / (1f64 + a)
+    })
+  }
+
+  fn process(&mut self, sample: T) -> T {
+    let mut x = sample;
+    let sections = self.coefficients.iter()
+      .zip(self.inputs.iter_mut())
+      .zip(self.outputs.iter_mut());
+    for ((a, input), output) in sections {
+      let y = *a * (x - *output) + *input;
+      *input = x;
+      *output = y;
+      x = y;
+    }
+    x
+  }
+
+  fn clear(&mut self) {
+    for state in self.inputs.iter_mut().chain(self.outputs.iter_mut()) {
+      *state = T::zero();
+    }
+  }
+}
+
+/// The polyphase branches of a half-band filter.
+enum Branches<T> {
+  Iir(AllpassChain<T>, AllpassChain<T>),
+  Fir(Fir<T>, Delay<T>)
+}
+
+/// A half-band filter that upsamples or downsamples by two.
+pub struct Stage<T> {
+  branches: Branches<T>,
+  /// The latency, in samples at the higher rate
+  latency: f64
+}
+
+impl<T> Stage<T> where T: Float + FloatConst {
+  /// Creates an IIR half-band filter for the `index`th stage of an
+  /// oversampler.
+  pub fn iir(index: usize) -> Self {
+    let (count, passband) = match index {
+      0 => (6, PASSBAND / 2f64),
+      1 => (3, PASSBAND / 4f64),
+      _ => (2, PASSBAND / 8f64)
+    };
+    let coefficients = iir_coefficients(count, 0.5f64 - 2f64 * passband);
+    let even: Vec<f64> = coefficients.iter().step_by(2).cloned().collect();
+    let odd: Vec<f64> = coefficients.iter().skip(1).step_by(2).cloned().collect();
+    let (even, odd) = (AllpassChain::new(&even), AllpassChain::new(&odd));
+
+    // Each all-pass delays by twice its delay at the lower rate, and the
+    // branches are averaged
+    let latency = (2f64 * even.delay() + 1f64 + 2f64 * odd.delay()) / 2f64;
+    Stage {
+      branches: Branches::Iir(even, odd),
+      latency: latency
+    }
+  }
+
+  /// Creates a linear-phase FIR half-band filter for the `index`th stage of
+  /// an oversampler.
+  pub fn fir(index: usize) -> Self {
+    let (taps, passband) = match index {
+      0 => (31, PASSBAND / 2f64),
+      1 => (15, PASSBAND / 4f64),
+      _ => (11, PASSBAND / 8f64)
+    };
+    // The branch is designed on its own, as a type II low-pass at the lower
+    // rate whose only band is the passband, which is better conditioned than
+    // the half-band itself
+    let center = (taps - 1) / 2;
+    let bands = [Band::new(0f64, 2f64 * passband, 1f64, 1f64)];
+    let branch: Vec<T> = optimal::remez(center + 1, 1f64, &bands).iter()
+      .map(|h| num::cast(*h).unwrap())
+      .collect();
+    Stage {
+      branches: Branches::Fir(Fir::new(branch), Delay::new((center - 1) / 2, center)),
+      latency: center as f64
+    }
+  }
+
+  /// Returns the latency of upsampling, in samples at the higher rate.
+  ///
+  /// Each downsampled sample is aligned with the second of its two samples,
+  /// so the latency of downsampling is one sample less.
+  pub fn latency(&self) -> f64 {
+    self.latency
+  }
+
+  /// Returns the two upsampled samples of `sample`, in order.
+  pub fn upsample(&mut self, sample: T) -> (T, T) {
+    match self.branches {
+      Branches::Iir(ref mut even, ref mut odd) => (even.process(sample), odd.process(sample)),
+      Branches::Fir(ref mut fir, ref mut delay) => (fir.process(sample), delay.process(sample))
+    }
+  }
+
+  /// Returns the downsampled sample of two samples, in order.
+  pub fn downsample(&mut self, first: T, second: T) -> T {
+    let half: T = num::cast(0.5f64).unwrap();
+    match self.branches {
+      Branches::Iir(ref mut even, ref mut odd) => {
+        (even.process(second) + odd.process(first)) * half
+      },
+      Branches::Fir(ref mut fir, ref mut delay) => {
+        (fir.process(second) + delay.process(first)) * half
+      }
+    }
+  }
+
+  pub fn clear(&mut self) {
+    match self.branches {
+      Branches::Iir(ref mut even, ref mut odd) => {
+        even.clear();
+        odd.clear();
+      },
+      Branches::Fir(ref mut fir, ref mut delay) => {
+        fir.clear();
+        delay.clear();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use test_util::amplitude;
+
+  /// Returns the upsampled sine of `frequency`, relative to the higher rate.
+  fn upsampled(stage: &mut Stage<f64>, frequency: f64) -> Vec<f64> {
+    let mut output = Vec::new();
+    for n in 0..4_000 {
+      let (a, b) = stage.upsample((2f64 * PI * frequency * (2 * n) as f64).sin());
+      output.push(a);
+      output.push(b);
+    }
+    output
+  }
+
+  /// Returns the downsampled sine of `frequency`, relative to the higher
+  /// rate.
+  fn downsampled(stage: &mut Stage<f64>, frequency: f64) -> Vec<f64> {
+    (0..4_000).map(|n| {
+      let x0 = (2f64 * PI * frequency * (2 * n) as f64).sin();
+      let x1 = (2f64 * PI * frequency * (2 * n + 1) as f64).sin();
+      stage.downsample(x0, x1)
+    }).collect()
+  }
+
+  #[test]
+  fn iir_coefficients_range() {
+    for a in iir_coefficients(12, 0.046f64).iter() {
+      assert!(*a > 0f64 && *a < 1f64);
+    }
+  }
+
+  #[test]
+  fn responses() {
+    for index in 0..4 {
+      // Whole cycles in the measured halves, at the end of the passband
+      let passband = (PASSBAND / (1 << index) as f64 / 2f64 * 4_000f64).floor() / 4_000f64;
+      for fir in [false, true].iter() {
+        let stage = || if *fir { Stage::fir(index) } else { Stage::iir(index) };
+        for frequency in [0.01f64, passband].iter() {
+          let up = upsampled(&mut stage(), *frequency);
+          let passed = amplitude(&up, up.len() / 2, *frequency);
+          let image = amplitude(&up, up.len() / 2, 0.5f64 - frequency);
+          let down = downsampled(&mut stage(), *frequency);
+          let level = amplitude(&down, down.len() / 2, 2f64 * frequency);
+          let aliased = downsampled(&mut stage(), 0.5f64 - frequency);
+          let alias = amplitude(&aliased, aliased.len() / 2, 2f64 * frequency);
+          assert!((passed - 1f64).abs() < 1e-5 && (level - 1f64).abs() < 1e-5);
+          assert!(image < 1e-5 && alias < 1e-5);
+        }
+      }
+    }
+  }
+}
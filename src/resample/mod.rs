@@ -0,0 +1,112 @@
+//! Sample-rate conversion.
+//!
+//! - `Polyphase` converts between rates with a rational ratio `L/M`, such as
+//!   44.1 kHz and 48 kHz (`160/147`), with a polyphase FIR filter
+//! - `SincResampler` converts by any ratio, which may change over time, by
+//!   interpolating the input with a windowed-sinc kernel
+//! - `Oversampled` runs a processor at 2, 4, 8, or 16 times the sample rate,
+//!   with cascaded half-band filters
+//!
+//! `Polyphase` and `SincResampler` are designed with a `Quality`, and report
+//! their group delay in input samples. Since a block of input does not
+//! produce a fixed number of output samples, they append their output to a
+//! `Vec`. `Oversampled` processes one sample at a time, and reports its
+//! latency in samples at the base rate.
+
+/* Notes on the resampling filters
+  - Both resamplers are low-pass filters whose cutoff frequency is a fraction
+    of the lower of the Nyquist frequencies, given by the `Quality`, to remove
+    the images when upsampling and the aliases when downsampling
+  - Each output sample is a weighted sum of `2 * zero_crossings` input
+    samples, when upsampling, where `zero_crossings` is the number of zero
+    crossings on each side of the sinc kernel; when downsampling, the kernel
+    is stretched by the ratio, and so are the number of input samples
+*/
+
+use window::Window;
+
+mod half_band;
+mod oversampled;
+mod polyphase;
+mod sinc;
+
+pub use self::oversampled::Oversampled as Oversampled;
+pub use self::polyphase::Polyphase     as Polyphase;
+pub use self::sinc::SincResampler      as SincResampler;
+
+/// The quality of a resampler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quality {
+  /// A short kernel with a Hann window, for previews and real-time use
+  Low,
+  /// A Blackman window, with about 75dB of stopband attenuation
+  Medium,
+  /// A long kernel with a Blackman-Harris window, for offline conversion
+  High
+}
+
+impl Quality {
+  /// Returns the number of zero crossings on each side of the kernel.
+  fn zero_crossings(self) -> usize {
+    match self {
+      Quality::Low    => 8,
+      Quality::Medium => 16,
+      Quality::High   => 32
+    }
+  }
+
+  /// Returns the window of the kernel.
+  fn window(self) -> Window {
+    match self {
+      Quality::Low    => Window::Hann,
+      Quality::Medium => Window::Blackman,
+      Quality::High   => Window::BlackmanHarris
+    }
+  }
+
+  /// Returns the cutoff frequency, as a fraction of the Nyquist frequency.
+  fn bandwidth(self) -> f64 {
+    match self {
+      Quality::Low    => 0.8f64,
+      Quality::Medium => 0.88f64,
+      Quality::High   => 0.92f64
+    }
+  }
+}
+
+/// The half-band filters of an oversampler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HalfBand {
+  /// Minimum-phase all-pass filters, with a low latency and no passband
+  /// ripple, but a nonlinear phase
+  Iir,
+  /// Linear-phase equiripple filters, with a higher latency
+  Fir
+}
+
+#[cfg(test)]
+pub mod tests {
+  /// Returns the largest error between `output` and a sine of `frequency`,
+  /// from the `start` of `output`, after resampling a sine from `input_rate`
+  /// to `output_rate` with a group delay of `delay` input samples.
+  pub fn sine_error(output: &[f64],
+                    start: usize,
+                    frequency: f64,
+                    input_rate: f64,
+                    output_rate: f64,
+                    delay: f64) -> f64
+  {
+    output.iter().enumerate().skip(start).fold(0f64, |error, (m, actual)| {
+      let time = m as f64 / output_rate - delay / input_rate;
+      let expected = (2f64 * ::std::f64::consts::PI * frequency * time).sin();
+      error.max((expected - actual).abs())
+    })
+  }
+
+  /// Returns a sine of `frequency` at `sample_rate`.
+  pub fn sine(length: usize, frequency: f64, sample_rate: f64) -> Vec<f64> {
+    (0..length)
+      .map(|n| (2f64 * ::std::f64::consts::PI * frequency * n as f64 / sample_rate).sin())
+      .collect()
+  }
+}
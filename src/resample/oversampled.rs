@@ -0,0 +1,231 @@
+use num;
+use num::traits::Float;
+
+use resample::HalfBand;
+use resample::half_band::Stage;
+use traits::{FloatConst, Processor};
+
+use std::mem;
+
+/* Notes on the oversampler
+  - Each stage doubles the sample rate, so a factor of `2^n` cascades `n`
+    half-band upsamplers, and `n` half-band downsamplers in reverse order
+  - The first stage has the narrowest transition band, since the images of
+    the input are closest to its passband, and later stages need fewer taps
+  - The latency of each stage is in samples at its higher rate, so a stage
+    running at `2^(i+1)` times the base rate adds `latency / 2^(i+1)` base
+    samples, once when upsampling and once when downsampling, where it is
+    one sample less
+*/
+
+/// A processor that runs at a multiple of the sample rate.
+///
+/// Nonlinear processors, such as waveshapers, create harmonics above the
+/// Nyquist frequency, which alias back into the audible band. Running them
+/// at a higher rate, and filtering before returning to the base rate, moves
+/// those harmonics out of the way.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::filter::Fir;
+/// use rasp::resample::{HalfBand, Oversampled};
+///
+/// let mut oversampled = Oversampled::new(Fir::new(vec![1f32]), 4, HalfBand::Fir);
+/// assert_eq!(oversampled.factor(), 4);
+///
+/// let latency = oversampled.latency();
+/// let output = oversampled.process(0.5f32);
+/// # assert!(latency > 0f32 && output.abs() < 0.5f32);
+/// ```
+pub struct Oversampled<T, P> {
+  processor: P,
+  factor: usize,
+  /// The stages, from the base rate to the highest rate
+  upsamplers: Vec<Stage<T>>,
+  downsamplers: Vec<Stage<T>>,
+  buffer: Vec<T>,
+  scratch: Vec<T>,
+  latency: T,
+  output: T
+}
+
+impl<T, P> Oversampled<T, P> where T: Float + FloatConst, P: Processor<T> {
+  /// Creates a new `Oversampled` processor, which runs `processor` at
+  /// `factor` times the sample rate, where `factor` is 2, 4, 8, or 16.
+  pub fn new(processor: P, factor: usize, half_band: HalfBand) -> Self {
+    debug_assert!(factor == 2 || factor == 4 || factor == 8 || factor == 16);
+    let stages = factor.trailing_zeros() as usize;
+    let stage = |index| match half_band {
+      HalfBand::Iir => Stage::iir(index),
+      HalfBand::Fir => Stage::fir(index)
+    };
+    let upsamplers: Vec<Stage<T>> = (0..stages).map(&stage).collect();
+    let downsamplers: Vec<Stage<T>> = (0..stages).map(&stage).collect();
+
+    let latency = upsamplers.iter()
+      .zip(downsamplers.iter())
+      .enumerate()
+      .fold(0f64, |latency, (index, (up, down))| {
+        latency + (up.latency() + down.latency() - 1f64) / (2 << index) as f64
+      });
+
+    Oversampled {
+      processor: processor,
+      factor: factor,
+      upsamplers: upsamplers,
+      downsamplers: downsamplers,
+      buffer: Vec::with_capacity(factor),
+      scratch: Vec::with_capacity(factor),
+      latency: num::cast(latency).unwrap(),
+      output: T::zero()
+    }
+  }
+
+  /// Returns the oversampling factor.
+  pub fn factor(&self) -> usize {
+    self.factor
+  }
+
+  /// Returns the latency added by the half-band filters, in samples at the
+  /// base rate.
+  ///
+  /// The IIR filters do not have a linear phase, so this is their group
+  /// delay at DC.
+  pub fn latency(&self) -> T {
+    self.latency
+  }
+
+  /// Returns the inner processor.
+  pub fn processor(&self) -> &P {
+    &self.processor
+  }
+
+  /// Returns the inner processor, to change its parameters.
+  pub fn processor_mut(&mut self) -> &mut P {
+    &mut self.processor
+  }
+}
+
+impl<T, P> Processor<T> for Oversampled<T, P> where T: Float + FloatConst, P: Processor<T> {
+  fn process(&mut self, sample: T) -> T {
+    self.buffer.clear();
+    self.buffer.push(sample);
+    for stage in self.upsamplers.iter_mut() {
+      self.scratch.clear();
+      for sample in self.buffer.iter() {
+        let (first, second) = stage.upsample(*sample);
+        self.scratch.push(first);
+        self.scratch.push(second);
+      }
+      mem::swap(&mut self.buffer, &mut self.scratch);
+    }
+
+    for sample in self.buffer.iter_mut() {
+      *sample = self.processor.process(*sample);
+    }
+
+    for stage in self.downsamplers.iter_mut().rev() {
+      self.scratch.clear();
+      for pair in self.buffer.chunks(2) {
+        self.scratch.push(stage.downsample(pair[0], pair[1]));
+      }
+      mem::swap(&mut self.buffer, &mut self.scratch);
+    }
+
+    self.output = self.buffer[0];
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for stage in self.upsamplers.iter_mut().chain(self.downsamplers.iter_mut()) {
+      stage.clear();
+    }
+    self.processor.clear();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use resample::tests::sine;
+  use test_util::amplitude;
+
+  use std::f64::consts::PI;
+
+  /// Raises samples to a power, which creates harmonics up to that multiple
+  /// of the input frequency.
+  struct Power {
+    exponent: i32,
+    output: f64
+  }
+
+  impl Processor<f64> for Power {
+    fn process(&mut self, sample: f64) -> f64 {
+      self.output = sample.powi(self.exponent);
+      self.output
+    }
+
+    fn clear(&mut self) {
+      self.output = 0f64;
+    }
+
+    fn last_out(&self) -> f64 {
+      self.output
+    }
+  }
+
+  #[test]
+  fn latency() {
+    // Without a processor, the output is the input, delayed by the latency
+    for half_band in [HalfBand::Iir, HalfBand::Fir].iter() {
+      for factor in [2, 4, 8, 16].iter() {
+        let mut oversampled = Oversampled::new(Power { exponent: 1, output: 0f64 },
+                                               *factor,
+                                               *half_band);
+        let latency = oversampled.latency();
+        let input = sine(2_000, 100f64, 48_000f64);
+        let error = input.iter().enumerate().fold(0f64, |error, (n, x)| {
+          let expected = (2f64 * PI * 100f64 * (n as f64 - latency) / 48_000f64).sin();
+          let actual = oversampled.process(*x);
+          if n < 500 { error } else { error.max((expected - actual).abs()) }
+        });
+        assert!(error < 1e-5);
+      }
+    }
+  }
+
+  #[test]
+  fn aliasing() {
+    // The seventh harmonic of 0.15 is at 1.05, which aliases to 0.05
+    let input = sine(4_000, 0.15f64, 1f64);
+    let mut power = Power { exponent: 7, output: 0f64 };
+    let aliased: Vec<f64> = input.iter().map(|x| power.process(*x)).collect();
+    assert!(amplitude(&aliased, 0, 0.05f64) > 1e-2);
+
+    for half_band in [HalfBand::Iir, HalfBand::Fir].iter() {
+      let mut oversampled = Oversampled::new(Power { exponent: 7, output: 0f64 }, 2, *half_band);
+      let output: Vec<f64> = input.iter().map(|x| oversampled.process(*x)).collect();
+      assert!(amplitude(&output, 2_000, 0.05f64) < 1e-4);
+
+      oversampled.clear();
+      assert_eq!(oversampled.last_out(), 0f64);
+      assert_eq!(oversampled.process(input[0]), output[0]);
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut oversampled = Oversampled::new(::filter::Fir::new(vec![1f32]), 8, HalfBand::Iir);
+    let mut samples = [1f32; 200];
+    oversampled.process_block(&mut samples);
+    assert!((samples[199] - 1f32).abs() < 1e-4f32);
+    assert_eq!(oversampled.processor().taps(), 1);
+  }
+}
@@ -0,0 +1,178 @@
+use num;
+use num::traits::Float;
+
+use filter::windowed_sinc;
+use resample::Quality;
+use traits::FloatConst;
+
+/* Notes on the polyphase resampler
+  - Upsampling by `L` inserts `L - 1` zeros between input samples, and
+    downsampling by `M` keeps every `M`th sample, with a low-pass prototype
+    filter at the intermediate rate between them
+  - Only one of every `L` taps of the prototype meets a non-zero sample, so
+    the prototype is split into `L` phases, `h[p + k*L]`, and each output only
+    computes the phase at its position between input samples
+  - The prototype has a gain of `L` to restore the level lost to the zeros
+*/
+
+fn gcd(a: usize, b: usize) -> usize {
+  if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// A polyphase resampler with a rational ratio.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::resample::{Polyphase, Quality};
+///
+/// // 44.1 kHz to 48 kHz
+/// let mut resampler = Polyphase::new(48_000, 44_100, Quality::Medium);
+/// assert_eq!(resampler.ratio(), (160, 147));
+///
+/// let mut output = Vec::new();
+/// resampler.process(&[0.5f32; 441], &mut output);
+/// assert_eq!(output.len(), 480);
+/// ```
+pub struct Polyphase<T> {
+  up: usize,
+  down: usize,
+  /// The phases of the prototype filter
+  phases: Vec<Vec<T>>,
+  /// The input history, written twice, as in `filter::Fir`
+  history: Vec<T>,
+  index: usize,
+  /// The position of the next output between input samples, at the
+  /// intermediate rate
+  phase: usize,
+  group_delay: T
+}
+
+impl<T> Polyphase<T> where T: Float + FloatConst {
+  /// Creates a new `Polyphase` resampler, which upsamples by `up` and then
+  /// downsamples by `down`.
+  ///
+  /// The ratio is reduced, so the sample rates can be given directly.
+  pub fn new(up: usize, down: usize, quality: Quality) -> Self {
+    debug_assert!(up > 0 && down > 0);
+    let divisor = gcd(up, down);
+    let (up, down) = (up / divisor, down / divisor);
+
+    // The prototype, at the intermediate rate of `up` samples per input,
+    // is stretched by `down / up` when downsampling
+    let crossings = 2 * quality.zero_crossings() * up.max(down);
+    let length = (crossings as f64 / up as f64).ceil() as usize;
+    let taps = length * up;
+    let nyquist = 0.5f64 / up.max(down) as f64;
+    let cutoff: T = num::cast(quality.bandwidth() * nyquist).unwrap();
+    let prototype = windowed_sinc::lowpass(taps, T::one(), cutoff, quality.window());
+    let gain: T = num::cast(up).unwrap();
+
+    let phases = (0..up).map(|p| {
+      (0..length).map(|k| prototype.coefficients()[p + k * up] * gain).collect()
+    }).collect();
+    let delay: T = num::cast((taps - 1) as f64 / (2f64 * up as f64)).unwrap();
+
+    Polyphase {
+      up: up,
+      down: down,
+      phases: phases,
+      history: vec![T::zero(); 2 * length],
+      index: 0,
+      phase: 0,
+      group_delay: delay
+    }
+  }
+
+  /// Returns the reduced ratio, `(up, down)`.
+  pub fn ratio(&self) -> (usize, usize) {
+    (self.up, self.down)
+  }
+
+  /// Returns the group delay, in input samples.
+  pub fn group_delay(&self) -> T {
+    self.group_delay
+  }
+
+  /// Resamples a block of `input`, appending the resampled samples to
+  /// `output`.
+  ///
+  /// Every input sample produces either `up / down` samples, rounded down,
+  /// or one more.
+  pub fn process(&mut self, input: &[T], output: &mut Vec<T>) {
+    let length = self.history.len() / 2;
+    for sample in input.iter() {
+      self.index = if self.index == 0 { length - 1 } else { self.index - 1 };
+      self.history[self.index] = *sample;
+      self.history[self.index + length] = *sample;
+
+      let history = &self.history[self.index..self.index + length];
+      while self.phase < self.up {
+        let phase = &self.phases[self.phase];
+        let sum = phase.iter().zip(history.iter()).fold(T::zero(), |sum, (h, x)| {
+          sum + *h * *x
+        });
+        output.push(sum);
+        self.phase += self.down;
+      }
+      self.phase -= self.up;
+    }
+  }
+
+  /// Clears the input history.
+  pub fn clear(&mut self) {
+    for sample in self.history.iter_mut() {
+      *sample = T::zero();
+    }
+    self.index = 0;
+    self.phase = 0;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use resample::tests::{sine, sine_error};
+
+  #[test]
+  fn ratio() {
+    assert_eq!(Polyphase::<f32>::new(48_000, 44_100, Quality::Low).ratio(), (160, 147));
+    assert_eq!(Polyphase::<f32>::new(2, 4, Quality::Low).ratio(), (1, 2));
+
+    let mut resampler = Polyphase::new(3, 2, Quality::Low);
+    let mut output = Vec::new();
+    for length in [1, 5, 10, 3, 1].iter() {
+      resampler.process(&vec![0f32; *length], &mut output);
+    }
+    assert_eq!(output.len(), 30);
+  }
+
+  #[test]
+  fn sines() {
+    let rates = [(44_100f64, 48_000f64), (48_000f64, 44_100f64),
+                 (44_100f64, 88_200f64), (96_000f64, 48_000f64)];
+    let qualities = [(Quality::Low, 2e-2), (Quality::Medium, 1e-3), (Quality::High, 1e-4)];
+    for &(input_rate, output_rate) in rates.iter() {
+      for &(quality, tolerance) in qualities.iter() {
+        let mut resampler = Polyphase::new(output_rate as usize, input_rate as usize, quality);
+        let mut output = Vec::new();
+        resampler.process(&sine(4_000, 1_000f64, input_rate), &mut output);
+
+        let delay = resampler.group_delay();
+        let error = sine_error(&output, 200, 1_000f64, input_rate, output_rate, delay);
+        println!("{} -> {}, {:?}: {}", input_rate, output_rate, quality, error);
+        assert!(error < tolerance);
+      }
+    }
+  }
+
+  #[test]
+  fn anti_aliasing() {
+    // A sine above the output Nyquist frequency is removed
+    let mut resampler = Polyphase::new(1, 2, Quality::Medium);
+    let mut output = Vec::new();
+    resampler.process(&sine(4_000, 15_000f64, 48_000f64), &mut output);
+    let peak = output.iter().skip(100).fold(0f64, |peak, x| peak.max(x.abs()));
+    assert!(peak < 1e-3);
+  }
+}
@@ -0,0 +1,233 @@
+use num;
+use num::traits::Float;
+
+use resample::Quality;
+use traits::FloatConst;
+use window;
+
+/* Notes on the windowed-sinc resampler
+  - Each output sample is the input interpolated at a fractional position,
+    with a windowed sinc kernel centered at that position
+  - The kernel is tabulated once, with `RESOLUTION` points per zero crossing,
+    and linearly interpolated between points
+  - When downsampling, the kernel is stretched by `1 / ratio` and scaled by
+    `ratio`, which lowers its cutoff frequency to the output Nyquist
+  - The position advances by `1 / ratio` input samples per output sample, so
+    changing the ratio changes the output rate without any discontinuity
+  - The input is preceded by `group_delay()` samples of silence, so the first
+    output sample is at the start of that silence
+*/
+
+/// The number of points of the kernel table per zero crossing.
+const RESOLUTION: usize = 512;
+
+/// A windowed-sinc resampler with an arbitrary, time-varying ratio.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::resample::{Quality, SincResampler};
+///
+/// let mut resampler = SincResampler::new(48_000f64 / 44_100f64, Quality::High);
+/// let mut output = Vec::new();
+/// resampler.process(&[0.5f32; 4_410], &mut output);
+///
+/// // Compensates for a clock drift of 10 ppm
+/// resampler.set_ratio(48_000f64 / 44_100f64 * 1.000_01f64);
+/// resampler.process(&[0.5f32; 4_410], &mut output);
+/// # assert!((output.len() as f64 - 9_600f64).abs() < 40f64);
+/// ```
+pub struct SincResampler<T> {
+  ratio: f64,
+  zero_crossings: usize,
+  /// The right half of the kernel, in zero crossings
+  table: Vec<T>,
+  /// The input samples from the earliest one the kernel can reach
+  buffer: Vec<T>,
+  /// The position of the next output sample in `buffer`
+  position: f64,
+  group_delay: usize
+}
+
+impl<T> SincResampler<T> where T: Float + FloatConst {
+  /// Creates a new `SincResampler`, where `ratio` is the output sample rate
+  /// divided by the input sample rate.
+  ///
+  /// The group delay is set for the initial ratio, so a resampler meant to
+  /// downsample further than `ratio` has a lower delay than its kernel needs,
+  /// and the start of its output is less accurate.
+  pub fn new(ratio: f64, quality: Quality) -> Self {
+    debug_assert!(ratio > 0f64);
+    let zero_crossings = quality.zero_crossings();
+    let length = zero_crossings * RESOLUTION;
+    let mut window = vec![T::one(); 2 * length + 1];
+    window::apply_window(&mut window, quality.window());
+
+    let bandwidth = quality.bandwidth();
+    let table = (0..length + 1).map(|i| {
+      let x = bandwidth * i as f64 / RESOLUTION as f64;
+      let sinc = if x == 0f64 { 1f64 } else {
+        (::std::f64::consts::PI * x).sin() / (::std::f64::consts::PI * x)
+      };
+      let sinc: T = num::cast(bandwidth * sinc).unwrap();
+      sinc * window[length + i]
+    }).collect();
+
+    let mut resampler = SincResampler {
+      ratio: ratio,
+      zero_crossings: zero_crossings,
+      table: table,
+      buffer: Vec::new(),
+      position: 0f64,
+      group_delay: 0
+    };
+    resampler.group_delay = resampler.half_width().ceil() as usize;
+    resampler.clear();
+    resampler
+  }
+
+  /// Returns the ratio of the output sample rate to the input sample rate.
+  pub fn ratio(&self) -> f64 {
+    self.ratio
+  }
+
+  /// Sets the ratio of the output sample rate to the input sample rate.
+  ///
+  /// This can be called between blocks of any length, without clicks.
+  pub fn set_ratio(&mut self, ratio: f64) {
+    debug_assert!(ratio > 0f64);
+    self.ratio = ratio;
+  }
+
+  /// Returns the group delay, in input samples.
+  pub fn group_delay(&self) -> T {
+    num::cast(self.group_delay).unwrap()
+  }
+
+  /// Returns the stretch of the kernel, which is one when upsampling.
+  fn scale(&self) -> f64 {
+    self.ratio.min(1f64)
+  }
+
+  /// Returns the number of input samples on each side of the kernel.
+  fn half_width(&self) -> f64 {
+    self.zero_crossings as f64 / self.scale()
+  }
+
+  /// Returns the kernel at `x` zero crossings from its center.
+  fn kernel(&self, x: f64) -> T {
+    let index = x.abs() * RESOLUTION as f64;
+    let i = index as usize;
+    if i >= self.table.len() - 1 {
+      return T::zero();
+    }
+    let fraction: T = num::cast(index - i as f64).unwrap();
+    self.table[i] + (self.table[i + 1] - self.table[i]) * fraction
+  }
+
+  /// Resamples a block of `input`, appending the resampled samples to
+  /// `output`.
+  pub fn process(&mut self, input: &[T], output: &mut Vec<T>) {
+    self.buffer.extend_from_slice(input);
+    let scale = self.scale();
+    let half_width = self.half_width();
+    let gain: T = num::cast(scale).unwrap();
+
+    while self.position + half_width < (self.buffer.len() - 1) as f64 {
+      let first = (self.position - half_width).ceil().max(0f64) as usize;
+      let last = (self.position + half_width).floor() as usize;
+      let mut sum = T::zero();
+      for (n, sample) in self.buffer[first..last + 1].iter().enumerate() {
+        let x = ((first + n) as f64 - self.position) * scale;
+        sum = sum + *sample * self.kernel(x);
+      }
+      output.push(sum * gain);
+      self.position += 1f64 / self.ratio;
+    }
+
+    // Discards the samples the kernel can no longer reach
+    let reach = (self.position - half_width).floor();
+    if reach >= 1f64 {
+      let consumed = reach as usize;
+      self.buffer.drain(..consumed);
+      self.position -= consumed as f64;
+    }
+  }
+
+  /// Clears the input.
+  pub fn clear(&mut self) {
+    self.buffer.clear();
+    self.buffer.resize(self.group_delay, T::zero());
+    self.position = 0f64;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use resample::tests::{sine, sine_error};
+
+  #[test]
+  fn sines() {
+    let rates = [(44_100f64, 48_000f64), (48_000f64, 44_100f64),
+                 (44_100f64, 88_200f64), (96_000f64, 44_100f64)];
+    let qualities = [(Quality::Low, 2e-2), (Quality::Medium, 1e-3), (Quality::High, 1e-4)];
+    for &(input_rate, output_rate) in rates.iter() {
+      for &(quality, tolerance) in qualities.iter() {
+        let mut resampler = SincResampler::new(output_rate / input_rate, quality);
+        let input = sine(4_000, 1_000f64, input_rate);
+        let mut output = Vec::new();
+        for block in input.chunks(300) {
+          resampler.process(block, &mut output);
+        }
+
+        let delay = resampler.group_delay();
+        let error = sine_error(&output, 200, 1_000f64, input_rate, output_rate, delay);
+        println!("{} -> {}, {:?}: {}", input_rate, output_rate, quality, error);
+        assert!(error < tolerance);
+
+        // The last samples are buffered until the kernel can reach past them
+        let ratio = output_rate / input_rate;
+        assert!((output.len() as f64 - 4_000f64 * ratio).abs() <= 2f64 * ratio);
+      }
+    }
+  }
+
+  #[test]
+  fn drift() {
+    // The ratio changes every block, and the phase of the output follows
+    let mut resampler = SincResampler::<f64>::new(1f64, Quality::Medium);
+    let input = sine(48_000, 500f64, 48_000f64);
+    let mut output = Vec::new();
+    let mut time = -resampler.group_delay();
+    let mut error = 0f64;
+    for (i, block) in input.chunks(64).enumerate() {
+      let ratio = 1f64 + 0.01f64 * (i as f64 * 0.05f64).sin();
+      resampler.set_ratio(ratio);
+      let start = output.len();
+      resampler.process(block, &mut output);
+      for actual in output[start..].iter() {
+        let expected = (2f64 * ::std::f64::consts::PI * 500f64 * time / 48_000f64).sin();
+        if time > 100f64 {
+          error = error.max((expected - actual).abs());
+        }
+        time += 1f64 / ratio;
+      }
+    }
+    println!("{}", error);
+    assert!(error < 1e-3);
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut resampler = SincResampler::new(0.5f64, Quality::Low);
+    let mut output = Vec::new();
+    resampler.process(&[1f32; 1_000], &mut output);
+    assert!((output[400] - 1f32).abs() < 1e-2f32);
+
+    resampler.clear();
+    let mut cleared = Vec::new();
+    resampler.process(&[1f32; 1_000], &mut cleared);
+    assert_eq!(output, cleared);
+  }
+}
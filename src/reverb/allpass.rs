@@ -0,0 +1,109 @@
+use num::traits::Float;
+
+use delay::LinearDelay;
+use traits::{ModulatedDelayLine, Processor, TappableDelayLine};
+
+/// A Schroeder all-pass filter, which diffuses its input without coloring
+/// its spectrum.
+///
+/// It uses the following equations, where `M` is the delay:
+///
+/// `v[n] = x[n] + g*v[n-M]; y[n] = v[n-M] - g*v[n]`
+///
+/// The delay may be fractional, and change over time, up to its maximum.
+/// The delay line is read as it is written, so `v[n-M]` is read a sample
+/// early, `M - 1` samples after `v[n-1]`.
+pub struct Allpass<T> {
+  delay: LinearDelay<T>,
+  /// The delay, in samples
+  length: T,
+  /// The value read for the next sample, `v[n+1-M]`
+  delayed: T,
+  gain: T,
+  output: T
+}
+
+impl<T> Allpass<T> where T: Float {
+  /// Creates a new `Allpass` with a delay of `delay` samples, which can be
+  /// modulated up to `max_delay` samples.
+  pub fn new(delay: T, max_delay: usize, gain: T) -> Self {
+    Allpass {
+      delay: LinearDelay::new(0f32, max_delay),
+      length: delay,
+      delayed: T::zero(),
+      gain: gain,
+      output: T::zero()
+    }
+  }
+
+  /// Returns the value stored `tap_delay` samples ago, inside the filter.
+  pub fn tap_out(&self, tap_delay: usize) -> T {
+    self.delay.tap_out(tap_delay)
+  }
+
+  /// Sets the feedback gain, which must be less than one.
+  pub fn set_gain(&mut self, gain: T) {
+    self.gain = gain;
+  }
+
+  /// Processes a sample with a delay of `delay` samples from the next
+  /// sample, which is kept as the delay.
+  pub fn process_modulated(&mut self, sample: T, delay: T) -> T {
+    self.length = delay;
+    let v = sample + self.gain * self.delayed;
+    self.output = self.delayed - self.gain * v;
+    self.delayed = self.delay.process_modulated(v, delay - T::one());
+    self.output
+  }
+}
+
+impl<T> Processor<T> for Allpass<T> where T: Float {
+  fn process(&mut self, sample: T) -> T {
+    let length = self.length;
+    self.process_modulated(sample, length)
+  }
+
+  fn clear(&mut self) {
+    self.delay.clear();
+    self.delayed = T::zero();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn lossless() {
+    // The impulse response keeps all of the energy of the impulse
+    let mut allpass = Allpass::new(7f64, 7, 0.7f64);
+    let energy = (0..2000).fold(0f64, |energy, n| {
+      let output = allpass.process(if n == 0 { 1f64 } else { 0f64 });
+      energy + output * output
+    });
+    assert!((energy - 1f64).abs() < 1e-9);
+    assert!((allpass.last_out()).abs() < 1e-9);
+
+    allpass.clear();
+    let energy = (0..2000).fold(0f64, |energy, n| {
+      let output = allpass.process_modulated(if n == 0 { 1f64 } else { 0f64 }, 4.5f64);
+      energy + output * output
+    });
+    assert!(energy > 0.5f64 && energy < 1f64);
+  }
+
+  #[test]
+  fn tap_out() {
+    let mut allpass = Allpass::new(3f64, 3, 0f64);
+    for x in [1f64, 0f64, 0f64].iter() {
+      allpass.process(*x);
+    }
+    assert_eq!(allpass.tap_out(2), 1f64);
+    assert_eq!(allpass.tap_out(0), 0f64);
+  }
+}
@@ -0,0 +1,375 @@
+use num;
+use num::traits::Float;
+
+use delay::{Delay, LinearDelay};
+use filter::OnePole;
+use reverb::allpass::Allpass;
+use traits::{FloatConst, Processor, TappableDelayLine};
+
+/* Notes on the plate reverb
+  - This follows Dattorro's "Effect Design, Part 1" (1997): a pre-delay, a
+    one-pole bandwidth filter, and four input all-pass diffusers, into a
+    "tank" of two cross-coupled halves
+  - Each half of the tank is a modulated all-pass, a delay, a damping
+    filter, the decay gain, a second all-pass, and a second delay, whose
+    output feeds the other half
+  - The lengths are those of the paper, at its sample rate of 29761 Hz, and
+    are scaled to the sample rate
+  - The outputs sum taps from the delays and all-passes of both halves, with
+    the positions and signs of the paper
+  - The two modulated all-passes are driven in quadrature, by a sine and a
+    cosine, so the modulation does not change the overall pitch
+*/
+
+/// The sample rate of the lengths in the paper.
+const RATE: f64 = 29_761.0;
+
+/// The lengths of the input diffusers.
+const DIFFUSERS: [usize; 4] = [142, 107, 379, 277];
+
+/// The lengths of the modulated all-passes, the first delays, the second
+/// all-passes, and the second delays, of the left and right halves.
+const LEFT: [usize; 4] = [672, 4453, 1800, 3720];
+const RIGHT: [usize; 4] = [908, 4217, 2656, 3163];
+
+/// The largest modulation depth, in samples.
+const MAX_EXCURSION: usize = 32;
+
+/// The output taps, as a line of a half, a position, and a sign, where the
+/// lines are the first delay, the second all-pass, and the second delay.
+const LEFT_TAPS: [(usize, usize, usize, f64); 7] = [
+  (1, 0, 266, 1.0),
+  (1, 0, 2974, 1.0),
+  (1, 1, 1913, -1.0),
+  (1, 2, 1996, 1.0),
+  (0, 0, 1990, -1.0),
+  (0, 1, 187, -1.0),
+  (0, 2, 1066, -1.0)
+];
+const RIGHT_TAPS: [(usize, usize, usize, f64); 7] = [
+  (0, 0, 353, 1.0),
+  (0, 0, 3627, 1.0),
+  (0, 1, 1228, -1.0),
+  (0, 2, 2673, 1.0),
+  (1, 0, 2111, -1.0),
+  (1, 1, 335, -1.0),
+  (1, 2, 121, -1.0)
+];
+
+/// One half of the tank.
+struct Half<T: Float> {
+  modulated: Allpass<T>,
+  first: Delay<T>,
+  damping: OnePole<T>,
+  allpass: Allpass<T>,
+  second: Delay<T>,
+  /// The unmodulated length of the modulated all-pass
+  length: T
+}
+
+impl<T> Half<T> where T: Float {
+  fn new<F: Fn(usize) -> usize>(lengths: &[usize; 4], scale: &F) -> Self {
+    let length = scale(lengths[0]);
+    Half {
+      modulated: Allpass::new(num::cast(length).unwrap(), length + scale(MAX_EXCURSION) + 1, T::zero()),
+      first: Delay::new(scale(lengths[1]), scale(lengths[1])),
+      damping: OnePole::new(),
+      allpass: Allpass::new(num::cast(scale(lengths[2])).unwrap(), scale(lengths[2]), T::zero()),
+      second: Delay::new(scale(lengths[3]), scale(lengths[3])),
+      length: num::cast(length).unwrap()
+    }
+  }
+
+  /// Processes a sample, with the modulated all-pass `excursion` samples
+  /// longer than its length.
+  fn process(&mut self, sample: T, excursion: T, decay: T) {
+    let x = self.modulated.process_modulated(sample, self.length + excursion);
+    let x = self.damping.process(self.first.process(x)) * decay;
+    let x = self.allpass.process(x);
+    self.second.process(x);
+  }
+
+  /// Returns the value at `tap` of `line`.
+  fn tap(&self, line: usize, tap: usize) -> T {
+    match line {
+      0 => self.first.tap_out(tap),
+      1 => self.allpass.tap_out(tap),
+      _ => self.second.tap_out(tap)
+    }
+  }
+
+  fn clear(&mut self) {
+    self.modulated.clear();
+    self.first.clear();
+    self.damping.clear();
+    self.allpass.clear();
+    self.second.clear();
+  }
+}
+
+/// Dattorro's plate reverb.
+///
+/// The output is only the reverberated signal.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::reverb::Dattorro;
+///
+/// let mut reverb = Dattorro::new(48_000f32);
+/// reverb.set_pre_delay(0.02f32);
+/// reverb.set_decay(0.7f32);
+/// reverb.set_damping(0.3f32);
+///
+/// let (left, right) = reverb.process_stereo(1f32);
+/// let tail = reverb.process(0f32);
+/// # assert_eq!((left, right, tail), (0f32, 0f32, 0f32));
+/// ```
+pub struct Dattorro<T: Float> {
+  sample_rate: T,
+  pre_delay: LinearDelay<T>,
+  bandwidth: OnePole<T>,
+  diffusers: Vec<Allpass<T>>,
+  left: Half<T>,
+  right: Half<T>,
+  /// The gain of the tank, which sets the decay time
+  decay: T,
+  /// The depth, in samples, and rate, relative to the sample rate, of the
+  /// modulation
+  excursion: T,
+  rate: T,
+  phase: T,
+  /// The scale of the lengths to the sample rate
+  ratio: f64,
+  output: T
+}
+
+impl<T> Dattorro<T> where T: Float + FloatConst {
+  /// Creates a new `Dattorro` reverb, with the parameters of the paper: a
+  /// bandwidth of 0.9995, input diffusions of 0.75 and 0.625, a decay of 0.5,
+  /// a decay diffusion of 0.7, a damping of 0.0005, and an excursion of 16
+  /// samples at 29761 Hz, here at 1 Hz.
+  ///
+  /// The pre-delay can be up to one second.
+  pub fn new(sample_rate: T) -> Self {
+    let ratio = num::cast::<T, f64>(sample_rate).unwrap() / RATE;
+    let scale = |length: usize| ((length as f64 * ratio).round() as usize).max(1);
+    let max_pre_delay: usize = num::cast(sample_rate.ceil()).unwrap();
+
+    let mut reverb = Dattorro {
+      sample_rate: sample_rate,
+      pre_delay: LinearDelay::new(0f32, max_pre_delay),
+      bandwidth: OnePole::new(),
+      diffusers: DIFFUSERS.iter().map(|length| {
+        Allpass::new(num::cast(scale(*length)).unwrap(), scale(*length), T::zero())
+      }).collect(),
+      left: Half::new(&LEFT, &scale),
+      right: Half::new(&RIGHT, &scale),
+      decay: T::zero(),
+      excursion: T::zero(),
+      rate: T::zero(),
+      phase: T::zero(),
+      ratio: ratio,
+      output: T::zero()
+    };
+
+    reverb.set_bandwidth(num::cast(0.9995f64).unwrap());
+    reverb.set_input_diffusion(num::cast(0.75f64).unwrap(), num::cast(0.625f64).unwrap());
+    reverb.set_decay(num::cast(0.5f64).unwrap());
+    reverb.set_decay_diffusion(num::cast(0.7f64).unwrap());
+    reverb.set_damping(num::cast(0.0005f64).unwrap());
+    reverb.set_modulation(num::cast(16f64).unwrap(), T::one());
+    reverb
+  }
+
+  /// Sets the pre-delay, in seconds.
+  pub fn set_pre_delay(&mut self, pre_delay: T) {
+    let samples: f32 = num::cast(pre_delay * self.sample_rate).unwrap();
+    self.pre_delay.set_delay(samples);
+  }
+
+  /// Sets the bandwidth of the input, from 0 to 1, where 1 lets all
+  /// frequencies through.
+  pub fn set_bandwidth(&mut self, bandwidth: T) {
+    self.bandwidth.set_coefficients(bandwidth, bandwidth - T::one());
+  }
+
+  /// Sets the gains of the first and second pairs of input diffusers, from 0
+  /// to below 1.
+  pub fn set_input_diffusion(&mut self, first: T, second: T) {
+    for (i, diffuser) in self.diffusers.iter_mut().enumerate() {
+      // The paper's diffusers subtract their feedback
+      diffuser.set_gain(-(if i < 2 { first } else { second }));
+    }
+  }
+
+  /// Sets the gain of the tank, from 0 to below 1, which sets the decay
+  /// time.
+  ///
+  /// This also sets the second decay diffusion to `decay + 0.15`, between
+  /// 0.25 and 0.5, as recommended in the paper.
+  pub fn set_decay(&mut self, decay: T) {
+    self.decay = decay;
+    let diffusion = (decay + num::cast(0.15f64).unwrap())
+      .max(num::cast(0.25f64).unwrap())
+      .min(num::cast(0.5f64).unwrap());
+    self.left.allpass.set_gain(-diffusion);
+    self.right.allpass.set_gain(-diffusion);
+  }
+
+  /// Sets the gain of the modulated all-passes, from 0 to below 1.
+  pub fn set_decay_diffusion(&mut self, diffusion: T) {
+    // Unlike the other all-passes, these add their feedback
+    self.left.modulated.set_gain(diffusion);
+    self.right.modulated.set_gain(diffusion);
+  }
+
+  /// Sets the damping of the high frequencies in the tank, from 0 to 1.
+  pub fn set_damping(&mut self, damping: T) {
+    for half in [&mut self.left, &mut self.right].iter_mut() {
+      half.damping.set_coefficients(T::one() - damping, -damping);
+    }
+  }
+
+  /// Sets the modulation of the tank, with an `excursion` in samples at the
+  /// paper's sample rate of 29761 Hz, up to 32, and a `rate` in hertz.
+  pub fn set_modulation(&mut self, excursion: T, rate: T) {
+    let ratio: T = num::cast(self.ratio).unwrap();
+    let max: T = num::cast(MAX_EXCURSION).unwrap();
+    self.excursion = excursion.max(T::zero()).min(max) * ratio;
+    self.rate = rate / self.sample_rate;
+  }
+
+  /// Returns the sum of `taps`, scaled to the sample rate.
+  fn taps(&self, taps: &[(usize, usize, usize, f64)]) -> T {
+    taps.iter().fold(T::zero(), |sum, &(half, line, tap, sign)| {
+      let tap = (tap as f64 * self.ratio).round() as usize;
+      let half = if half == 0 { &self.left } else { &self.right };
+      sum + half.tap(line, tap) * num::cast(sign).unwrap()
+    })
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right channels of the reverb.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    let x = self.bandwidth.process(self.pre_delay.process(sample));
+    let x = self.diffusers.iter_mut().fold(x, |x, diffuser| diffuser.process(x));
+
+    let phase = T::two() * T::pi() * self.phase;
+    let left_excursion = self.excursion * phase.sin();
+    let right_excursion = self.excursion * phase.cos();
+    self.phase = self.phase + self.rate;
+    if self.phase >= T::one() {
+      self.phase = self.phase - T::one();
+    }
+
+    // Each half is fed by the other, before either is processed
+    let left_in = x + self.right.second.last_out() * self.decay;
+    let right_in = x + self.left.second.last_out() * self.decay;
+    self.left.process(left_in, left_excursion, self.decay);
+    self.right.process(right_in, right_excursion, self.decay);
+
+    let gain: T = num::cast(0.6f64).unwrap();
+    let left = self.taps(&LEFT_TAPS) * gain;
+    let right = self.taps(&RIGHT_TAPS) * gain;
+    self.output = (left + right) / T::two();
+    (left, right)
+  }
+}
+
+impl<T> Processor<T> for Dattorro<T> where T: Float + FloatConst {
+  /// Processes and stores input sample into memory, and outputs the average
+  /// of the left and right channels.
+  fn process(&mut self, sample: T) -> T {
+    self.process_stereo(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    self.pre_delay.clear();
+    self.bandwidth.clear();
+    for diffuser in self.diffusers.iter_mut() {
+      diffuser.clear();
+    }
+    self.left.clear();
+    self.right.clear();
+    self.phase = T::zero();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+  use reverb::tests::{energy, impulse_response};
+
+  #[test]
+  fn lengths() {
+    let reverb = Dattorro::<f64>::new(2f64 * RATE);
+    assert_eq!(reverb.diffusers[0].tap_out(0), 0f64);
+    assert_eq!(reverb.left.first.get_delay(), 8906);
+    assert_eq!(reverb.right.second.get_delay(), 6326);
+    assert_eq!(reverb.right.length, 1816f64);
+  }
+
+  #[test]
+  fn decay() {
+    // A higher decay gain rings longer
+    let mut short = Dattorro::new(44_100f64);
+    short.set_decay(0.3f64);
+    let mut long = Dattorro::new(44_100f64);
+    long.set_decay(0.8f64);
+    let short = impulse_response(44_100, |x| short.process_stereo(x));
+    let long = impulse_response(44_100, |x| long.process_stereo(x));
+    assert!(energy(&short[22_050..]) < energy(&long[22_050..]));
+    assert!(energy(&long[40_000..]) > 0f64);
+    assert!(long.iter().all(|&(left, right)| left.abs() < 1f64 && right.abs() < 1f64));
+  }
+
+  #[test]
+  fn pre_delay() {
+    let mut reverb = Dattorro::new(44_100f64);
+    let response = impulse_response(10_000, |x| reverb.process_stereo(x));
+    let onset = response.iter().position(|&(left, right)| left != 0f64 || right != 0f64);
+
+    reverb.clear();
+    reverb.set_pre_delay(0.01f64);
+    let delayed = impulse_response(10_000, |x| reverb.process_stereo(x));
+    let delayed_onset = delayed.iter().position(|&(left, right)| left != 0f64 || right != 0f64);
+    assert_eq!(delayed_onset.unwrap(), onset.unwrap() + 441);
+  }
+
+  #[test]
+  fn stereo() {
+    let mut reverb = Dattorro::new(44_100f64);
+    let mut products = 0f64;
+    let response = impulse_response(44_100, |x| reverb.process_stereo(x));
+    for &(left, right) in response.iter() {
+      products += left * right;
+    }
+    let left = response.iter().fold(0f64, |energy, &(left, _)| energy + left * left);
+    let right = response.iter().fold(0f64, |energy, &(_, right)| energy + right * right);
+    assert!((products / (left * right).sqrt()).abs() < 0.3f64);
+
+    reverb.clear();
+    assert_eq!(reverb.last_out(), 0f64);
+    assert_eq!(energy(&(0..10_000).map(|_| reverb.process_stereo(0f64)).collect::<Vec<_>>()), 0f64);
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut reverb = Dattorro::new(48_000f32);
+    reverb.set_damping(0.5f32);
+    let mut samples = vec![0f32; 48_000];
+    samples[0] = 1f32;
+    reverb.process_block(&mut samples);
+    assert!(samples.iter().all(|x| x.is_finite() && x.abs() < 1f32));
+    assert!(samples.iter().any(|x| *x != 0f32));
+  }
+}
@@ -0,0 +1,461 @@
+use num;
+use num::traits::Float;
+
+use delay::LinearDelay;
+use filter::Biquad2;
+use reverb::allpass::Allpass;
+use traits::{FloatConst, ModulatedDelayLine, Processor};
+
+/* Notes on the feedback delay network
+  - The input is diffused by four all-pass filters in series, and then fed
+    to every delay line, with signs from a row of a Hadamard matrix
+  - The outputs of the lines are filtered by their absorption filters, mixed
+    by the feedback matrix, and fed back into the lines; both matrices are
+    orthogonal, so the mix is lossless and the decay is set by the filters
+    alone
+  - An absorption filter has the gain of `RT60` seconds of decay over the
+    length of its line, which is `10^(-3 * length / (sample_rate * RT60))`,
+    so that every line decays at the same rate, as in Jot's design
+  - The low and high bands are first-order shelves around the mid gain, at
+    the crossover frequencies, cascaded into a single biquad
+  - The line lengths are primes, spread geometrically from `SHORTEST` to
+    `LONGEST` seconds, so their modes do not line up
+  - Each line length is modulated by a sine, with phases spread across the
+    lines, which moves the modes of the network and avoids a metallic ring
+  - The lines are read as they are written, a sample early, since their
+    outputs are needed before their inputs
+  - The left and right outputs sum the lines with signs from two other rows
+    of a Hadamard matrix, which are orthogonal, so the channels are
+    decorrelated
+*/
+
+/// The length of the shortest delay line, in seconds.
+const SHORTEST: f64 = 0.029;
+
+/// The length of the longest delay line, in seconds.
+const LONGEST: f64 = 0.083;
+
+/// The longest modulation depth, in seconds.
+const MAX_DEPTH: f64 = 0.005;
+
+/// The lengths of the input all-pass filters, in seconds, and their share of
+/// the diffusion.
+const DIFFUSERS: [(f64, f64); 4] = [
+  (0.004_771, 1.0),
+  (0.003_595, 1.0),
+  (0.012_735, 0.833),
+  (0.009_307, 0.833)
+];
+
+/// The feedback matrix of an `Fdn`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedbackMatrix {
+  /// `I - 2/N * 1*1^T`, which costs `2N` operations, and feeds each line
+  /// mostly back into itself
+  Householder,
+  /// The normalized Hadamard matrix, which costs `N*log(N)` operations, and
+  /// mixes every line equally into every other
+  Hadamard
+}
+
+impl FeedbackMatrix {
+  /// Multiplies `lines` by the matrix, in place.
+  fn apply<T: Float>(self, lines: &mut [T]) {
+    let length: T = num::cast(lines.len()).unwrap();
+    match self {
+      FeedbackMatrix::Householder => {
+        let sum = lines.iter().fold(T::zero(), |sum, x| sum + *x);
+        let reflection = (sum + sum) / length;
+        for x in lines.iter_mut() {
+          *x = *x - reflection;
+        }
+      },
+      FeedbackMatrix::Hadamard => {
+        // Fast Walsh-Hadamard transform
+        let mut half = 1;
+        while half < lines.len() {
+          for block in lines.chunks_mut(2 * half) {
+            let (first, second) = block.split_at_mut(half);
+            for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+              let sum = *a + *b;
+              *b = *a - *b;
+              *a = sum;
+            }
+          }
+          half *= 2;
+        }
+        let scale = length.sqrt().recip();
+        for x in lines.iter_mut() {
+          *x = *x * scale;
+        }
+      }
+    }
+  }
+}
+
+/// Returns the sign of row `row` of a Hadamard matrix at `column`.
+fn hadamard_sign<T: Float>(row: usize, column: usize) -> T {
+  match (row & column).count_ones() % 2 {
+    0 => T::one(),
+    _ => -T::one()
+  }
+}
+
+/// Returns the smallest prime number not less than `n`.
+fn next_prime(n: usize) -> usize {
+  let is_prime = |n: usize| n > 1 && (2..).take_while(|d| d * d <= n).all(|d| n / d * d != n);
+  (n..).find(|n| is_prime(*n)).unwrap()
+}
+
+/// Returns the gain of `decay` seconds of decay, to -60 dB, over a line of
+/// `length` samples.
+fn gain<T: Float>(length: T, decay: T, sample_rate: T) -> T {
+  let ten: T = num::cast(10).unwrap();
+  let exponent: T = num::cast(-3).unwrap();
+  ten.powf(exponent * length / (sample_rate * decay))
+}
+
+/// Returns the coefficients of a first-order shelf, `(b0, b1, a1)`, with a
+/// gain of `low` below `frequency` and `high` above it.
+fn shelf<T: Float + FloatConst>(low: T, high: T, frequency: T, sample_rate: T) -> (T, T, T) {
+  let k = (T::pi() * frequency / sample_rate).tan();
+  let norm = (T::one() + k).recip();
+  ((high + k * low) * norm, (k * low - high) * norm, (k - T::one()) * norm)
+}
+
+/// A feedback delay network reverb.
+///
+/// The output is only the reverberated signal.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::reverb::{Fdn, FeedbackMatrix};
+///
+/// let mut reverb = Fdn::new(8, FeedbackMatrix::Hadamard, 48_000f32);
+///
+/// // A hall, with less decay in the low frequencies
+/// reverb.set_decay(3.2f32, 2.5f32, 1.2f32);
+/// reverb.set_crossovers(200f32, 5_000f32);
+///
+/// let (left, right) = reverb.process_stereo(1f32);
+/// let tail = reverb.process(0f32);
+/// # assert_eq!((left, right, tail), (0f32, 0f32, 0f32));
+/// ```
+pub struct Fdn<T> {
+  sample_rate: T,
+  matrix: FeedbackMatrix,
+  diffusers: Vec<Allpass<T>>,
+  lines: Vec<LinearDelay<T>>,
+  /// The unmodulated length of each line, in samples
+  lengths: Vec<T>,
+  filters: Vec<Biquad2<T>>,
+  /// The outputs of the lines for the next sample
+  reads: Vec<T>,
+  /// The outputs of the lines, and then the feedback
+  feedback: Vec<T>,
+  /// The decay time, in seconds, of the low, mid, and high bands
+  decay: (T, T, T),
+  crossovers: (T, T),
+  diffusion: T,
+  depth: T,
+  rate: T,
+  phase: T,
+  output: T
+}
+
+impl<T> Fdn<T> where T: Float + FloatConst {
+  /// Creates a new `Fdn` with `lines` delay lines, which is 4, 8, or 16.
+  ///
+  /// It decays in two seconds, has crossovers at 250 Hz and 4 kHz, a
+  /// diffusion of 0.7, and a modulation of 0.5 ms at 0.5 Hz.
+  pub fn new(lines: usize, matrix: FeedbackMatrix, sample_rate: T) -> Self {
+    debug_assert!(lines == 4 || lines == 8 || lines == 16);
+    let rate: f64 = num::cast(sample_rate).unwrap();
+
+    let ratio = (LONGEST / SHORTEST).powf(1f64 / (lines - 1) as f64);
+    let mut lengths: Vec<usize> = Vec::with_capacity(lines);
+    for i in 0..lines {
+      let length = next_prime((SHORTEST * ratio.powi(i as i32) * rate).round() as usize);
+      let previous = lengths.last().cloned().unwrap_or(0);
+      lengths.push(if length > previous { length } else { next_prime(previous + 1) });
+    }
+    let max_depth = (MAX_DEPTH * rate).ceil() as usize + 1;
+
+    let diffusers = DIFFUSERS.iter().map(|&(length, _)| {
+      let length = (length * rate).round().max(1f64);
+      Allpass::new(num::cast(length).unwrap(), length as usize, T::zero())
+    }).collect();
+
+    let mut reverb = Fdn {
+      sample_rate: sample_rate,
+      matrix: matrix,
+      diffusers: diffusers,
+      lines: lengths.iter().map(|length| {
+        LinearDelay::new(*length as f32, length + max_depth)
+      }).collect(),
+      lengths: lengths.iter().map(|length| num::cast(*length).unwrap()).collect(),
+      filters: (0..lines).map(|_| Biquad2::new()).collect(),
+      reads: vec![T::zero(); lines],
+      feedback: vec![T::zero(); lines],
+      decay: (T::zero(), T::zero(), T::zero()),
+      crossovers: (num::cast(250).unwrap(), num::cast(4_000).unwrap()),
+      diffusion: T::zero(),
+      depth: T::zero(),
+      rate: T::zero(),
+      phase: T::zero(),
+      output: T::zero()
+    };
+
+    let two: T = num::cast(2).unwrap();
+    reverb.set_decay(two, two, two);
+    reverb.set_diffusion(num::cast(0.7f64).unwrap());
+    reverb.set_modulation(num::cast(0.000_5f64).unwrap(), num::cast(0.5f64).unwrap());
+    reverb
+  }
+
+  /// Returns the number of delay lines.
+  pub fn lines(&self) -> usize {
+    self.lines.len()
+  }
+
+  /// Returns the feedback matrix.
+  pub fn matrix(&self) -> FeedbackMatrix {
+    self.matrix
+  }
+
+  /// Sets the feedback matrix.
+  pub fn set_matrix(&mut self, matrix: FeedbackMatrix) {
+    self.matrix = matrix;
+  }
+
+  /// Sets the time, in seconds, for the low, mid, and high bands to decay by
+  /// 60 dB.
+  pub fn set_decay(&mut self, low: T, mid: T, high: T) {
+    self.decay = (low, mid, high);
+    self.update_filters();
+  }
+
+  /// Sets the frequencies, in hertz, between the low and mid bands, and the
+  /// mid and high bands.
+  ///
+  /// The high crossover is clipped below the Nyquist frequency.
+  pub fn set_crossovers(&mut self, low: T, high: T) {
+    let nyquist = self.sample_rate / T::two();
+    let limit = nyquist * num::cast(0.95f64).unwrap();
+    self.crossovers = (low.min(limit), high.min(limit));
+    self.update_filters();
+  }
+
+  /// Sets the gain of the input all-pass filters, from 0, which bypasses
+  /// them, to below 1.
+  pub fn set_diffusion(&mut self, diffusion: T) {
+    self.diffusion = diffusion;
+    for (allpass, &(_, share)) in self.diffusers.iter_mut().zip(DIFFUSERS.iter()) {
+      allpass.set_gain(diffusion * num::cast(share).unwrap());
+    }
+  }
+
+  /// Sets the modulation of the line lengths, with a `depth` in seconds, up
+  /// to 5 ms, and a `rate` in hertz.
+  pub fn set_modulation(&mut self, depth: T, rate: T) {
+    let max_depth: T = num::cast(MAX_DEPTH).unwrap();
+    self.depth = depth.max(T::zero()).min(max_depth) * self.sample_rate;
+    self.rate = rate;
+  }
+
+  fn update_filters(&mut self) {
+    let (low, mid, high) = self.decay;
+    for (length, filter) in self.lengths.iter().zip(self.filters.iter_mut()) {
+      let g_low = gain(*length, low, self.sample_rate);
+      let g_mid = gain(*length, mid, self.sample_rate);
+      let g_high = gain(*length, high, self.sample_rate);
+
+      let (b0, b1, a1) = shelf(g_low / g_mid, T::one(), self.crossovers.0, self.sample_rate);
+      let (c0, c1, d1) = shelf(T::one(), g_high / g_mid, self.crossovers.1, self.sample_rate);
+      filter.set_coefficients(g_mid * b0 * c0,
+                              g_mid * (b0 * c1 + b1 * c0),
+                              g_mid * b1 * c1,
+                              a1 + d1,
+                              a1 * d1);
+    }
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right channels of the reverb.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    let diffused = self.diffusers.iter_mut().fold(sample, |x, allpass| allpass.process(x));
+
+    let mut left = T::zero();
+    let mut right = T::zero();
+    for (i, ((read, filter), output)) in self.reads.iter()
+      .zip(self.filters.iter_mut())
+      .zip(self.feedback.iter_mut())
+      .enumerate()
+    {
+      *output = filter.process(*read);
+      left = left + *output * hadamard_sign(1, i);
+      right = right + *output * hadamard_sign(2, i);
+    }
+
+    self.phase = self.phase + self.rate / self.sample_rate;
+    if self.phase >= T::one() {
+      self.phase = self.phase - T::one();
+    }
+
+    // Feeds back into the lines, and reads their outputs for the next
+    // sample, with modulated lengths
+    self.matrix.apply(&mut self.feedback);
+    let count: T = num::cast(self.lines.len()).unwrap();
+    for (i, (line, read)) in self.lines.iter_mut().zip(self.reads.iter_mut()).enumerate() {
+      let offset: T = num::cast(i).unwrap();
+      let phase = T::two() * T::pi() * (self.phase + offset / count);
+      let length = self.lengths[i] + self.depth * phase.sin();
+      *read = line.process_modulated(self.feedback[i] + diffused * hadamard_sign(3, i),
+                                     length - T::one());
+    }
+
+    let scale = count.sqrt().recip();
+    left = left * scale;
+    right = right * scale;
+    self.output = (left + right) / T::two();
+    (left, right)
+  }
+}
+
+impl<T> Processor<T> for Fdn<T> where T: Float + FloatConst {
+  /// Processes and stores input sample into memory, and outputs the average
+  /// of the left and right channels.
+  fn process(&mut self, sample: T) -> T {
+    self.process_stereo(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for allpass in self.diffusers.iter_mut() {
+      allpass.clear();
+    }
+    for line in self.lines.iter_mut() {
+      line.clear();
+    }
+    for read in self.reads.iter_mut() {
+      *read = T::zero();
+    }
+    for filter in self.filters.iter_mut() {
+      filter.clear();
+    }
+    self.phase = T::zero();
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::{FrequencyResponse, Processor};
+
+  /// Returns the energy of `signal` from `start` to `end` seconds.
+  fn energy(signal: &[f64], sample_rate: f64, start: f64, end: f64) -> f64 {
+    let (start, end) = ((start * sample_rate) as usize, (end * sample_rate) as usize);
+    signal[start..end].iter().fold(0f64, |energy, x| energy + x * x)
+  }
+
+  #[test]
+  fn matrices() {
+    // Both matrices are orthogonal, so they keep the energy of the lines
+    for matrix in [FeedbackMatrix::Householder, FeedbackMatrix::Hadamard].iter() {
+      for size in [4, 8, 16].iter() {
+        let mut lines: Vec<f64> = (0..*size).map(|i| (i as f64 * 1.3f64).sin()).collect();
+        let before = lines.iter().fold(0f64, |energy, x| energy + x * x);
+        matrix.apply(&mut lines);
+        let after = lines.iter().fold(0f64, |energy, x| energy + x * x);
+        assert!((before - after).abs() < 1e-12);
+      }
+    }
+  }
+
+  #[test]
+  fn lengths() {
+    let reverb = Fdn::new(16, FeedbackMatrix::Householder, 44_100f64);
+    assert_eq!(reverb.lines(), 16);
+    for pair in reverb.lengths.windows(2) {
+      assert!(pair[0] < pair[1]);
+    }
+    assert_eq!(next_prime(1_000), 1_009);
+  }
+
+  #[test]
+  fn absorption() {
+    // Each filter has the gain of its decay over its line
+    let mut reverb = Fdn::new(4, FeedbackMatrix::Hadamard, 48_000f64);
+    reverb.set_decay(4f64, 2f64, 0.5f64);
+    reverb.set_crossovers(100f64, 10_000f64);
+    for (length, filter) in reverb.lengths.iter().zip(reverb.filters.iter()) {
+      let dc = filter.magnitude(1f64, 48_000f64);
+      let nyquist = filter.magnitude(23_999f64, 48_000f64);
+      let mid = filter.magnitude(1_000f64, 48_000f64);
+      assert!((dc / gain(*length, 4f64, 48_000f64) - 1f64).abs() < 1e-3);
+      assert!((nyquist / gain(*length, 0.5f64, 48_000f64) - 1f64).abs() < 1e-2);
+      assert!((mid / gain(*length, 2f64, 48_000f64) - 1f64).abs() < 5e-2);
+    }
+  }
+
+  #[test]
+  fn decay() {
+    // The energy decays by 60 dB in the decay time
+    let sample_rate = 8_000f64;
+    for matrix in [FeedbackMatrix::Householder, FeedbackMatrix::Hadamard].iter() {
+      for lines in [4, 8, 16].iter() {
+        let mut reverb = Fdn::new(*lines, *matrix, sample_rate);
+        reverb.set_decay(0.5f64, 0.5f64, 0.5f64);
+        reverb.set_modulation(0f64, 0f64);
+        let output: Vec<f64> = (0..4_000).map(|n| {
+          reverb.process(if n == 0 { 1f64 } else { 0f64 })
+        }).collect();
+
+        let first = energy(&output, sample_rate, 0.1f64, 0.2f64);
+        let second = energy(&output, sample_rate, 0.3f64, 0.4f64);
+        let decay = 10f64 * (first / second).log10();
+        assert!((decay - 24f64).abs() < 3f64);
+      }
+    }
+  }
+
+  #[test]
+  fn stereo() {
+    let mut reverb = Fdn::new(8, FeedbackMatrix::Householder, 44_100f64);
+    let mut products = 0f64;
+    let mut left_energy = 0f64;
+    let mut right_energy = 0f64;
+    for n in 0..20_000 {
+      let (left, right) = reverb.process_stereo(if n == 0 { 1f64 } else { 0f64 });
+      products += left * right;
+      left_energy += left * left;
+      right_energy += right * right;
+    }
+    let correlation = products / (left_energy * right_energy).sqrt();
+    assert!(left_energy > 0f64 && right_energy > 0f64);
+    assert!(correlation.abs() < 0.3f64);
+
+    reverb.clear();
+    assert_eq!(reverb.last_out(), 0f64);
+    for _ in 0..10_000 {
+      assert_eq!(reverb.process(0f64), 0f64);
+    }
+  }
+
+  #[test]
+  fn process_f32() {
+    let mut reverb = Fdn::new(4, FeedbackMatrix::Hadamard, 48_000f32);
+    let mut samples = vec![0f32; 48_000];
+    samples[0] = 1f32;
+    reverb.process_block(&mut samples);
+    assert!(samples.iter().all(|x| x.is_finite() && x.abs() < 1f32));
+    assert!(samples.iter().any(|x| *x != 0f32));
+  }
+}
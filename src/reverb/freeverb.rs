@@ -0,0 +1,359 @@
+use num;
+use num::traits::Float;
+
+use delay::Delay;
+use filter::OnePole;
+use traits::Processor;
+
+/* Notes on Freeverb
+  - This follows Jezar's public domain Freeverb: eight parallel comb filters,
+    with a one-pole low-pass filter in their feedback, into four all-pass
+    filters in series, for each channel
+  - The right channel uses lines `STEREO_SPREAD` samples longer than the
+    left channel, which decorrelates them
+  - The line lengths are tuned for 44.1 kHz, and scaled to the sample rate
+  - The parameters are in the same units, from 0 to 1, and are scaled by the
+    same constants, so that presets from other Freeverb ports sound the same
+  - Freeverb mixes the left and right inputs, so a mono sample is fed as both,
+    and it is the only reverb in this module to also output the dry signal
+*/
+
+/// The lengths of the comb filters, in samples at 44.1 kHz.
+const COMBS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+
+/// The lengths of the all-pass filters, in samples at 44.1 kHz.
+const ALLPASSES: [usize; 4] = [556, 441, 341, 225];
+
+/// The extra length of the right channel lines, in samples at 44.1 kHz.
+const STEREO_SPREAD: usize = 23;
+
+const FIXED_GAIN: f64 = 0.015;
+const SCALE_WET: f64 = 3.0;
+const SCALE_DRY: f64 = 2.0;
+const SCALE_DAMPING: f64 = 0.4;
+const SCALE_ROOM: f64 = 0.28;
+const OFFSET_ROOM: f64 = 0.7;
+const ALLPASS_FEEDBACK: f64 = 0.5;
+
+/// A lowpass-feedback comb filter.
+struct Comb<T: Float> {
+  delay: Delay<T>,
+  damping: OnePole<T>,
+  feedback: T
+}
+
+impl<T> Comb<T> where T: Float {
+  fn new(length: usize) -> Self {
+    Comb {
+      delay: Delay::new(length, length),
+      damping: OnePole::new(),
+      feedback: T::zero()
+    }
+  }
+
+  fn process(&mut self, sample: T) -> T {
+    let output = self.delay.next_out();
+    let filtered = self.damping.process(output);
+    self.delay.process(sample + filtered * self.feedback);
+    output
+  }
+}
+
+/// Freeverb's all-pass filter, which is only all-pass when its feedback is
+/// the golden ratio conjugate, but is kept as is for its sound.
+///
+/// `v[n] = x[n] + g*v[n-M]; y[n] = v[n-M] - x[n]`
+struct Diffuser<T> {
+  delay: Delay<T>
+}
+
+impl<T> Diffuser<T> where T: Float {
+  fn new(length: usize) -> Self {
+    Diffuser { delay: Delay::new(length, length) }
+  }
+
+  fn process(&mut self, sample: T) -> T {
+    let delayed = self.delay.next_out();
+    let feedback: T = num::cast(ALLPASS_FEEDBACK).unwrap();
+    self.delay.process(sample + delayed * feedback);
+    delayed - sample
+  }
+}
+
+/// Jezar's Freeverb, a Schroeder-Moorer reverb with parallel comb filters and
+/// series all-pass filters.
+///
+/// The output is the reverberated signal, mixed with the dry signal.
+///
+/// # Examples
+///
+/// ```
+/// use rasp::traits::Processor;
+/// use rasp::reverb::Freeverb;
+///
+/// let mut reverb = Freeverb::new(44_100f32);
+/// reverb.set_room_size(0.8f32);
+/// reverb.set_damping(0.2f32);
+/// reverb.set_width(0.5f32);
+/// reverb.set_wet(0.3f32);
+/// reverb.set_dry(0.5f32);
+///
+/// // Only the dry signal comes through at first
+/// let (left, right) = reverb.process_stereo(0.5f32);
+/// assert_eq!((left, right), (0.5f32, 0.5f32));
+/// let tail = reverb.process(0f32);
+/// # assert_eq!(tail, 0f32);
+/// ```
+pub struct Freeverb<T: Float> {
+  left_combs: Vec<Comb<T>>,
+  right_combs: Vec<Comb<T>>,
+  left_diffusers: Vec<Diffuser<T>>,
+  right_diffusers: Vec<Diffuser<T>>,
+  room_size: T,
+  damping: T,
+  wet: T,
+  dry: T,
+  width: T,
+  frozen: bool,
+  /// The input gain, which is zero when frozen
+  gain: T,
+  /// The gains of each channel into itself, and into the other channel
+  wet1: T,
+  wet2: T,
+  output: T
+}
+
+impl<T> Freeverb<T> where T: Float {
+  /// Creates a new `Freeverb`, with Freeverb's initial parameters: a room
+  /// size and damping of 0.5, a width of 1, a wet level of 1/3, and no dry
+  /// signal.
+  pub fn new(sample_rate: T) -> Self {
+    let ratio: f64 = num::cast::<T, f64>(sample_rate).unwrap() / 44_100f64;
+    let scale = |length: usize| ((length as f64 * ratio).round() as usize).max(1);
+
+    let mut reverb = Freeverb {
+      left_combs: COMBS.iter().map(|length| Comb::new(scale(*length))).collect(),
+      right_combs: COMBS.iter().map(|length| Comb::new(scale(length + STEREO_SPREAD))).collect(),
+      left_diffusers: ALLPASSES.iter().map(|length| Diffuser::new(scale(*length))).collect(),
+      right_diffusers: ALLPASSES.iter().map(|length| {
+        Diffuser::new(scale(length + STEREO_SPREAD))
+      }).collect(),
+      room_size: T::zero(),
+      damping: T::zero(),
+      wet: T::zero(),
+      dry: T::zero(),
+      width: T::one(),
+      frozen: false,
+      gain: T::zero(),
+      wet1: T::zero(),
+      wet2: T::zero(),
+      output: T::zero()
+    };
+
+    let half: T = num::cast(0.5f64).unwrap();
+    reverb.set_room_size(half);
+    reverb.set_damping(half);
+    reverb.set_wet(num::cast(1f64 / SCALE_WET).unwrap());
+    reverb.set_dry(T::zero());
+    reverb
+  }
+
+  /// Sets the room size, from 0 to 1, which sets the feedback of the combs.
+  pub fn set_room_size(&mut self, room_size: T) {
+    self.room_size = room_size;
+    self.update();
+  }
+
+  /// Returns the room size.
+  pub fn room_size(&self) -> T {
+    self.room_size
+  }
+
+  /// Sets the damping of the high frequencies, from 0 to 1.
+  pub fn set_damping(&mut self, damping: T) {
+    self.damping = damping;
+    self.update();
+  }
+
+  /// Returns the damping.
+  pub fn damping(&self) -> T {
+    self.damping
+  }
+
+  /// Sets the level of the reverberated signal, from 0 to 1.
+  pub fn set_wet(&mut self, wet: T) {
+    self.wet = wet;
+    self.update();
+  }
+
+  /// Returns the level of the reverberated signal.
+  pub fn wet(&self) -> T {
+    self.wet
+  }
+
+  /// Sets the level of the dry signal, from 0 to 1.
+  pub fn set_dry(&mut self, dry: T) {
+    self.dry = dry;
+  }
+
+  /// Returns the level of the dry signal.
+  pub fn dry(&self) -> T {
+    self.dry
+  }
+
+  /// Sets the stereo width, from 0, which is mono, to 1.
+  pub fn set_width(&mut self, width: T) {
+    self.width = width;
+    self.update();
+  }
+
+  /// Returns the stereo width.
+  pub fn width(&self) -> T {
+    self.width
+  }
+
+  /// Freezes the reverb, which then loops its contents forever, and ignores
+  /// its input.
+  pub fn set_freeze(&mut self, frozen: bool) {
+    self.frozen = frozen;
+    self.update();
+  }
+
+  /// Returns `true` if the reverb is frozen.
+  pub fn is_frozen(&self) -> bool {
+    self.frozen
+  }
+
+  fn update(&mut self) {
+    let two = T::one() + T::one();
+    let wet = self.wet * num::cast(SCALE_WET).unwrap();
+    self.wet1 = wet * (self.width / two + T::one() / two);
+    self.wet2 = wet * ((T::one() - self.width) / two);
+
+    let (feedback, damping) = if self.frozen {
+      self.gain = T::zero();
+      (T::one(), T::zero())
+    } else {
+      self.gain = num::cast(FIXED_GAIN).unwrap();
+      (self.room_size * num::cast(SCALE_ROOM).unwrap() + num::cast(OFFSET_ROOM).unwrap(),
+       self.damping * num::cast(SCALE_DAMPING).unwrap())
+    };
+
+    for comb in self.left_combs.iter_mut().chain(self.right_combs.iter_mut()) {
+      comb.feedback = feedback;
+      comb.damping.set_coefficients(T::one() - damping, -damping);
+    }
+  }
+
+  /// Processes and stores input sample into memory, and outputs the left and
+  /// right channels.
+  pub fn process_stereo(&mut self, sample: T) -> (T, T) {
+    let input = (sample + sample) * self.gain;
+
+    let mut left = self.left_combs.iter_mut().fold(T::zero(), |sum, comb| sum + comb.process(input));
+    let mut right = self.right_combs.iter_mut().fold(T::zero(), |sum, comb| sum + comb.process(input));
+    left = self.left_diffusers.iter_mut().fold(left, |x, diffuser| diffuser.process(x));
+    right = self.right_diffusers.iter_mut().fold(right, |x, diffuser| diffuser.process(x));
+
+    let dry = sample * self.dry * num::cast(SCALE_DRY).unwrap();
+    let left_out = left * self.wet1 + right * self.wet2 + dry;
+    let right_out = right * self.wet1 + left * self.wet2 + dry;
+    self.output = (left_out + right_out) / (T::one() + T::one());
+    (left_out, right_out)
+  }
+}
+
+impl<T> Processor<T> for Freeverb<T> where T: Float {
+  /// Processes and stores input sample into memory, and outputs the average
+  /// of the left and right channels.
+  fn process(&mut self, sample: T) -> T {
+    self.process_stereo(sample);
+    self.output
+  }
+
+  fn clear(&mut self) {
+    for comb in self.left_combs.iter_mut().chain(self.right_combs.iter_mut()) {
+      comb.delay.clear();
+      comb.damping.clear();
+    }
+    for diffuser in self.left_diffusers.iter_mut().chain(self.right_diffusers.iter_mut()) {
+      diffuser.delay.clear();
+    }
+    self.output = T::zero();
+  }
+
+  fn last_out(&self) -> T {
+    self.output
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ::traits::Processor;
+  use reverb::tests::{energy, impulse_response};
+
+  #[test]
+  fn lengths() {
+    let reverb = Freeverb::<f64>::new(88_200f64);
+    assert_eq!(reverb.left_combs[0].delay.get_delay(), 2232);
+    assert_eq!(reverb.right_combs[7].delay.get_delay(), 3280);
+    assert_eq!(reverb.right_diffusers[3].delay.get_delay(), 496);
+  }
+
+  #[test]
+  fn room_size() {
+    // A larger room decays more slowly
+    let mut small = Freeverb::new(44_100f64);
+    small.set_room_size(0.2f64);
+    let mut large = Freeverb::new(44_100f64);
+    large.set_room_size(0.9f64);
+    let small = impulse_response(44_100, |x| small.process_stereo(x));
+    let large = impulse_response(44_100, |x| large.process_stereo(x));
+    assert!(energy(&small[22_050..]) < energy(&large[22_050..]));
+    assert!(energy(&large[40_000..]) > 0f64);
+    assert!(large.iter().all(|&(left, right)| left.is_finite() && right.is_finite()));
+  }
+
+  #[test]
+  fn mix() {
+    let mut reverb = Freeverb::new(44_100f64);
+    reverb.set_wet(0f64);
+    reverb.set_dry(0.5f64);
+    for &(left, right) in impulse_response(5_000, |x| reverb.process_stereo(x)).iter().skip(1) {
+      assert_eq!((left, right), (0f64, 0f64));
+    }
+
+    // Without width, both channels are the same
+    let mut reverb = Freeverb::new(44_100f64);
+    reverb.set_width(0f64);
+    for &(left, right) in impulse_response(5_000, |x| reverb.process_stereo(x)).iter() {
+      assert!((left - right).abs() < 1e-15);
+    }
+
+    let mut reverb = Freeverb::new(44_100f64);
+    let response = impulse_response(5_000, |x| reverb.process_stereo(x));
+    assert!(response.iter().any(|&(left, right)| left != right));
+  }
+
+  #[test]
+  fn freeze() {
+    // A frozen reverb keeps its tail, and ignores its input
+    let mut reverb = Freeverb::new(44_100f64);
+    let mut other = Freeverb::new(44_100f64);
+    impulse_response(2_000, |x| reverb.process_stereo(x));
+    impulse_response(2_000, |x| other.process_stereo(x));
+    reverb.set_freeze(true);
+    other.set_freeze(true);
+    assert!(reverb.is_frozen());
+    let tail: Vec<(f64, f64)> = (0..40_000).map(|_| reverb.process_stereo(0f64)).collect();
+    for (n, frozen) in tail.iter().enumerate() {
+      assert_eq!(other.process_stereo(if n % 2 == 1 { 1f64 } else { -1f64 }), *frozen);
+    }
+    assert!(energy(&tail[30_000..]) > 0.5f64 * energy(&tail[..10_000]));
+
+    reverb.clear();
+    assert_eq!(reverb.last_out(), 0f64);
+    assert_eq!(energy(&impulse_response(5_000, |x| reverb.process_stereo(x))), 0f64);
+  }
+}
@@ -0,0 +1,46 @@
+//! Reverberators, which simulate the reflections of a room.
+//!
+//! A reverb is fed the dry signal, and outputs only the reverberated, or wet,
+//! signal, which is mixed with the dry signal by the caller, except for
+//! `Freeverb`, which has its own wet and dry levels.
+//!
+//! - `Fdn` is a feedback delay network, with 4, 8, or 16 delay lines mixed
+//!   by a `FeedbackMatrix`, and a decay time for each of three bands
+//! - `Freeverb` is Jezar's Freeverb, with its parameters and their scaling,
+//!   so that presets match other ports
+//! - `Dattorro` is Dattorro's plate reverb, with modulated all-passes in its
+//!   tank
+
+/* Notes on the reverbs
+  - The reverbs are stereo, with `process_stereo` returning the left and
+    right channels, and `Processor::process` returning their average, for
+    use in mono chains
+  - Decay times are in seconds, to -60 dB, and frequencies in hertz, so the
+    sample rate is passed on creation
+*/
+
+mod allpass;
+mod dattorro;
+mod fdn;
+mod freeverb;
+
+pub use self::dattorro::Dattorro  as Dattorro;
+pub use self::fdn::Fdn            as Fdn;
+pub use self::fdn::FeedbackMatrix as FeedbackMatrix;
+pub use self::freeverb::Freeverb  as Freeverb;
+
+#[cfg(test)]
+pub mod tests {
+  /// Returns the first `length` stereo samples of the response of `process`
+  /// to an impulse.
+  pub fn impulse_response<F>(length: usize, mut process: F) -> Vec<(f64, f64)>
+    where F: FnMut(f64) -> (f64, f64)
+  {
+    (0..length).map(|n| process(if n == 0 { 1f64 } else { 0f64 })).collect()
+  }
+
+  /// Returns the energy of both channels of `signal`.
+  pub fn energy(signal: &[(f64, f64)]) -> f64 {
+    signal.iter().fold(0f64, |energy, &(left, right)| energy + left * left + right * right)
+  }
+}
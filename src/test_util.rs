@@ -0,0 +1,15 @@
+//! Helpers shared by the tests of several modules.
+
+use std::f64::consts::PI;
+
+/// Returns the amplitude of `frequency`, relative to the sample rate, in
+/// `signal` from `start`.
+pub fn amplitude(signal: &[f64], start: usize, frequency: f64) -> f64 {
+  let (mut re, mut im) = (0f64, 0f64);
+  for (n, x) in signal.iter().enumerate().skip(start) {
+    let phase = 2f64 * PI * frequency * n as f64;
+    re += x * phase.cos();
+    im += x * phase.sin();
+  }
+  2f64 * (re * re + im * im).sqrt() / (signal.len() - start) as f64
+}
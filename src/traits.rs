@@ -1,7 +1,11 @@
+use num;
+use num::Complex;
 use num::traits::Float;
 
 use std;
 
+use util;
+
 /// Common floating point constants
 pub trait FloatConst {
   fn pi() -> Self;
@@ -67,3 +71,118 @@ pub trait TappableDelayLine<T: Float> {
   /// input.
   fn add_to(&mut self, value: T, tap_delay: usize) -> T;
 }
+
+/// A delay line that can be tapped between samples.
+pub trait FractionalDelayLine<T: Float> {
+  /// Returns the value at `tap_delay` samples from the current delay-line
+  /// input, interpolated between the samples around it.
+  fn tap_out_fractional(&self, tap_delay: f32) -> T;
+}
+
+/// A delay line whose delay can change on every sample.
+pub trait ModulatedDelayLine<T: Float> {
+  /// Processes and stores input sample into memory, and outputs the sample
+  /// at `delay` samples, interpolated between samples.
+  fn process_modulated(&mut self, sample: T, delay: T) -> T;
+
+  /// Processes a contiguous sequence of samples, calling
+  /// `process_modulated()` on each sample with its delay from `delays`.
+  fn process_modulated_block(&mut self, samples: &mut [T], delays: &[T]) -> T {
+    debug_assert_eq!(samples.len(), delays.len());
+    for (sample, delay) in samples.iter_mut().zip(delays.iter()) {
+      *sample = self.process_modulated(*sample, *delay);
+    }
+    *samples.last().unwrap()
+  }
+}
+
+/// A filter with a known frequency response.
+///
+/// Frequencies are in hertz, and phases are in radians.
+pub trait FrequencyResponse<T: Float + FloatConst> {
+  /// Returns the complex response, `H(e^jw)`, at `frequency`.
+  fn response(&self, frequency: T, sample_rate: T) -> Complex<T>;
+
+  /// Returns the magnitude response at `frequency`, as a ratio.
+  fn magnitude(&self, frequency: T, sample_rate: T) -> T {
+    self.response(frequency, sample_rate).norm()
+  }
+
+  /// Returns the magnitude response at `frequency`, in dB.
+  fn magnitude_db(&self, frequency: T, sample_rate: T) -> T {
+    util::to_db(self.magnitude(frequency, sample_rate))
+  }
+
+  /// Returns the wrapped phase response at `frequency`, in `[-pi, pi]`.
+  fn phase(&self, frequency: T, sample_rate: T) -> T {
+    self.response(frequency, sample_rate).arg()
+  }
+
+  /// Returns the group delay at `frequency`, in samples.
+  ///
+  /// By default, this is the numerical derivative of the phase response,
+  /// with a step of the square root of the epsilon of `T`, relative to the
+  /// sample rate, which balances the rounding of the phase against the
+  /// curvature of the response. Filters with known coefficients should
+  /// override it with the exact group delay.
+  fn group_delay(&self, frequency: T, sample_rate: T) -> T {
+    let delta = sample_rate * T::epsilon().sqrt();
+    let omega = T::two() * T::pi() * delta / sample_rate;
+    let upper = self.response(frequency + delta, sample_rate);
+    let lower = self.response(frequency - delta, sample_rate);
+    -(upper / lower).arg() / (T::two() * omega)
+  }
+
+  /// Evaluates the response at `points` log-spaced frequencies, from `start`
+  /// to `end` inclusive.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use rasp::filter::rbj::Peak;
+  /// use rasp::traits::FrequencyResponse;
+  ///
+  /// let mut filter = Peak::new();
+  /// filter.set_coefficients(44_100f32, 1_000f32, 6f32, 0.71f32);
+  ///
+  /// let sweep = filter.sweep(44_100f32, 20f32, 20_000f32, 128);
+  /// for (frequency, gain) in sweep.frequencies.iter().zip(sweep.magnitude_db.iter()) {
+  ///   println!("{} Hz: {} dB", frequency, gain);
+  /// }
+  /// # assert_eq!(sweep.frequencies.len(), 128);
+  /// ```
+  fn sweep(&self, sample_rate: T, start: T, end: T, points: usize) -> Sweep<T> {
+    debug_assert!(start > T::zero() && points > 1);
+    let ratio = (end / start).ln() / num::cast(points - 1).unwrap();
+    let frequencies: Vec<T> = (0..points)
+      .map(|i| start * (ratio * num::cast(i).unwrap()).exp())
+      .collect();
+
+    let mut sweep = Sweep {
+      magnitude_db: Vec::with_capacity(points),
+      phase: Vec::with_capacity(points),
+      group_delay: Vec::with_capacity(points),
+      frequencies: frequencies
+    };
+    for frequency in sweep.frequencies.iter() {
+      let response = self.response(*frequency, sample_rate);
+      sweep.magnitude_db.push(util::to_db(response.norm()));
+      sweep.phase.push(response.arg());
+      sweep.group_delay.push(self.group_delay(*frequency, sample_rate));
+    }
+    util::unwrap_phase(&mut sweep.phase);
+    sweep
+  }
+}
+
+/// A frequency response evaluated over a range of frequencies.
+pub struct Sweep<T> {
+  /// The frequencies of the sweep, in hertz
+  pub frequencies: Vec<T>,
+  /// The magnitude response, in dB
+  pub magnitude_db: Vec<T>,
+  /// The unwrapped phase response, in radians
+  pub phase: Vec<T>,
+  /// The group delay, in samples
+  pub group_delay: Vec<T>
+}
@@ -42,10 +42,37 @@ pub fn apply_gain<T: Float>(sample: T, ratio: T) -> T {
   sample * ratio
 }
 
+/// Unwraps a sequence of phases, in radians, in place.
+///
+/// Jumps between consecutive phases that are greater than `pi` are removed
+/// by adding multiples of `2 * pi`.
+pub fn unwrap_phase<T: Float + FloatConst>(phases: &mut [T]) {
+  let two_pi = T::two() * T::pi();
+  let mut offset = T::zero();
+  let mut previous = match phases.first() {
+    Some(phase) => *phase,
+    None => return
+  };
+
+  for phase in phases.iter_mut().skip(1) {
+    let wrapped = *phase;
+    let jump = wrapped - previous;
+    if jump > T::pi() {
+      offset = offset - two_pi * ((jump + T::pi()) / two_pi).floor();
+    }
+    else if jump < -T::pi() {
+      offset = offset + two_pi * ((T::pi() - jump) / two_pi).floor();
+    }
+    previous = wrapped;
+    *phase = wrapped + offset;
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use std::f32::*;
+  use std::f32::consts::PI;
 
   #[test]
   fn conversion_to_decibels() {
@@ -89,6 +116,18 @@ mod tests {
     assert!((to_db(NEG_INFINITY) - -120f32).abs() < EPSILON);
   }
 
+  #[test]
+  fn unwrapping_phase() {
+    let mut phases = vec![0f32, 3f32, -3f32, -0.5f32, 2.5f32, -2f32];
+    let expected = vec![0f32, 3f32, 2f32 * PI - 3f32, 2f32 * PI - 0.5f32,
+                        2f32 * PI + 2.5f32, 4f32 * PI - 2f32];
+    unwrap_phase(&mut phases);
+    for (actual, expected) in phases.iter().zip(expected.iter()) {
+      println!("{:.6} - {:.6} = {:.6}", expected, actual, expected - actual);
+      assert!((expected - actual).abs() < 1e-6f32);
+    }
+  }
+
   #[test]
   fn conversion_to_samples() {
     /* Below minimum */
@@ -1,4 +1,5 @@
 // Integration tests
+extern crate num;
 extern crate rasp;
 
 #[cfg(test)]
@@ -33,6 +34,39 @@ mod api {
     }
   }
 
+  mod fft {
+    use rasp::fft::{Fft, Istft, RealFft, Stft};
+    use rasp::window::Window;
+
+    #[test]
+    fn fft() {
+      let mut fft = Fft::new(12);
+      let mut buffer = vec![num::Complex::new(0f32, 0f32); 12];
+      buffer[0].re = 1f32;
+      fft.forward(&mut buffer);
+      assert!(buffer.iter().all(|x| (x.re - 1f32).abs() < 1e-6f32));
+    }
+
+    #[test]
+    fn real_fft() {
+      let mut fft = RealFft::new(16);
+      let mut spectrum = vec![num::Complex::new(0f64, 0f64); fft.spectrum_size()];
+      fft.forward(&[1f64; 16], &mut spectrum);
+      assert_eq!(spectrum[0].re, 16f64);
+    }
+
+    #[test]
+    fn stft() {
+      let mut stft = Stft::new(16, 4, Window::Hann);
+      let mut istft = Istft::new(16, 4, Window::Hann);
+      let mut output = vec![0f32; 64];
+      stft.process_block(&[1f32; 64], |spectrum| istft.add_frame(spectrum));
+      istft.read_block(&mut output);
+      assert_eq!(istft.latency(), 15);
+      assert!((output[15] - 1f32).abs() < 1e-5f32);
+    }
+  }
+
   mod filter {
     use std::f32::EPSILON;
     use rasp::traits::Processor;
@@ -105,6 +139,25 @@ mod api {
         assert!(filter.process(0.1f32) != 0.1f32);
       }
 
+      #[test]
+      fn glide() {
+        let mut filter = Peak::new();
+        filter.set_glide_length(64);
+        filter.set_coefficients(44_100f32, 1_000f32, 0f32, 0.71f32);
+        for _ in 0..2_000 {
+          filter.process(0.1f32);
+        }
+        filter.set_coefficients(44_100f32, 4_000f32, 12f32, 2f32);
+        assert!(filter.is_gliding());
+        let mut previous = filter.last_out();
+        for _ in 0..64 {
+          let output = filter.process(0.1f32);
+          assert!((output - previous).abs() < 0.01f32);
+          previous = output;
+        }
+        assert!(!filter.is_gliding());
+      }
+
       #[test]
       fn highpass() {
         let mut filter = HighPass::new();
@@ -161,14 +214,263 @@ mod api {
         assert!(filter.process(0.1f32) != 0.1f32);
       }
     }
+
+    #[cfg(test)]
+    mod response {
+      use num::Complex;
+      use rasp::traits::FrequencyResponse;
+      use rasp::filter::OnePole;
+      use rasp::filter::rbj::Peak;
+      use std::f32::consts::PI;
+
+      #[test]
+      fn one_pole() {
+        let filter = OnePole::<f32>::new();
+        assert!(filter.magnitude_db(1_000f32, 44_100f32).abs() < 1e-6f32);
+        assert!(filter.group_delay(1_000f32, 44_100f32).abs() < 1e-6f32);
+      }
+
+      #[test]
+      fn peak() {
+        let mut filter = Peak::new();
+        filter.set_coefficients(44_100f64, 1_000f64, 6f64, 0.71f64);
+        assert!((filter.magnitude_db(1_000f64, 44_100f64) - 6f64).abs() < 1e-9);
+
+        let sweep = filter.sweep(44_100f64, 20f64, 20_000f64, 64);
+        assert_eq!(sweep.magnitude_db.len(), 64);
+      }
+
+      /// A one-pole low-pass, with only its response given.
+      struct Leaky(f32);
+
+      impl FrequencyResponse<f32> for Leaky {
+        fn response(&self, frequency: f32, sample_rate: f32) -> Complex<f32> {
+          let w = 2f32 * PI * frequency / sample_rate;
+          Complex::new(1f32, 0f32) / Complex::new(1f32 - self.0 * w.cos(), self.0 * w.sin())
+        }
+      }
+
+      #[test]
+      fn numerical_group_delay() {
+        let filter = Leaky(0.9f32);
+        for frequency in [100f32, 1_000f32, 10_000f32].iter() {
+          let w = 2f64 * ::std::f64::consts::PI * *frequency as f64 / 44_100f64;
+          let expected = (0.9f64 * w.cos() - 0.81f64) / (1.81f64 - 1.8f64 * w.cos());
+          let actual = filter.group_delay(*frequency, 44_100f32) as f64;
+          println!("{} {:.6} - {:.6} = {:.6}", frequency, expected, actual, expected - actual);
+          assert!((expected - actual).abs() < 1e-3 * expected.abs().max(1f64));
+        }
+      }
+    }
+
+    mod svf {
+      use rasp::traits::Processor;
+      use rasp::filter::{Svf, SvfMode};
+
+      #[test]
+      fn svf() {
+        let mut filter = Svf::new();
+        assert!((filter.process(0.1f32) - 0.1f32).abs() < 1e-6f32);
+
+        filter.set_mode(SvfMode::LowPass);
+        filter.set_coefficients(44_100f32, 1_000f32, 0.71f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+        assert!(filter.process_all(0.1f32).highpass != 0.1f32);
+      }
+    }
+
+    mod ladder {
+      use rasp::traits::Processor;
+      use rasp::filter::ladder::{DiodeLadder, Moog, SallenKey};
+
+      #[test]
+      fn moog() {
+        let mut filter = Moog::new();
+        filter.set_coefficients(44_100f32, 1_000f32, 0.5f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn diode_ladder() {
+        let mut filter = DiodeLadder::new();
+        filter.set_coefficients(44_100f32, 1_000f32, 0.5f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn sallen_key() {
+        let mut filter = SallenKey::new();
+        filter.set_coefficients(44_100f32, 1_000f32, 0.5f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+    }
+
+    mod windowed_sinc {
+      use rasp::traits::Processor;
+      use rasp::filter::windowed_sinc;
+      use rasp::window::Window;
+
+      #[test]
+      fn lowpass() {
+        let mut filter = windowed_sinc::lowpass(31, 44_100f32, 1_000f32, Window::Hamming);
+        assert_eq!(filter.latency(), 15f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn bandstop() {
+        let mut filter = windowed_sinc::bandstop(31, 44_100f32, 500f32, 2_000f32, Window::Hann);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+    }
+
+    mod optimal {
+      use rasp::traits::Processor;
+      use rasp::filter::Fir;
+      use rasp::filter::optimal::{self, Band};
+
+      #[test]
+      fn remez() {
+        let bands = [
+          Band::new(0f32, 2_000f32, 1f32, 1f32),
+          Band::new(4_000f32, 22_050f32, 0f32, 10f32)
+        ];
+        let mut filter = Fir::new(optimal::remez(31, 44_100f32, &bands));
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn least_squares() {
+        let bands = [
+          Band::new(0f64, 4_000f64, 0f64, 1f64),
+          Band::new(6_000f64, 22_050f64, 1f64, 1f64)
+        ];
+        let mut filter = Fir::new(optimal::least_squares(31, 44_100f64, &bands));
+        assert!(filter.process(0.1f64) != 0.1f64);
+      }
+    }
+
+    mod convolver {
+      use rasp::traits::Processor;
+      use rasp::filter::Convolver;
+
+      #[test]
+      fn convolver() {
+        let mut convolver = Convolver::new(&[0.5f32; 100], 16);
+        assert_eq!(convolver.process(1f32), 0f32);
+        convolver.set_impulse_response(&[0.25f32; 10], 32);
+        assert!(convolver.is_crossfading());
+      }
+    }
+
+    mod butterworth {
+      use rasp::traits::Processor;
+      use rasp::filter::butterworth;
+
+      #[test]
+      fn lowpass() {
+        let mut filter = butterworth::lowpass(4, 44_100f32, 12_000f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn highpass() {
+        let mut filter = butterworth::highpass(4, 44_100f32, 12_000f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn bandpass() {
+        let mut filter = butterworth::bandpass(4, 44_100f32, 1_000f32, 12_000f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+
+      #[test]
+      fn bandstop() {
+        let mut filter = butterworth::bandstop(4, 44_100f32, 1_000f32, 12_000f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+    }
+
+    #[cfg(test)]
+    mod chebyshev {
+      use rasp::traits::Processor;
+      use rasp::filter::{chebyshev1, chebyshev2};
+
+      #[test]
+      fn chebyshev1() {
+        let mut filter = chebyshev1::lowpass(4, 44_100f32, 12_000f32, 1f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+        let mut filter = chebyshev1::bandstop(4, 44_100f64, 1_000f64, 12_000f64, 1f64);
+        assert!(filter.process(0.1f64) != 0.1f64);
+      }
+
+      #[test]
+      fn chebyshev2() {
+        let mut filter = chebyshev2::highpass(4, 44_100f32, 12_000f32, 40f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+        let mut filter = chebyshev2::bandpass(4, 44_100f64, 1_000f64, 12_000f64, 40f64);
+        assert!(filter.process(0.1f64) != 0.1f64);
+      }
+    }
+
+    #[cfg(test)]
+    mod elliptic {
+      use rasp::traits::Processor;
+      use rasp::filter::elliptic;
+
+      #[test]
+      fn elliptic() {
+        let order = elliptic::estimate_order(44_100f32, 12_000f32, 14_000f32, 1f32, 60f32);
+        let mut filter = elliptic::lowpass(order, 44_100f32, 12_000f32, 1f32, 60f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+    }
+
+    #[cfg(test)]
+    mod bessel {
+      use rasp::traits::Processor;
+      use rasp::filter::bessel::{self, Normalization};
+
+      #[test]
+      fn bessel() {
+        let normalizations = vec![
+          Normalization::Phase,
+          Normalization::Delay,
+          Normalization::Magnitude
+        ];
+        for normalization in normalizations.into_iter() {
+          let mut filter = bessel::lowpass(4, 44_100f32, 12_000f32, normalization);
+          assert!(filter.process(0.1f32) != 0.1f32);
+        }
+      }
+    }
+
+    #[cfg(test)]
+    mod legendre {
+      use rasp::traits::Processor;
+      use rasp::filter::legendre;
+
+      #[test]
+      fn legendre() {
+        let mut filter = legendre::lowpass(4, 44_100f32, 12_000f32);
+        assert!(filter.process(0.1f32) != 0.1f32);
+      }
+    }
   }
 
   mod delay {
     use std::f32::EPSILON;
-    use rasp::traits::Processor;
+    use rasp::traits::{FractionalDelayLine, ModulatedDelayLine, Processor};
     use rasp::delay::{
+      AllpassDelay,
       Delay,
-      LinearDelay
+      Interpolation,
+      InterpolatingDelay,
+      LinearDelay,
+      MultiTapDelay,
+      Pattern,
+      Tap
     };
 
     #[test]
@@ -186,6 +488,246 @@ mod api {
       assert!((delay.process(1f32) - 0f32).abs() < EPSILON );
       assert!((delay.process(0f32) - 1f32).abs() < EPSILON );
     }
+
+    #[test]
+    fn allpass_delay() {
+      // Single sample delay
+      let mut delay = AllpassDelay::new(1f32, 4);
+      assert!((delay.process(1f32) - 0f32).abs() < EPSILON );
+      assert!((delay.process(0f32) - 1f32).abs() < EPSILON );
+    }
+
+    #[test]
+    fn interpolating_delay() {
+      // Single sample delay, tapped between samples
+      let mut delay = InterpolatingDelay::new(1f32, 4, Interpolation::Hermite);
+      assert!((delay.process(1f32) - 0f32).abs() < EPSILON );
+      assert!((delay.process(0f32) - 1f32).abs() < EPSILON );
+      assert!(delay.tap_out_fractional(1.5f32) > 0f32);
+    }
+
+    #[test]
+    fn multi_tap_delay() {
+      let mut delay = MultiTapDelay::new(8, Interpolation::Linear);
+      delay.add_tap(Tap::new(1f32, 0.5f32, 0f32, 0f32));
+      assert!((delay.process(1f32) - 0f32).abs() < EPSILON );
+      assert!((delay.process(0f32) - 0.5f32).abs() < EPSILON );
+
+      let pattern: Pattern<f32> = "1 0.5 0 0".parse().unwrap();
+      assert_eq!(delay.pattern(), pattern);
+    }
+
+    #[test]
+    fn modulated_delay() {
+      let mut delay = InterpolatingDelay::new(2f32, 64, Interpolation::Lagrange3);
+      let mut samples = [1f64, 0f64, 0f64, 0f64];
+      delay.process_modulated_block(&mut samples, &[2f64, 1.5f64, 1f64, 0.5f64]);
+      assert!((samples[2] - 0f64).abs() < 1e-9);
+    }
+  }
+
+  mod effects {
+    use rasp::effects::{Chorus, Division, Flanger, Phaser, PingPongDelay, PitchShifter, TapeEcho, Waveform};
+    use rasp::traits::Processor;
+    use rasp::window::Window;
+
+    #[test]
+    fn chorus() {
+      let mut chorus = Chorus::new(4, 44_100f32);
+      chorus.set_voices(2);
+      chorus.set_delay(0.02f32);
+      chorus.set_depth(0.002f32);
+      chorus.set_rate(1.2f32);
+      chorus.set_waveform(Waveform::Triangle);
+      chorus.set_mix(0.3f32);
+      let mut samples: Vec<f32> = (0..2_000).map(|n| (n as f32 * 0.1f32).sin()).collect();
+      chorus.process_block(&mut samples);
+      let (left, right) = chorus.process_stereo(0.5f32);
+      assert!(left != right);
+      assert_eq!(chorus.voices(), 2);
+      chorus.clear();
+      assert_eq!(chorus.last_out(), 0f32);
+    }
+
+    #[test]
+    fn flanger() {
+      let mut flanger = Flanger::new(48_000f64);
+      flanger.set_delay(0.001f64);
+      flanger.set_depth(0.001f64);
+      flanger.set_rate(0.5f64);
+      flanger.set_waveform(Waveform::Sine);
+      flanger.set_feedback(0.5f64);
+      flanger.set_mix(0.7f64);
+      flanger.set_through_zero(true);
+      let mut samples = [0.5f64; 2_000];
+      flanger.process_block(&mut samples);
+      assert!(flanger.is_through_zero());
+      flanger.clear();
+      assert_eq!(flanger.last_out(), 0f64);
+    }
+
+    #[test]
+    fn phaser() {
+      let mut phaser = Phaser::new(4, 48_000f32);
+      phaser.set_stages(8);
+      phaser.set_range(100f32, 2_000f32);
+      phaser.set_rate(0.2f32);
+      phaser.set_waveform(Waveform::Triangle);
+      phaser.set_feedback(-0.5f32);
+      phaser.set_mix(0.5f32);
+      let mut samples = [0.5f32; 2_000];
+      phaser.process_block(&mut samples);
+      assert_eq!(phaser.stages(), 8);
+      phaser.clear();
+      assert_eq!(phaser.last_out(), 0f32);
+    }
+
+    #[test]
+    fn ping_pong_delay() {
+      let mut delay = PingPongDelay::new(2f32, 44_100f32);
+      delay.set_tempo(100f32, Division::TripletEighth);
+      delay.set_glide(0.1f32);
+      delay.set_feedback(0.4f32);
+      delay.set_mix(0.5f32);
+      let mut samples = [0.5f32; 20_000];
+      delay.process_block(&mut samples);
+      delay.set_time(0.1f32);
+      let (left, right) = delay.process_stereo(0.5f32);
+      assert!(left != right);
+      assert!(delay.time() > 0.1f32);
+      delay.clear();
+      assert_eq!(delay.last_out(), 0f32);
+    }
+
+    #[test]
+    fn pitch_shifter() {
+      let mut shifter = PitchShifter::new(3, 44_100f32);
+      shifter.set_pitch(7f32, 10f32);
+      shifter.set_grain_size(0.04f32);
+      shifter.set_window(Window::Triangular);
+      shifter.set_splice(true);
+      shifter.set_mix(0.8f32);
+      let mut samples: Vec<f32> = (0..4_000).map(|n| (n as f32 * 0.05f32).sin()).collect();
+      shifter.process_block(&mut samples);
+      assert!(samples.iter().all(|x| x.abs() < 2f32));
+      assert_eq!(shifter.heads(), 3);
+      assert_eq!(shifter.window(), Window::Triangular);
+      shifter.clear();
+      assert_eq!(shifter.last_out(), 0f32);
+    }
+
+    #[test]
+    fn tape_echo() {
+      let mut echo = TapeEcho::new(1f64, 48_000f64);
+      echo.set_time(0.02f64);
+      echo.set_glide(0.05f64);
+      echo.set_wow(0.001f64, 0.7f64);
+      echo.set_flutter(0.000_1f64, 5f64);
+      echo.set_drive(3f64);
+      echo.set_tone(200f64, 2_500f64);
+      echo.set_feedback(1.1f64);
+      echo.set_mix(0.4f64);
+      let mut samples = [0.5f64; 4_800];
+      echo.process_block(&mut samples);
+      assert!(samples.iter().all(|x| x.abs() < 1f64));
+      echo.clear();
+      assert_eq!(echo.last_out(), 0f64);
+    }
+  }
+
+  mod resample {
+    use rasp::filter::Fir;
+    use rasp::resample::{HalfBand, Oversampled, Polyphase, Quality, SincResampler};
+    use rasp::traits::Processor;
+
+    #[test]
+    fn polyphase() {
+      let mut resampler = Polyphase::new(48_000, 44_100, Quality::Low);
+      let mut output = Vec::new();
+      resampler.process(&[0.1f32; 147], &mut output);
+      assert_eq!(output.len(), 160);
+      assert!(resampler.group_delay() > 0f32);
+    }
+
+    #[test]
+    fn sinc_resampler() {
+      let mut resampler = SincResampler::new(0.5f64, Quality::Low);
+      let mut output = Vec::new();
+      resampler.process(&[0.1f32; 100], &mut output);
+      resampler.set_ratio(0.51f64);
+      resampler.process(&[0.1f32; 100], &mut output);
+      assert!(output.len() > 90);
+    }
+
+    #[test]
+    fn oversampled() {
+      for half_band in [HalfBand::Iir, HalfBand::Fir].iter() {
+        let mut oversampled = Oversampled::new(Fir::new(vec![0.5f32]), 16, *half_band);
+        oversampled.process(1f32);
+        oversampled.processor_mut().set_coefficients(&[1f32]);
+        oversampled.clear();
+        assert_eq!(oversampled.last_out(), 0f32);
+        assert!(oversampled.latency() > 0f32);
+      }
+    }
+  }
+
+  mod reverb {
+    use rasp::reverb::{Dattorro, Fdn, FeedbackMatrix, Freeverb};
+    use rasp::traits::Processor;
+
+    #[test]
+    fn fdn() {
+      for lines in [4, 8, 16].iter() {
+        let mut reverb = Fdn::new(*lines, FeedbackMatrix::Householder, 44_100f32);
+        reverb.set_matrix(FeedbackMatrix::Hadamard);
+        reverb.set_decay(1.5f32, 1f32, 0.5f32);
+        reverb.set_crossovers(300f32, 3_000f32);
+        reverb.set_diffusion(0.5f32);
+        reverb.set_modulation(0.001f32, 0.3f32);
+        let mut samples = [0.5f32; 4_410];
+        reverb.process_block(&mut samples);
+        let (left, right) = reverb.process_stereo(0f32);
+        assert!(left != right);
+        assert_eq!(reverb.lines(), *lines);
+        reverb.clear();
+        assert_eq!(reverb.last_out(), 0f32);
+      }
+    }
+
+    #[test]
+    fn freeverb() {
+      let mut reverb = Freeverb::new(48_000f64);
+      reverb.set_room_size(0.9f64);
+      reverb.set_damping(0.1f64);
+      reverb.set_width(0.8f64);
+      reverb.set_wet(0.5f64);
+      reverb.set_dry(0.25f64);
+      let mut samples = [0.5f64; 4_800];
+      reverb.process_block(&mut samples);
+      reverb.set_freeze(true);
+      assert!(reverb.process(0f64) != 0f64);
+      reverb.clear();
+      assert_eq!(reverb.process_stereo(0f64), (0f64, 0f64));
+    }
+
+    #[test]
+    fn dattorro() {
+      let mut reverb = Dattorro::new(44_100f32);
+      reverb.set_pre_delay(0.01f32);
+      reverb.set_bandwidth(0.7f32);
+      reverb.set_input_diffusion(0.7f32, 0.5f32);
+      reverb.set_decay(0.8f32);
+      reverb.set_decay_diffusion(0.6f32);
+      reverb.set_damping(0.2f32);
+      reverb.set_modulation(8f32, 0.5f32);
+      let mut samples = [0.5f32; 4_410];
+      reverb.process_block(&mut samples);
+      let (left, right) = reverb.process_stereo(0f32);
+      assert!(left != right);
+      reverb.clear();
+      assert_eq!(reverb.last_out(), 0f32);
+    }
   }
 
   mod util {